@@ -0,0 +1,95 @@
+// benches/codec.rs
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use ggwave_rs::{GGWave, ProtocolId, protocols, sample_formats};
+use std::hint::black_box;
+
+const TEXT: &str = "Hello from the ggwave benchmarks!";
+const VOLUME: i32 = 50;
+const PROTOCOLS: [(&str, ProtocolId); 3] = [
+    ("audible_normal", protocols::AUDIBLE_NORMAL),
+    ("audible_fast", protocols::AUDIBLE_FAST),
+    ("audible_fastest", protocols::AUDIBLE_FASTEST),
+];
+
+fn bench_encode(c: &mut Criterion, gg: &GGWave) {
+    let mut group = c.benchmark_group("encode");
+    group.throughput(Throughput::Bytes(TEXT.len() as u64));
+
+    for (name, protocol) in PROTOCOLS {
+        group.bench_with_input(
+            BenchmarkId::new("encode", name),
+            &protocol,
+            |b, &protocol| b.iter(|| gg.encode(black_box(TEXT), protocol, VOLUME).unwrap()),
+        );
+
+        let size = gg
+            .calculate_encode_buffer_size(TEXT, protocol, VOLUME)
+            .unwrap();
+        let mut buffer = vec![0u8; size];
+        group.bench_with_input(
+            BenchmarkId::new("encode_into_buffer", name),
+            &protocol,
+            |b, &protocol| {
+                b.iter(|| {
+                    gg.encode_into_buffer(black_box(TEXT), protocol, VOLUME, &mut buffer)
+                        .unwrap()
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn bench_decode(c: &mut Criterion, gg: &GGWave) {
+    let mut group = c.benchmark_group("decode");
+    let frame_bytes = gg.parameters().samplesPerFrame as usize * size_of::<f32>();
+    let mut buffer = vec![0u8; 256];
+
+    for (name, protocol) in PROTOCOLS {
+        let waveform = gg.encode(TEXT, protocol, VOLUME).unwrap();
+        assert_eq!(gg.decode(&waveform, &mut buffer).unwrap(), TEXT);
+        group.throughput(Throughput::Bytes(waveform.len() as u64));
+
+        group.bench_with_input(
+            BenchmarkId::new("decode", name),
+            &waveform,
+            |b, waveform| b.iter(|| gg.decode(black_box(waveform), &mut buffer).unwrap().len()),
+        );
+
+        // Fed one frame at a time, as from a capture callback
+        group.bench_with_input(
+            BenchmarkId::new("process_audio_chunk", name),
+            &waveform,
+            |b, waveform| {
+                b.iter(|| {
+                    let mut decoded = 0;
+                    for chunk in black_box(waveform).chunks(frame_bytes) {
+                        if let Some(text) = gg.process_audio_chunk(chunk, &mut buffer).unwrap() {
+                            decoded = text.len();
+                        }
+                    }
+                    assert_eq!(decoded, TEXT.len());
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn codec(c: &mut Criterion) {
+    // ggwave allows at most 4 live instances per process, so every benchmark
+    // shares this one
+    let gg = GGWave::builder()
+        .input_sample_format(sample_formats::F32)
+        .output_sample_format(sample_formats::F32)
+        .build()
+        .expect("Failed to initialize GGWave");
+
+    bench_encode(c, &gg);
+    bench_decode(c, &gg);
+}
+
+criterion_group!(benches, codec);
+criterion_main!(benches);
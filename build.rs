@@ -2,8 +2,35 @@ use std::path::PathBuf;
 use std::process::Command;
 use std::{env, fs};
 
-fn main() {
-    println!("Starting debug build.rs");
+/// Environment variable pointing at a ggwave checkout to build instead of
+/// cloning one
+const SRC_DIR_VAR: &str = "GGWAVE_SRC_DIR";
+
+/// Directory the ggwave repository is cloned into when no checkout is given
+const VENDOR_DIR: &str = "vendors/ggwave";
+
+/// Find the ggwave sources
+///
+/// A checkout named by `GGWAVE_SRC_DIR` is used as-is and never cloned, so
+/// offline builds work. Otherwise the repository is cloned into
+/// `vendors/ggwave` unless a previous build already did. A failed clone is
+/// reported by the caller when it finds the sources missing.
+fn locate_sources() -> PathBuf {
+    if let Some(dir) = env::var_os(SRC_DIR_VAR) {
+        let dir = PathBuf::from(dir);
+        println!(
+            "Using ggwave sources from {}: {}",
+            SRC_DIR_VAR,
+            dir.display()
+        );
+        return dir;
+    }
+
+    let ggwave_dir = PathBuf::from(VENDOR_DIR);
+    if ggwave_dir.exists() {
+        println!("ggwave directory already exists: {}", ggwave_dir.display());
+        return ggwave_dir;
+    }
 
     // Create vendors directory if not exists
     fs::create_dir_all("vendors/").unwrap_or_else(|e| {
@@ -11,48 +38,51 @@ fn main() {
         // Continue anyway
     });
 
-    // Clone ggwave repository if not exists
-    let ggwave_dir = PathBuf::from("vendors/ggwave");
-    if !ggwave_dir.exists() {
-        println!("Cloning ggwave repository...");
-        let status = Command::new("git")
-            .args(&[
-                "clone",
-                "https://github.com/ggerganov/ggwave.git",
-                "--depth=1",
-                "vendors/ggwave",
-            ])
-            .status();
-
-        match status {
-            Ok(exit_status) if exit_status.success() => {
-                println!("Successfully cloned ggwave repository");
-            }
-            Ok(exit_status) => {
-                eprintln!("Failed to clone ggwave repository: {}", exit_status);
-            }
-            Err(e) => {
-                eprintln!("Failed to execute git clone: {}", e);
-            }
+    println!("Cloning ggwave repository...");
+    let status = Command::new("git")
+        .args([
+            "clone",
+            "https://github.com/ggerganov/ggwave.git",
+            "--depth=1",
+            VENDOR_DIR,
+        ])
+        .status();
+
+    match status {
+        Ok(exit_status) if exit_status.success() => {
+            println!("Successfully cloned ggwave repository");
+        }
+        Ok(exit_status) => {
+            eprintln!("Failed to clone ggwave repository: {}", exit_status);
+        }
+        Err(e) => {
+            eprintln!("Failed to execute git clone: {}", e);
         }
-    } else {
-        println!("ggwave directory already exists: {}", ggwave_dir.display());
     }
 
+    ggwave_dir
+}
+
+fn main() {
+    println!("Starting debug build.rs");
+
+    let ggwave_dir = locate_sources();
+
     // Check that the required files exist
     let header_path = ggwave_dir.join("include/ggwave/ggwave.h");
     let source_path = ggwave_dir.join("src/ggwave.cpp");
 
-    if !header_path.exists() {
-        eprintln!("ERROR: Header file not found: {}", header_path.display());
-    } else {
-        println!("Found header file: {}", header_path.display());
-    }
-
-    if !source_path.exists() {
-        eprintln!("ERROR: Source file not found: {}", source_path.display());
-    } else {
-        println!("Found source file: {}", source_path.display());
+    for path in [&header_path, &source_path] {
+        if !path.exists() {
+            panic!(
+                "ggwave source file not found: {}\n\
+                 Download ggwave (https://github.com/ggerganov/ggwave) and set \
+                 {}=/path/to/ggwave to build without network access",
+                path.display(),
+                SRC_DIR_VAR
+            );
+        }
+        println!("Found ggwave file: {}", path.display());
     }
 
     // Get compiler flags
@@ -69,8 +99,9 @@ fn main() {
 
     compiler
         .cpp(true)
-        .file("vendors/ggwave/src/ggwave.cpp")
-        .include("vendors/ggwave/include")
+        .file(&source_path)
+        .file("src/ggwave_ext.cpp")
+        .include(ggwave_dir.join("include"))
         .define("GGWAVE_SHARED", None) // Build with GGWAVE_SHARED defined
         .flag_if_supported("-std=c++11")
         .warnings(true) // Enable warnings to see potential issues
@@ -107,6 +138,8 @@ fn main() {
         .allowlist_function("ggwave_.*")
         .allowlist_var("GGWAVE_.*")
         .derive_default(true)
+        // Keep the bindings usable without std
+        .use_core()
         .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()));
 
     let bindings = match bindings_builder.generate() {
@@ -133,8 +166,10 @@ fn main() {
 
     // Make sure we rebuild if the header changes
     println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed={}", SRC_DIR_VAR);
     println!("cargo:rerun-if-changed={}", header_path.to_string_lossy());
     println!("cargo:rerun-if-changed={}", source_path.to_string_lossy());
+    println!("cargo:rerun-if-changed=src/ggwave_ext.cpp");
 
     println!("build.rs completed successfully");
 }
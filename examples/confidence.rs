@@ -0,0 +1,74 @@
+// examples/confidence.rs
+use ggwave_rs::{GGWave, Result, protocols, sample_formats};
+
+const NOISE_LEVELS: [f32; 4] = [0.0, 0.1, 0.3, 0.6];
+
+fn main() -> Result<()> {
+    let gg = GGWave::builder()
+        .input_sample_format(sample_formats::F32)
+        .build()?;
+
+    // Decode a mono recording at the input sample rate if one is given,
+    // otherwise simulate the same message sent over noisier and noisier channels
+    let samples = match std::env::args().nth(1) {
+        Some(path) => read_wav(&path)?,
+        None => simulate(&gg)?,
+    };
+
+    let waveform: Vec<u8> = samples.iter().flat_map(|s| s.to_ne_bytes()).collect();
+    let messages = gg.decode_all(&waveform, 256)?;
+    if messages.is_empty() {
+        println!("No message received");
+    }
+
+    let rate = gg.parameters().sampleRateInp;
+    for message in messages {
+        println!(
+            "{:>6.2}s  {:<16} confidence {:.2}  {:?}",
+            message.offset as f32 / rate,
+            protocols::name(message.protocol),
+            message.confidence,
+            message.text
+        );
+    }
+
+    Ok(())
+}
+
+fn simulate(gg: &GGWave) -> Result<Vec<f32>> {
+    let mut seed = 0x1234_5678u32;
+    let mut samples = Vec::new();
+
+    for (i, level) in NOISE_LEVELS.into_iter().enumerate() {
+        let message =
+            gg.encode_normalized(&format!("Noise level {}", i), protocols::AUDIBLE_FAST, 0.5)?;
+        // Half a second of silence between messages
+        samples.extend(std::iter::repeat_n(0.0, 24000));
+        samples.extend(message.iter().map(|s| {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            s + ((seed >> 8) as f32 / (1u32 << 24) as f32 - 0.5) * level
+        }));
+    }
+    samples.extend(std::iter::repeat_n(0.0, 24000));
+
+    Ok(samples)
+}
+
+fn read_wav(path: &str) -> Result<Vec<f32>> {
+    let mut reader = hound::WavReader::open(path)?;
+    let spec = reader.spec();
+
+    let samples = match spec.sample_format {
+        hound::SampleFormat::Float => reader
+            .samples::<f32>()
+            .collect::<std::result::Result<_, _>>()?,
+        hound::SampleFormat::Int => {
+            let scale = (1u32 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|s| s as f32 / scale))
+                .collect::<std::result::Result<_, _>>()?
+        }
+    };
+    Ok(samples)
+}
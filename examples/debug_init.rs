@@ -1,5 +1,5 @@
 // examples/debug_init.rs
-use ggwave_rs::{GGWave, Parameters, operating_modes, sample_formats};
+use ggwave_rs::{GGWave, Parameters, describe_parameters, operating_modes, sample_formats};
 use std::io::Write;
 use std::path::PathBuf;
 use std::process::Command;
@@ -49,14 +49,7 @@ fn main() {
         operating_modes::RX_AND_TX,
         operating_modes::TX_ONLY_TONES,
     ] {
-        let mode_str = match mode {
-            m if m == operating_modes::RX => "RX",
-            m if m == operating_modes::TX => "TX",
-            m if m == operating_modes::RX_AND_TX => "RX_AND_TX",
-            m if m == operating_modes::TX_ONLY_TONES => "TX_ONLY_TONES",
-            _ => "UNKNOWN",
-        };
-
+        let mode_str = operating_modes::describe(mode);
         println!("Trying with mode: {}", mode_str);
 
         let mut params = GGWave::default_parameters();
@@ -93,15 +86,9 @@ fn main() {
 }
 
 fn print_parameters(params: &Parameters) {
-    println!("  payloadLength:        {}", params.payloadLength);
-    println!("  sampleRateInp:        {}", params.sampleRateInp);
-    println!("  sampleRateOut:        {}", params.sampleRateOut);
-    println!("  sampleRate:           {}", params.sampleRate);
-    println!("  samplesPerFrame:      {}", params.samplesPerFrame);
-    println!("  soundMarkerThreshold: {}", params.soundMarkerThreshold);
-    println!("  sampleFormatInp:      {}", params.sampleFormatInp);
-    println!("  sampleFormatOut:      {}", params.sampleFormatOut);
-    println!("  operatingMode:        {}", params.operatingMode);
+    for line in describe_parameters(params).lines() {
+        println!("  {}", line);
+    }
 }
 
 fn check_build_environment() {
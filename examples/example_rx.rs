@@ -1,7 +1,7 @@
 // examples/example_rx.rs
 use anyhow::Result;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use ggwave_rs::{GGWave, operating_modes, sample_formats};
+use ggwave_rs::{Error, GGWaveBuilder};
 use hound::{WavSpec, WavWriter};
 use std::io::Write;
 use std::sync::Mutex;
@@ -13,7 +13,6 @@ use std::{thread, time::Duration};
 
 const SAMPLE_RATE: u32 = 48000;
 const CHANNELS: usize = 1;
-const BUFFER_SIZE: usize = SAMPLE_RATE as usize * 10; // 10 seconds of audio at 48kHz
 const PROCESS_FRAMES: i32 = 1024; // Process 1024 samples at a time (matches C++ implementation)
 
 fn main() -> Result<()> {
@@ -43,15 +42,7 @@ fn main() -> Result<()> {
     }
 
     // Create a GGWave instance configured similarly to the C++ implementation
-    let ggwave = match GGWave::builder()
-        .sample_rate(SAMPLE_RATE as f32)
-        .samples_per_frame(PROCESS_FRAMES)
-        .sound_marker_threshold(0.5)
-        .input_sample_format(sample_formats::F32)
-        .output_sample_format(sample_formats::I16)
-        .operating_mode(operating_modes::RX_AND_TX)
-        .build()
-    {
+    let ggwave = match GGWaveBuilder::rx_preset().build() {
         Ok(instance) => {
             println!("GGWave instance created successfully");
             instance
@@ -65,6 +56,10 @@ fn main() -> Result<()> {
     // Enable all reception protocols (matching C++ implementation)
     ggwave.enable_all_rx_protocols();
 
+    // Enough audio for the longest message the decoder can receive
+    let buffer_size =
+        ggwave.max_rx_duration_frames() * ggwave.parameters().samplesPerFrame as usize;
+
     // Set up audio capture
     let host = cpal::default_host();
 
@@ -141,10 +136,14 @@ fn main() -> Result<()> {
     println!("    - Format:            {}", "f32");
     println!("    - Channels:          {}", CHANNELS);
     println!("    - Samples per frame: {}", PROCESS_FRAMES);
+    println!(
+        "    - Max rx duration:   {:.2}s",
+        buffer_size as f32 / ggwave.parameters().sampleRateInp
+    );
     println!("    - Debug mode:        {}", if debug_mode { "enabled" } else { "disabled" });
 
     // Create audio processing buffer (circular buffer like the C++ impl)
-    let audio_buffer = Arc::new(Mutex::new(Vec::<f32>::with_capacity(BUFFER_SIZE)));
+    let audio_buffer = Arc::new(Mutex::new(Vec::<f32>::with_capacity(buffer_size)));
     let audio_buffer_clone = audio_buffer.clone();
 
     // Create a buffer for recording samples to WAV file
@@ -216,8 +215,8 @@ fn main() -> Result<()> {
             audio_buf.extend_from_slice(data);
 
             // Keep buffer at a reasonable size
-            if audio_buf.len() > BUFFER_SIZE {
-                let excess = audio_buf.len() - BUFFER_SIZE;
+            if audio_buf.len() > buffer_size {
+                let excess = audio_buf.len() - buffer_size;
                 audio_buf.drain(0..excess);
             }
 
@@ -308,9 +307,6 @@ fn main() -> Result<()> {
     // Decode buffer - matching kMaxDataSize in C++ implementation
     let mut decode_buffer = vec![0u8; 256];
 
-    // Create buffer for raw audio data
-    let mut raw_audio = vec![0u8; (PROCESS_FRAMES as usize) * 4]; // 4 bytes per f32 sample
-
     // Main processing loop - structured similar to the C++ GGWave_mainLoop function
     let mut dot_timer = std::time::Instant::now();
 
@@ -342,15 +338,8 @@ fn main() -> Result<()> {
             data
         };
 
-        // Convert f32 samples to bytes for processing
-        // This mimics how C++ handles the audio buffer conversion
-        for (i, sample) in audio_data.iter().enumerate() {
-            let bytes = sample.to_le_bytes();
-            raw_audio[i * 4..(i + 1) * 4].copy_from_slice(&bytes);
-        }
-
         // Process the audio with ggwave (similar to the C++ g_ggWave->decode call)
-        match ggwave.decode(&raw_audio, &mut decode_buffer) {
+        match ggwave.decode_f32(&audio_data, &mut decode_buffer) {
             Ok(decoded_text) if !decoded_text.is_empty() => {
                 println!("\n✅ Message received: \"{}\"", decoded_text);
 
@@ -359,10 +348,11 @@ fn main() -> Result<()> {
                     save_wav_snapshot(&recording_buffer, "message_detected.wav", true)?;
                 }
             }
+            // Most chunks hold no message yet
+            Ok(_) | Err(Error::NoMessage) => {}
             Err(e) => {
                 eprintln!("Error decoding audio: {:?}", e);
             }
-            _ => {}
         }
 
         // Show activity indicator (similar to simple progress output in other ggwave examples)
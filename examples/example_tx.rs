@@ -19,7 +19,6 @@ use std::{
 const SAMPLE_RATE: u32 = 48000;
 const CHANNELS: usize = 1;
 const BUFFER_SIZE: usize = SAMPLE_RATE as usize * 5; // 5 seconds of audio
-const MAX_MESSAGE_SIZE: usize = 140; // Maximum message size for variable encoding
 
 fn main() -> Result<()> {
     println!("GGWave Optimized Transmitter");
@@ -57,10 +56,6 @@ fn main() -> Result<()> {
     let playing = Arc::new(AtomicBool::new(false));
     let playing_clone = playing.clone();
 
-    // Buffer for encoded waveform, shared between threads
-    let waveform_buffer = Arc::new(Mutex::new(Vec::new()));
-    let waveform_clone = waveform_buffer.clone();
-
     // Start audio playback stream
     let stream = device.build_output_stream(
         &config,
@@ -141,45 +136,41 @@ fn main() -> Result<()> {
             break;
         }
 
-        if input.len() > MAX_MESSAGE_SIZE {
+        if !ggwave.can_encode(input) {
             println!(
                 "Message too long! Maximum is {} characters.",
-                MAX_MESSAGE_SIZE
+                ggwave.max_payload_len()
             );
             continue;
         }
 
         // List available protocols
+        let choices = &protocols::all()[..=protocols::ULTRASOUND_FASTEST as usize];
         println!("\nAvailable protocols:");
-        println!("1. AUDIBLE_NORMAL - Standard audible transmission");
-        println!("2. AUDIBLE_FAST - Faster audible transmission");
-        println!("3. AUDIBLE_FASTEST - Fastest audible transmission");
-        println!("4. ULTRASOUND_NORMAL - Standard ultrasound transmission");
-        println!("5. ULTRASOUND_FAST - Faster ultrasound transmission");
-        println!("6. ULTRASOUND_FASTEST - Fastest ultrasound transmission");
-        // Choose protocol
-        println!("Select protocol (1-6):");
+        for (i, &id) in choices.iter().enumerate() {
+            println!("{}. {}", i + 1, protocols::name(id));
+        }
+        // Choose protocol, by number or by name
+        println!("Select protocol (1-{}):", choices.len());
         print!("> ");
         io::stdout().flush()?;
 
         let mut protocol_input = String::new();
         io::stdin().read_line(&mut protocol_input)?;
 
-        let protocol_id = match protocol_input.trim().parse::<u8>() {
-            Ok(1) => protocols::AUDIBLE_NORMAL,
-            Ok(2) => protocols::AUDIBLE_FAST,
-            Ok(3) => protocols::AUDIBLE_FASTEST,
-            Ok(4) => protocols::ULTRASOUND_NORMAL,
-            Ok(5) => protocols::ULTRASOUND_FAST,
-            Ok(6) => protocols::ULTRASOUND_FASTEST,
-            _ => {
-                println!("Invalid selection, using AUDIBLE_NORMAL");
-                protocols::AUDIBLE_NORMAL
-            }
+        let protocol_input = protocol_input.trim();
+        let protocol_id = match protocol_input.parse::<usize>() {
+            Ok(n) if (1..=choices.len()).contains(&n) => Some(choices[n - 1]),
+            _ => protocols::from_name(protocol_input),
         };
+        let protocol_id = protocol_id.unwrap_or_else(|| {
+            println!("Invalid selection, using AUDIBLE_NORMAL");
+            protocols::AUDIBLE_NORMAL
+        });
 
         // Choose volume
-        println!("Volume (1-100, default 50):");
+        let default_volume = protocols::recommended_volume(protocol_id);
+        println!("Volume (1-100, default {}):", default_volume);
         print!("> ");
         io::stdout().flush()?;
 
@@ -188,7 +179,7 @@ fn main() -> Result<()> {
 
         let volume = match volume_input.trim().parse::<i32>() {
             Ok(v) if v > 0 && v <= 100 => v,
-            _ => 50,
+            _ => default_volume,
         };
 
         // Encode the message
@@ -196,16 +187,8 @@ fn main() -> Result<()> {
         let estimated_duration = ggwave.estimate_duration(protocol_id, input.len());
         println!("Estimated duration: {:.2} seconds", estimated_duration);
 
-        match ggwave.encode(input, protocol_id, volume) {
-            Ok(waveform) => {
-                // Store the encoded waveform
-                let mut waveform_guard = waveform_clone.lock().unwrap();
-                *waveform_guard = waveform;
-                drop(waveform_guard);
-
-                // Convert waveform to f32 samples and push to ring buffer
-                let waveform_guard = waveform_clone.lock().unwrap();
-
+        match ggwave.encode_full(input, protocol_id, volume) {
+            Ok(audio) => {
                 // Clear the consumer and producer buffer
                 {
                     let mut consumer_guard = consumer.lock().unwrap();
@@ -215,24 +198,13 @@ fn main() -> Result<()> {
                     }
                 }
 
-                // Convert to f32 samples, handling alignment correctly
-                if waveform_guard.len() % 4 != 0 {
-                    println!("Warning: Waveform length is not a multiple of 4 bytes");
-                }
-
-                let bytes = waveform_guard.as_slice();
+                // Push the samples to the ring buffer one chunk at a time
                 let mut producer_guard = producer.lock().unwrap();
-
-                // Process in batches to avoid holding the lock too long
-                for chunk in bytes.chunks(4) {
-                    if chunk.len() == 4 {
-                        let sample = f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
-                        if producer_guard.is_full() {
-                            // If buffer is full, break - we can't store more
-                            println!("Warning: Buffer full, some audio may be truncated");
-                            break;
-                        }
-                        let _ = producer_guard.try_push(sample);
+                for chunk in audio.chunks_f32(1024)? {
+                    if producer_guard.push_slice(&chunk) < chunk.len() {
+                        // If buffer is full, break - we can't store more
+                        println!("Warning: Buffer full, some audio may be truncated");
+                        break;
                     }
                 }
 
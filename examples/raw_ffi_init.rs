@@ -1,5 +1,5 @@
 // examples/raw_ffi_init.rs
-use ggwave_rs::ffi;
+use ggwave_rs::{describe_parameters, ffi};
 use std::mem;
 
 fn main() {
@@ -11,9 +11,7 @@ fn main() {
         let params = ffi::ggwave_getDefaultParameters();
 
         println!("Default parameters obtained:");
-        println!("  sampleRate:      {}", params.sampleRate);
-        println!("  samplesPerFrame: {}", params.samplesPerFrame);
-        println!("  operatingMode:   {}", params.operatingMode);
+        println!("{}", describe_parameters(&params));
 
         // Attempt to initialize with default parameters
         println!("\nAttempting initialization with default parameters...");
@@ -32,9 +30,7 @@ fn main() {
             min_params.soundMarkerThreshold = 0.1;
             min_params.operatingMode = ffi::GGWAVE_OPERATING_MODE_TX as i32;
 
-            println!("  sampleRate:      {}", min_params.sampleRate);
-            println!("  samplesPerFrame: {}", min_params.samplesPerFrame);
-            println!("  operatingMode:   {}", min_params.operatingMode);
+            println!("{}", describe_parameters(&min_params));
 
             let min_instance = ffi::ggwave_init(min_params);
             if min_instance <= 0 {
@@ -0,0 +1,44 @@
+// examples/spectrum.rs
+use ggwave_rs::{GGWave, Result, protocols};
+
+const BAR_WIDTH: f32 = 60.0;
+
+fn main() -> Result<()> {
+    let gg = GGWave::new()?;
+    let protocol = protocols::AUDIBLE_FAST;
+    let text = "Hello from Rust GGWave!";
+
+    // Encode a message and look at one frame in the middle of it
+    let samples = gg.encode_normalized(text, protocol, 1.0)?;
+    let spf = gg.parameters().samplesPerFrame as usize;
+    let end = (samples.len() / 2 / spf + 1) * spf;
+
+    let Some(spectrum) = gg.frame_spectrum(&samples[..end]) else {
+        println!("The waveform is shorter than one frame");
+        return Ok(());
+    };
+
+    let band = gg.protocol_band(protocol).unwrap_or(0.0..8000.0);
+    println!(
+        "Spectrum of frame {} of {:?} ({} Hz per bin)",
+        end / spf - 1,
+        protocols::name(protocol),
+        spectrum.bin_width()
+    );
+    println!("Protocol band: {:.0}-{:.0} Hz\n", band.start, band.end);
+
+    // Include a few bins on each side of the band
+    let margin = 4.0 * spectrum.bin_width();
+    let peak = spectrum.peak().map_or(1.0, |(_, magnitude)| magnitude.max(1e-6));
+
+    for (frequency, magnitude) in spectrum.bins() {
+        if frequency < band.start - margin || frequency > band.end + margin {
+            continue;
+        }
+
+        let bar = "#".repeat((magnitude / peak * BAR_WIDTH).round() as usize);
+        println!("{:>7.0} Hz | {}", frequency, bar);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,113 @@
+//! Spectrum analysis for tuning and visualization
+//!
+//! The C API does not expose the spectrum ggwave computes internally, so this
+//! module computes it again with `rustfft`. Like the decoder, it analyzes one
+//! frame of `samplesPerFrame` samples without windowing, so the bins line up
+//! with the ones the protocols' tones are placed on.
+
+use rustfft::FftPlanner;
+use rustfft::num_complex::Complex;
+
+/// Magnitude spectrum of one frame of audio
+///
+/// # Examples
+///
+/// ```
+/// use ggwave_rs::analysis::Spectrum;
+///
+/// // A full-scale 1.5 kHz tone, which falls exactly on bin 32
+/// let frame: Vec<f32> = (0..1024)
+///     .map(|i| (2.0 * std::f32::consts::PI * 1500.0 * i as f32 / 48000.0).sin())
+///     .collect();
+///
+/// let spectrum = Spectrum::compute(&frame, 48000.0);
+/// assert_eq!(spectrum.peak(), Some((1500.0, spectrum.magnitudes()[32])));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spectrum {
+    magnitudes: Vec<f32>,
+    bin_width: f32,
+}
+
+impl Spectrum {
+    /// Compute the spectrum of a frame
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - Normalized samples of one frame
+    /// * `sample_rate` - Sample rate of the frame in Hz
+    ///
+    /// # Returns
+    ///
+    /// The spectrum from 0 Hz up to, but excluding, the Nyquist frequency
+    pub fn compute(frame: &[f32], sample_rate: f32) -> Self {
+        let len = frame.len();
+        if len == 0 {
+            return Self {
+                magnitudes: Vec::new(),
+                bin_width: 0.0,
+            };
+        }
+
+        let mut buffer: Vec<Complex<f32>> = frame.iter().map(|&s| Complex::new(s, 0.0)).collect();
+        FftPlanner::new().plan_fft_forward(len).process(&mut buffer);
+
+        // Scaled so that a full-scale sine on a bin has a magnitude of 1.0
+        let scale = 2.0 / len as f32;
+        let magnitudes = buffer[..len / 2].iter().map(|c| c.norm() * scale).collect();
+
+        Self {
+            magnitudes,
+            bin_width: sample_rate / len as f32,
+        }
+    }
+
+    /// Magnitude of each bin, in order of frequency
+    pub fn magnitudes(&self) -> &[f32] {
+        &self.magnitudes
+    }
+
+    /// Width of one bin in Hz
+    pub fn bin_width(&self) -> f32 {
+        self.bin_width
+    }
+
+    /// Center frequency of a bin in Hz
+    pub fn frequency(&self, bin: usize) -> f32 {
+        bin as f32 * self.bin_width
+    }
+
+    /// The bins as `(frequency in Hz, magnitude)` pairs
+    pub fn bins(&self) -> impl Iterator<Item = (f32, f32)> + '_ {
+        self.magnitudes
+            .iter()
+            .enumerate()
+            .map(|(bin, &magnitude)| (self.frequency(bin), magnitude))
+    }
+
+    /// The strongest bin, ignoring the DC offset at bin 0
+    pub fn peak(&self) -> Option<(f32, f32)> {
+        self.bins().skip(1).max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spectrum_peak() {
+        let frame: Vec<f32> = (0..1024)
+            .map(|i| 0.5 * (2.0 * std::f32::consts::PI * 3000.0 * i as f32 / 48000.0).sin())
+            .collect();
+
+        let spectrum = Spectrum::compute(&frame, 48000.0);
+        assert_eq!(spectrum.magnitudes().len(), 512);
+        assert_eq!(spectrum.bin_width(), 46.875);
+
+        let (frequency, magnitude) = spectrum.peak().unwrap();
+        assert_eq!(frequency, 3000.0);
+        assert!((magnitude - 0.5).abs() < 1e-3);
+        assert_eq!(Spectrum::compute(&[], 48000.0).peak(), None);
+    }
+}
@@ -3,12 +3,16 @@
 //! This module provides async wrappers around the synchronous GGWave API,
 //! allowing for non-blocking encode/decode operations and stream processing.
 
-use crate::{Error, GGWave, Parameters, ProtocolId, Result};
+use crate::{
+    DecodeResult, Error, GGWave, Parameters, ProtocolId, Result, SampleFormat, constants, convert,
+    operating_modes::{Flags, OperatingMode},
+};
 use std::path::Path;
 use std::sync::Arc;
 use tokio::fs;
 use tokio::sync::Mutex;
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
 use tokio::task;
 
 /// Async wrapper around GGWave
@@ -247,6 +251,108 @@ impl AsyncGGWave {
         }).await.map_err(|_| Error::DecodeFailed(-1))?
     }
 
+    /// Decode raw audio data asynchronously into a reusable buffer
+    ///
+    /// Unlike [`decode_to_string`](Self::decode_to_string), neither the
+    /// waveform nor the payload buffer is copied or allocated per call: both are
+    /// moved into the blocking task and the buffer is handed back, so a
+    /// streaming loop can recycle it.
+    ///
+    /// # Arguments
+    ///
+    /// * `waveform` - The raw audio data to decode
+    /// * `buffer` - The payload buffer; its length is the maximum payload size
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the decoded text and the buffer
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::async_impl::AsyncGGWave;
+    /// use ggwave_rs::protocols;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let ggwave = AsyncGGWave::new().await.expect("Failed to initialize AsyncGGWave");
+    ///     let mut buffer = vec![0u8; 1024];
+    ///
+    ///     for text in ["Hello", "World"] {
+    ///         let waveform = ggwave.encode(text, protocols::AUDIBLE_FAST, 50)
+    ///             .await
+    ///             .expect("Failed to encode text");
+    ///
+    ///         let (decoded, returned) = ggwave.decode_into(waveform, buffer)
+    ///             .await
+    ///             .expect("Failed to decode waveform");
+    ///         assert_eq!(decoded, text);
+    ///         buffer = returned;
+    ///     }
+    /// }
+    /// ```
+    pub async fn decode_into(&self, waveform: Vec<u8>, mut buffer: Vec<u8>) -> Result<(String, Vec<u8>)> {
+        let inner = self.inner.clone();
+
+        task::spawn_blocking(move || {
+            let ggwave = inner.blocking_lock();
+            let text = ggwave.decode(&waveform, &mut buffer)?.to_string();
+            Ok((text, buffer))
+        }).await.map_err(|_| Error::DecodeFailed(-1))?
+    }
+
+    /// Decode raw audio data to binary data asynchronously
+    ///
+    /// This is the variant of decode to use when the data being transmitted
+    /// is not UTF-8 text.
+    ///
+    /// # Arguments
+    ///
+    /// * `waveform` - The raw audio data to decode
+    /// * `max_payload` - The maximum size of the decoded payload
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the decoded bytes, or `Error::NoMessage` if the
+    /// audio does not contain a complete message
+    pub async fn decode_binary(&self, waveform: Vec<u8>, max_payload: usize) -> Result<Vec<u8>> {
+        let inner = self.inner.clone();
+
+        task::spawn_blocking(move || {
+            let ggwave = inner.blocking_lock();
+            let mut buffer = vec![0u8; max_payload];
+            ggwave.decode_binary(&waveform, &mut buffer).map(<[u8]>::to_vec)
+        }).await.map_err(|_| Error::DecodeFailed(-1))?
+    }
+
+    /// Decode raw audio data asynchronously, reporting which protocol carried
+    /// the message and how cleanly it was received
+    ///
+    /// See [`GGWave::decode_detailed`].
+    ///
+    /// # Arguments
+    ///
+    /// * `waveform` - The raw audio data to decode
+    /// * `max_payload` - The maximum size of the decoded payload
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the decoded message and its details, or `None` if
+    /// no message was found
+    pub async fn decode_detailed(
+        &self,
+        waveform: Vec<u8>,
+        max_payload: usize,
+    ) -> Result<Option<DecodeResult>> {
+        let inner = self.inner.clone();
+
+        task::spawn_blocking(move || {
+            let ggwave = inner.blocking_lock();
+            let mut buffer = vec![0u8; max_payload];
+            ggwave.decode_detailed(&waveform, &mut buffer)
+        }).await.map_err(|_| Error::DecodeFailed(-1))?
+    }
+
     /// Process an audio chunk asynchronously
     ///
     /// This method is useful for real-time streaming audio processing.
@@ -279,6 +385,37 @@ impl AsyncGGWave {
         Ok(result)
     }
 
+    /// Process a chunk of `f32` samples asynchronously
+    ///
+    /// Takes the buffer delivered by audio callbacks such as cpal's. The
+    /// samples are converted to the instance's input sample format on the
+    /// blocking thread, so callers don't need to reinterpret them as bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - Normalized mono samples in the range [-1.0, 1.0]
+    /// * `max_payload_size` - The maximum size of the decoded payload
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing an Option with the decoded string if something was found
+    pub async fn process_f32_chunk(
+        &self,
+        samples: Vec<f32>,
+        max_payload_size: usize,
+    ) -> Result<Option<String>> {
+        let inner = self.inner.clone();
+
+        task::spawn_blocking(move || -> Result<Option<String>> {
+            let ggwave = inner.blocking_lock();
+            let audio_chunk = convert::f32_to_bytes(&samples, ggwave.parameters().sampleFormatInp)?;
+            let mut buffer = vec![0u8; max_payload_size];
+            Ok(ggwave
+                .process_audio_chunk(&audio_chunk, &mut buffer)?
+                .map(str::to_string))
+        }).await.map_err(|_| Error::DecodeFailed(-1))?
+    }
+
     /// Encode text and save directly to a WAV file asynchronously
     ///
     /// # Arguments
@@ -407,6 +544,10 @@ impl AsyncGGWave {
 
     /// Process an audio stream for decoding
     ///
+    /// Reads rarely line up with the decoder's frames, or even with whole
+    /// samples, so the bytes of an incomplete frame are kept until the next
+    /// read and the decoder is always fed whole frames.
+    ///
     /// # Arguments
     ///
     /// * `reader` - The async reader to stream from
@@ -429,6 +570,7 @@ impl AsyncGGWave {
         F: FnMut(String) -> Result<()>,
     {
         let mut buffer = vec![0u8; chunk_size];
+        let mut pending = FramePending::new(self.frame_bytes().await?);
         
         loop {
             // Read a chunk from the stream
@@ -437,15 +579,110 @@ impl AsyncGGWave {
                 break; // End of stream
             }
             
-            // Process the chunk
-            if let Some(decoded) = self.process_audio_chunk(&buffer[..n], max_payload_size).await? {
-                callback(decoded)?;
+            // Process the whole frames read so far
+            if let Some(frames) = pending.push(&buffer[..n]) {
+                for decoded in self.decode_frames(frames, max_payload_size).await? {
+                    callback(decoded)?;
+                }
             }
         }
         
         Ok(())
     }
 
+    /// Size in bytes of one frame of input audio
+    async fn frame_bytes(&self) -> Result<usize> {
+        let params = *self.inner.lock().await.parameters();
+        Ok(params.samplesPerFrame.max(1) as usize * convert::sample_size(params.sampleFormatInp)?)
+    }
+
+    /// Decode whole frames of input audio, collecting every completed message
+    async fn decode_frames(&self, frames: Vec<u8>, max_payload_size: usize) -> Result<Vec<String>> {
+        let inner = self.inner.clone();
+
+        task::spawn_blocking(move || {
+            let ggwave = inner.blocking_lock();
+            let mut buffer = vec![0u8; max_payload_size];
+            ggwave.process_audio_chunk_all(&frames, &mut buffer)
+        }).await.map_err(|_| Error::DecodeFailed(-1))?
+    }
+
+    /// Decode length-prefixed frames of raw samples received over TCP
+    ///
+    /// Each frame is a little-endian `u32` byte length followed by that many
+    /// bytes of samples in `format`. See [`GGWave::decode_from_tcp`] for details.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - The connection to read frames from
+    /// * `format` - The sample format of the received frames
+    /// * `callback` - Function to call when data is decoded
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ggwave_rs::async_impl::AsyncGGWave;
+    /// use ggwave_rs::sample_formats;
+    /// use tokio::net::TcpListener;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let ggwave = AsyncGGWave::new().await.expect("Failed to initialize AsyncGGWave");
+    ///     let listener = TcpListener::bind("0.0.0.0:9000").await.expect("Failed to bind");
+    ///
+    ///     let (stream, _) = listener.accept().await.expect("Failed to accept connection");
+    ///     ggwave
+    ///         .decode_from_tcp(stream, sample_formats::I16, |message| {
+    ///             println!("Received: {}", message);
+    ///             Ok(())
+    ///         })
+    ///         .await
+    ///         .expect("Failed to decode stream");
+    /// }
+    /// ```
+    pub async fn decode_from_tcp<F>(
+        &self,
+        stream: TcpStream,
+        format: SampleFormat,
+        mut callback: F,
+    ) -> Result<()>
+    where
+        F: FnMut(String) -> Result<()>,
+    {
+        let mut reader = BufReader::new(stream);
+        // Moved into each decode task and handed back, so frames share them
+        let mut buffer = vec![0u8; constants::MAX_DATA_SIZE];
+        let mut pending = Vec::new();
+
+        loop {
+            let len = match reader.read_u32_le().await {
+                Ok(len) => crate::stream_frame_len(len)?,
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(()),
+                Err(e) => return Err(Error::IoError(e)),
+            };
+
+            let mut frame = vec![0u8; len];
+            reader.read_exact(&mut frame).await.map_err(Error::IoError)?;
+
+            let inner = self.inner.clone();
+            let (returned, returned_pending, decoded) = task::spawn_blocking(move || {
+                let ggwave = inner.blocking_lock();
+                let decoded = ggwave.decode_stream_frame(&frame, format, &mut pending, &mut buffer);
+                (buffer, pending, decoded)
+            }).await.map_err(|_| Error::DecodeFailed(-1))?;
+            buffer = returned;
+            pending = returned_pending;
+
+            for message in decoded? {
+                callback(message)?;
+            }
+        }
+    }
+
     /// Toggle reception of a specific protocol
     pub async fn toggle_rx_protocol(&self, protocol_id: ProtocolId, enabled: bool) {
         let inner = self.inner.clone();
@@ -476,11 +713,24 @@ impl AsyncGGWave {
         }).await.ok();
     }
 
+    /// Get the protocols currently enabled for reception
+    pub async fn enabled_rx_protocols(&self) -> Vec<ProtocolId> {
+        self.inner.lock().await.enabled_rx_protocols()
+    }
+
+    /// Get the protocols currently enabled for transmission
+    pub async fn enabled_tx_protocols(&self) -> Vec<ProtocolId> {
+        self.inner.lock().await.enabled_tx_protocols()
+    }
+}
+
+impl Clone for AsyncGGWave {
     /// Create a clone of this AsyncGGWave instance
     ///
     /// This is useful for sharing the same underlying GGWave instance
-    /// across multiple tasks.
-    pub fn clone(&self) -> Self {
+    /// across multiple tasks. Calls on the clones are serialized; use
+    /// `GGWave::try_clone` for independent instances.
+    fn clone(&self) -> Self {
         Self {
             inner: self.inner.clone(),
         }
@@ -519,6 +769,12 @@ impl AsyncGGWaveBuilder {
         self
     }
 
+    /// Set the processing sample rate only
+    pub fn processing_sample_rate(mut self, rate: f32) -> Self {
+        self.inner_builder = self.inner_builder.processing_sample_rate(rate);
+        self
+    }
+
     /// Set samples per frame
     pub fn samples_per_frame(mut self, samples: i32) -> Self {
         self.inner_builder = self.inner_builder.samples_per_frame(samples);
@@ -544,17 +800,78 @@ impl AsyncGGWaveBuilder {
     }
 
     /// Set operating mode
-    pub fn operating_mode(mut self, mode: i32) -> Self {
+    pub fn operating_mode(mut self, mode: impl Into<i32>) -> Self {
         self.inner_builder = self.inner_builder.operating_mode(mode);
         self
     }
 
+    /// Set the direction of the instance, keeping the modifier flags
+    pub fn base_mode(mut self, mode: OperatingMode) -> Self {
+        self.inner_builder = self.inner_builder.base_mode(mode);
+        self
+    }
+
+    /// Add operating mode flags, keeping those already set
+    pub fn with_mode_flags(mut self, flags: Flags) -> Self {
+        self.inner_builder = self.inner_builder.with_mode_flags(flags);
+        self
+    }
+
+    /// Enable or disable Direct Sequence Spread, keeping the other flags
+    pub fn use_dss(mut self, enabled: bool) -> Self {
+        self.inner_builder = self.inner_builder.use_dss(enabled);
+        self
+    }
+
+    /// Enable or disable tone-only transmission, keeping the other flags
+    pub fn tx_only_tones(mut self, enabled: bool) -> Self {
+        self.inner_builder = self.inner_builder.tx_only_tones(enabled);
+        self
+    }
+
     /// Set fixed payload length
     pub fn fixed_payload_length(mut self, length: i32) -> Self {
         self.inner_builder = self.inner_builder.fixed_payload_length(length);
         self
     }
 
+    /// Limit the number of samples a single encode may produce
+    pub fn max_output_samples(mut self, samples: usize) -> Self {
+        self.inner_builder = self.inner_builder.max_output_samples(samples);
+        self
+    }
+
+    /// Set the protocols the instance can receive
+    pub fn rx_protocols(mut self, mask: crate::ProtocolMask) -> Self {
+        self.inner_builder = self.inner_builder.rx_protocols(mask);
+        self
+    }
+
+    /// Set the protocols the instance can transmit
+    pub fn tx_protocols(mut self, mask: crate::ProtocolMask) -> Self {
+        self.inner_builder = self.inner_builder.tx_protocols(mask);
+        self
+    }
+
+    /// Set how payloads are represented on the air
+    pub fn transport_encoding(mut self, encoding: crate::TransportEncoding) -> Self {
+        self.inner_builder = self.inner_builder.transport_encoding(encoding);
+        self
+    }
+
+    /// Get the parameters configured so far
+    pub fn parameters(&self) -> &crate::Parameters {
+        self.inner_builder.parameters()
+    }
+
+    /// Take the configured parameters, to create several instances from them
+    ///
+    /// See [`crate::GGWaveBuilder::into_parameters`] for the settings that
+    /// are dropped.
+    pub fn into_parameters(self) -> crate::Parameters {
+        self.inner_builder.into_parameters()
+    }
+
     /// Build an AsyncGGWave instance with the configured parameters
     pub async fn build(self) -> Result<AsyncGGWave> {
         let inner_builder = self.inner_builder;
@@ -569,6 +886,28 @@ impl AsyncGGWaveBuilder {
     }
 }
 
+/// Bytes read from a stream that do not fill a frame yet
+struct FramePending {
+    frame_bytes: usize,
+    bytes: Vec<u8>,
+}
+
+impl FramePending {
+    fn new(frame_bytes: usize) -> Self {
+        Self {
+            frame_bytes,
+            bytes: Vec::with_capacity(frame_bytes),
+        }
+    }
+
+    /// Append a read, returning the whole frames accumulated so far
+    fn push(&mut self, data: &[u8]) -> Option<Vec<u8>> {
+        self.bytes.extend_from_slice(data);
+        let whole = self.bytes.len() - self.bytes.len() % self.frame_bytes;
+        (whole > 0).then(|| self.bytes.drain(..whole).collect())
+    }
+}
+
 impl Default for AsyncGGWaveBuilder {
     fn default() -> Self {
         Self::new()
@@ -621,6 +960,9 @@ pub mod streams {
 
     /// Start processing an audio stream in the background
     ///
+    /// Like [`AsyncGGWave::process_audio_stream`], incomplete frames are kept
+    /// between reads so the decoder is always fed whole frames.
+    ///
     /// # Arguments
     ///
     /// * `ggwave` - The AsyncGGWave instance to use
@@ -633,37 +975,84 @@ pub mod streams {
     ///
     /// A `Result` containing a MessageReceiver that can be used to receive decoded messages
     pub async fn start_background_processing<R>(
+        ggwave: AsyncGGWave,
+        reader: R,
+        chunk_size: usize,
+        max_payload_size: usize,
+        buffer_size: usize,
+    ) -> Result<MessageReceiver>
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        start_background_processing_until(
+            ggwave,
+            reader,
+            chunk_size,
+            max_payload_size,
+            buffer_size,
+            |_| false,
+        )
+        .await
+    }
+
+    /// Start processing an audio stream in the background until a message
+    /// matches a predicate
+    ///
+    /// Works like [`start_background_processing`], but the task ends as soon
+    /// as `stop_when` returns true for a decoded message, e.g. a handshake
+    /// token. That message is still sent through the channel, after which the
+    /// channel closes and [`MessageReceiver::recv`] returns `None`.
+    ///
+    /// # Arguments
+    ///
+    /// * `ggwave` - The AsyncGGWave instance to use
+    /// * `reader` - The async reader to stream from
+    /// * `chunk_size` - The size of chunks to read at once
+    /// * `max_payload_size` - The maximum size of the decoded payload
+    /// * `buffer_size` - The size of the message channel buffer
+    /// * `stop_when` - Called with each decoded message; returning true stops
+    ///   the processing
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a MessageReceiver that can be used to receive decoded messages
+    pub async fn start_background_processing_until<R, F>(
         ggwave: AsyncGGWave,
         mut reader: R,
         chunk_size: usize,
         max_payload_size: usize,
         buffer_size: usize,
+        stop_when: F,
     ) -> Result<MessageReceiver>
     where
         R: AsyncRead + Unpin + Send + 'static,
+        F: Fn(&str) -> bool + Send + 'static,
     {
         let (tx, rx) = mpsc::channel(buffer_size);
+        let mut pending = FramePending::new(ggwave.frame_bytes().await?);
         
         // Spawn a task to process the audio stream
         tokio::spawn(async move {
             let mut buffer = vec![0u8; chunk_size];
             
-            loop {
-                // Read a chunk from the stream
-                let n = match reader.read(&mut buffer).await {
-                    Ok(n) => n,
-                    Err(_) => break, // Error reading from stream
-                };
-                
+            // Read chunks until the stream errors out
+            while let Ok(n) = reader.read(&mut buffer).await {
                 if n == 0 {
                     break; // End of stream
                 }
                 
-                // Process the chunk
-                if let Ok(Some(decoded)) = ggwave.process_audio_chunk(&buffer[..n], max_payload_size).await {
+                // Process the whole frames read so far
+                let Some(frames) = pending.push(&buffer[..n]) else {
+                    continue;
+                };
+                let Ok(messages) = ggwave.decode_frames(frames, max_payload_size).await else {
+                    continue;
+                };
+                for decoded in messages {
+                    let stop = stop_when(&decoded);
                     // Try to send the decoded message
-                    if tx.send(decoded).await.is_err() {
-                        break; // Receiver dropped
+                    if tx.send(decoded).await.is_err() || stop {
+                        return; // Receiver dropped or predicate matched
                     }
                 }
             }
@@ -671,9 +1060,124 @@ pub mod streams {
         
         Ok(MessageReceiver { rx })
     }
+
+    /// A message waiting to be transmitted
+    struct Outgoing {
+        text: String,
+        protocol_id: ProtocolId,
+        volume: i32,
+    }
+
+    /// A queue of messages encoded and written by a background task
+    ///
+    /// Created by [`start_background_transmitting`].
+    pub struct MessageSender<W> {
+        tx: mpsc::Sender<Outgoing>,
+        task: task::JoinHandle<Result<W>>,
+    }
+
+    impl<W> MessageSender<W> {
+        /// Queue a message for transmission
+        ///
+        /// Waits while the queue is full, so a slow sink slows down the sender.
+        ///
+        /// # Arguments
+        ///
+        /// * `text` - The text to encode
+        /// * `protocol_id` - The protocol to use for encoding
+        /// * `volume` - The volume of the encoded audio (0-100)
+        ///
+        /// # Returns
+        ///
+        /// A `Result` indicating whether the message was queued, or
+        /// `Error::IoError` if the background task has stopped; [`finish`](Self::finish)
+        /// returns the reason
+        pub async fn send(&self, text: &str, protocol_id: ProtocolId, volume: i32) -> Result<()> {
+            let message = Outgoing {
+                text: text.to_string(),
+                protocol_id,
+                volume,
+            };
+            self.tx
+                .send(message)
+                .await
+                .map_err(|_| Error::IoError(std::io::ErrorKind::BrokenPipe.into()))
+        }
+
+        /// Wait until every queued message was written and get the writer back
+        ///
+        /// # Returns
+        ///
+        /// A `Result` containing the flushed writer, or the first error that
+        /// stopped the background task
+        pub async fn finish(self) -> Result<W> {
+            drop(self.tx);
+            self.task.await.map_err(|_| Error::EncodeFailed(-1))?
+        }
+    }
+
+    /// Start encoding and writing messages in the background
+    ///
+    /// Messages queued on the returned [`MessageSender`] are encoded one at a
+    /// time and written to `writer` back to back, in the order they were
+    /// queued. The task stops at the first message that fails to encode or
+    /// write, and the error is returned by [`MessageSender::finish`].
+    ///
+    /// # Arguments
+    ///
+    /// * `ggwave` - The AsyncGGWave instance to use
+    /// * `writer` - The async writer to send the encoded audio to
+    /// * `buffer_size` - The number of messages that can wait in the queue
+    ///
+    /// # Returns
+    ///
+    /// A MessageSender that queues messages for transmission
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ggwave_rs::async_impl::{AsyncGGWave, streams};
+    /// use ggwave_rs::protocols;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let ggwave = AsyncGGWave::new().await.expect("Failed to initialize AsyncGGWave");
+    ///     let file = tokio::fs::File::create("outbound.raw").await.expect("Failed to create file");
+    ///
+    ///     let sender = streams::start_background_transmitting(ggwave, file, 8);
+    ///     sender.send("Hello", protocols::AUDIBLE_FAST, 50).await.expect("Transmitter stopped");
+    ///     sender.finish().await.expect("Failed to transmit messages");
+    /// }
+    /// ```
+    pub fn start_background_transmitting<W>(
+        ggwave: AsyncGGWave,
+        mut writer: W,
+        buffer_size: usize,
+    ) -> MessageSender<W>
+    where
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let (tx, mut rx) = mpsc::channel::<Outgoing>(buffer_size);
+
+        let task = tokio::spawn(async move {
+            // Ends when the sender is finished or dropped
+            while let Some(message) = rx.recv().await {
+                ggwave
+                    .stream_encoded(&message.text, message.protocol_id, message.volume, &mut writer)
+                    .await?;
+            }
+
+            writer.flush().await.map_err(Error::IoError)?;
+            Ok(writer)
+        });
+
+        MessageSender { tx, task }
+    }
 }
 
 #[cfg(test)]
+// The guard only serializes instance creation across tests
+#[allow(clippy::await_holding_lock)]
 mod tests {
     use crate::{protocols, sample_formats};
 
@@ -681,6 +1185,7 @@ mod tests {
     
     #[tokio::test]
     async fn test_async_encode_decode() {
+        let _serial = crate::tests::serial();
         let ggwave = AsyncGGWave::new().await.expect("Failed to initialize AsyncGGWave");
         let text = "Hello, Async GGWave!";
         
@@ -695,8 +1200,111 @@ mod tests {
         assert_eq!(decoded, text);
     }
     
+    #[tokio::test]
+    async fn test_async_clone() {
+        fn clones<T: Clone>(value: &T, count: usize) -> Vec<T> {
+            vec![value.clone(); count]
+        }
+
+        let _serial = crate::tests::serial();
+        let ggwave = AsyncGGWave::new().await.expect("Failed to initialize AsyncGGWave");
+        let shared: Vec<AsyncGGWave> = clones(&ggwave, 3);
+
+        // Every clone shares the one underlying instance
+        assert!(shared.iter().all(|clone| Arc::ptr_eq(&clone.inner, &ggwave.inner)));
+        let waveform = shared[0].encode("Shared", protocols::AUDIBLE_FAST, 50)
+            .await
+            .expect("Failed to encode text");
+        let decoded = shared[2].decode_to_string(&waveform, 1024)
+            .await
+            .expect("Failed to decode waveform");
+        assert_eq!(decoded, "Shared");
+    }
+
+    #[tokio::test]
+    async fn test_async_decode_into() {
+        let _serial = crate::tests::serial();
+        let ggwave = AsyncGGWave::new().await.expect("Failed to initialize AsyncGGWave");
+        let mut buffer = vec![0u8; 1024];
+
+        for text in ["First", "Second"] {
+            let waveform = ggwave.encode(text, protocols::AUDIBLE_FAST, 50)
+                .await
+                .expect("Failed to encode text");
+
+            let (decoded, returned) = ggwave.decode_into(waveform, buffer)
+                .await
+                .expect("Failed to decode waveform");
+            assert_eq!(decoded, text);
+            assert_eq!(returned.len(), 1024);
+            buffer = returned;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_decode_binary() {
+        let _serial = crate::tests::serial();
+        let ggwave = AsyncGGWave::new().await.expect("Failed to initialize AsyncGGWave");
+        let payload = vec![0x00, 0xFF, 0xFE, 0x80, 0x01];
+
+        let waveform = ggwave
+            .inner
+            .lock()
+            .await
+            .encode_payload(&payload, protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode payload");
+
+        let decoded = ggwave.decode_binary(waveform.clone(), 1024)
+            .await
+            .expect("Failed to decode waveform");
+        assert_eq!(decoded, payload);
+
+        let silence = vec![0u8; 4 * 48000];
+        let result = ggwave.decode_detailed(silence, 1024)
+            .await
+            .expect("Failed to decode silence");
+        assert_eq!(result, None);
+
+        // A fresh instance, so the silence above leaves no receive state behind
+        drop(ggwave);
+        let ggwave = AsyncGGWave::new().await.expect("Failed to initialize AsyncGGWave");
+        let text = "Detailed";
+        let waveform = ggwave.encode(text, protocols::AUDIBLE_FAST, 50)
+            .await
+            .expect("Failed to encode text");
+        let result = ggwave.decode_detailed(waveform, 1024)
+            .await
+            .expect("Failed to decode waveform")
+            .expect("No message found");
+        assert_eq!(result.text, text);
+        assert_eq!(result.protocol, protocols::AUDIBLE_FAST);
+    }
+
+    #[tokio::test]
+    async fn test_async_process_f32_chunk() {
+        let _serial = crate::tests::serial();
+        let ggwave = AsyncGGWave::builder()
+            .output_sample_format(sample_formats::F32)
+            .build()
+            .await
+            .expect("Failed to initialize AsyncGGWave");
+        let text = "Async f32";
+
+        let waveform = ggwave.encode(text, protocols::AUDIBLE_FAST, 50)
+            .await
+            .expect("Failed to encode text");
+        let samples = convert::bytes_to_f32(&waveform, sample_formats::F32)
+            .expect("Failed to convert samples");
+
+        let decoded = ggwave.process_f32_chunk(samples, 1024)
+            .await
+            .expect("Failed to process samples");
+        assert_eq!(decoded.as_deref(), Some(text));
+    }
+
     #[tokio::test]
     async fn test_async_builder() {
+        let _serial = crate::tests::serial();
         let ggwave = AsyncGGWave::builder()
             .sample_rate(48000.0)
             .output_sample_format(sample_formats::F32)
@@ -715,4 +1323,132 @@ mod tests {
             
         assert_eq!(decoded, text);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_async_decode_from_tcp() {
+        let _serial = crate::tests::serial();
+        let ggwave = AsyncGGWave::new().await.expect("Failed to initialize AsyncGGWave");
+        let text = "Hello over async TCP";
+
+        let waveform = ggwave.encode(text, protocols::AUDIBLE_FAST, 50)
+            .await
+            .expect("Failed to encode text");
+        // The default output format is F32
+        let frame_bytes = 1024 * 4;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("Failed to bind");
+        let addr = listener.local_addr().unwrap();
+
+        let client = tokio::spawn(async move {
+            let mut stream = TcpStream::connect(addr).await.expect("Failed to connect");
+            let silence = vec![0u8; frame_bytes * 8];
+            // Network frames that do not line up with the decoder's frames
+            for frame in waveform.chunks(700 * 4).chain(silence.chunks(700 * 4)) {
+                stream.write_u32_le(frame.len() as u32).await.unwrap();
+                stream.write_all(frame).await.unwrap();
+            }
+        });
+
+        let (stream, _) = listener.accept().await.expect("Failed to accept connection");
+        let mut messages = Vec::new();
+        ggwave
+            .decode_from_tcp(stream, sample_formats::F32, |message| {
+                messages.push(message);
+                Ok(())
+            })
+            .await
+            .expect("Failed to decode TCP stream");
+        client.await.unwrap();
+
+        assert_eq!(messages, [text]);
+    }
+
+    #[tokio::test]
+    async fn test_async_stream_unaligned_chunks() {
+        let _serial = crate::tests::serial();
+        let ggwave = AsyncGGWave::new().await.expect("Failed to initialize AsyncGGWave");
+        let text = "Split across reads";
+
+        let mut waveform = ggwave.encode(text, protocols::AUDIBLE_FAST, 50)
+            .await
+            .expect("Failed to encode text");
+        // Trailing silence so the end marker is followed by whole frames
+        waveform.resize(waveform.len() + 1024 * 4 * 8, 0);
+
+        // 777 bytes is neither a whole frame nor a whole F32 sample
+        let mut messages = Vec::new();
+        ggwave
+            .process_audio_stream(&mut waveform.as_slice(), 777, 1024, |message| {
+                messages.push(message);
+                Ok(())
+            })
+            .await
+            .expect("Failed to process stream");
+        assert_eq!(messages, [text]);
+
+        let mut receiver = streams::start_background_processing(
+            ggwave.clone(),
+            std::io::Cursor::new(waveform),
+            777,
+            1024,
+            4,
+        )
+        .await
+        .expect("Failed to start background processing");
+        assert_eq!(receiver.recv().await.as_deref(), Some(text));
+    }
+
+    #[tokio::test]
+    async fn test_background_transmitting() {
+        let _serial = crate::tests::serial();
+        let ggwave = AsyncGGWave::new().await.expect("Failed to initialize AsyncGGWave");
+        let texts = ["One", "Two", "Three"];
+
+        let mut expected = Vec::new();
+        for text in texts {
+            expected.extend(ggwave.encode(text, protocols::AUDIBLE_FAST, 50)
+                .await
+                .expect("Failed to encode text"));
+        }
+
+        let sender = streams::start_background_transmitting(ggwave.clone(), Vec::new(), 1);
+        for text in texts {
+            sender.send(text, protocols::AUDIBLE_FAST, 50)
+                .await
+                .expect("Failed to queue message");
+        }
+        let written = sender.finish().await.expect("Failed to transmit messages");
+
+        assert_eq!(written, expected);
+    }
+
+    #[tokio::test]
+    async fn test_background_processing_until() {
+        let _serial = crate::tests::serial();
+        let ggwave = AsyncGGWave::new().await.expect("Failed to initialize AsyncGGWave");
+
+        // Silence between messages so each one ends before the next starts
+        let mut waveform = Vec::new();
+        for text in ["HELLO", "STOP", "AFTER"] {
+            waveform.extend(ggwave.encode(text, protocols::AUDIBLE_FAST, 50)
+                .await
+                .expect("Failed to encode text"));
+            waveform.resize(waveform.len() + 1024 * 4 * 8, 0);
+        }
+
+        let mut receiver = streams::start_background_processing_until(
+            ggwave.clone(),
+            std::io::Cursor::new(waveform),
+            4096,
+            1024,
+            4,
+            |message| message == "STOP",
+        )
+        .await
+        .expect("Failed to start background processing");
+
+        assert_eq!(receiver.recv().await.as_deref(), Some("HELLO"));
+        assert_eq!(receiver.recv().await.as_deref(), Some("STOP"));
+        assert_eq!(receiver.recv().await, None);
+    }
+}
@@ -0,0 +1,742 @@
+//! Audio capture and playback helpers built on cpal
+//!
+//! This module wraps the device handling that every real-time application
+//! otherwise has to write by hand: opening the default (or a named) device,
+//! buffering captured samples into frame-sized chunks for the decoder, and
+//! playing encoded waveforms to completion.
+//!
+//! Streams are opened as mono `f32` at the sample rates configured on the
+//! `GGWave` instance, so the device must support that configuration.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use ggwave_rs::{GGWave, audio, protocols};
+//!
+//! let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+//! audio::transmit(&ggwave, "Hello, World!", protocols::AUDIBLE_FAST, 50)
+//!     .expect("Failed to transmit message");
+//! ```
+
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+use crate::stream_decoder::FrameDecoder;
+use crate::{Error, GGWave, ProtocolId, Result, constants, convert, protocols};
+
+/// Chunks of captured samples, or the error reported by the input stream
+type Captured = mpsc::Receiver<std::result::Result<Vec<f32>, String>>;
+
+/// Callback for errors reported while a [`RealtimeReceiver`] is running
+type ErrorCallback = Box<dyn FnMut(Error) + Send>;
+
+/// Extra time to keep the output stream alive so the device can drain its buffer
+const PLAYBACK_TAIL: Duration = Duration::from_millis(200);
+
+/// How much longer than its duration a waveform may take to play before giving up
+const PLAYBACK_SLACK: Duration = Duration::from_secs(2);
+
+/// Silence played after the self-test message so the decoder sees its end marker
+const LOOPBACK_PADDING_SECS: f32 = 0.5;
+
+/// How long [`transmit_reliable`] listens for an acknowledgement after each try
+pub const ACK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How much [`transmit_reliable`] raises the volume after a try goes unacknowledged
+pub const ACK_VOLUME_STEP: i32 = 10;
+
+/// Listen on an input device and invoke a callback for each decoded message
+///
+/// Captured audio is buffered into chunks of `samplesPerFrame` samples and
+/// converted to the instance's input sample format before being passed to
+/// [`GGWave::process_audio_chunk`].
+///
+/// This function blocks the current thread until the input stream reports an
+/// error, so it is usually run on a dedicated thread.
+///
+/// # Arguments
+///
+/// * `ggwave` - The GGWave instance used for decoding
+/// * `device` - Name of the input device to use, or `None` for the default device
+/// * `callback` - Function to call with each decoded message
+///
+/// # Examples
+///
+/// ```no_run
+/// use ggwave_rs::{GGWave, audio};
+///
+/// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+/// audio::listen(&ggwave, None, |message| println!("Received: {}", message))
+///     .expect("Audio capture failed");
+/// ```
+pub fn listen<F>(ggwave: &GGWave, device: Option<&str>, mut callback: F) -> Result<()>
+where
+    F: FnMut(String),
+{
+    let params = ggwave.parameters();
+    let host = cpal::default_host();
+    let device = input_device(&host, device)?;
+    let (_stream, rx) = capture(&device, params.sampleRateInp)?;
+
+    let frame_len = params.samplesPerFrame.max(1) as usize;
+    let mut pending = Vec::with_capacity(frame_len * 2);
+    let mut decode_buffer = vec![0u8; constants::MAX_DATA_SIZE];
+
+    for captured in rx {
+        pending.extend_from_slice(&captured.map_err(Error::AudioError)?);
+
+        while pending.len() >= frame_len {
+            let frame = convert::f32_to_bytes(&pending[..frame_len], params.sampleFormatInp)?;
+            pending.drain(..frame_len);
+
+            match ggwave.process_audio_chunk(&frame, &mut decode_buffer) {
+                Ok(Some(message)) if !message.is_empty() => callback(message.to_string()),
+                // A message that failed to decode is not fatal for the capture loop
+                Ok(_) | Err(Error::DecodeFailed(_)) | Err(Error::Utf8Error(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// A decoder that listens on an input device in the background
+///
+/// Unlike [`listen`], this owns the input stream and decodes on a dedicated
+/// thread, so the caller is not blocked and the audio callback never waits
+/// for the decoder. Captured audio is cut into frames as by
+/// [`crate::StreamDecoder`], and each decoded message is passed to the
+/// callback given to [`start`](Self::start) on the decode thread.
+///
+/// Stream errors, such as the device being disconnected, and decode errors
+/// are passed to the callback set with [`on_error`](Self::on_error) and do
+/// not stop the receiver. Dropping the receiver stops it.
+///
+/// # Examples
+///
+/// ```no_run
+/// use ggwave_rs::{GGWave, audio::RealtimeReceiver};
+///
+/// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+/// let mut receiver = RealtimeReceiver::new(ggwave, None)
+///     .on_error(|error| eprintln!("Receiver error: {}", error));
+///
+/// receiver
+///     .start(|message| println!("Received: {}", message))
+///     .expect("Failed to start receiver");
+/// std::thread::sleep(std::time::Duration::from_secs(30));
+/// receiver.stop().expect("Failed to stop receiver");
+/// ```
+pub struct RealtimeReceiver {
+    /// The instance, while it is not lent to the decode thread
+    ggwave: Option<GGWave>,
+    device: Option<String>,
+    on_error: Option<ErrorCallback>,
+    running: Option<Running>,
+}
+
+/// The input stream and decode thread of a started [`RealtimeReceiver`]
+struct Running {
+    stream: cpal::Stream,
+    decoder: thread::JoinHandle<(GGWave, Option<ErrorCallback>)>,
+}
+
+impl RealtimeReceiver {
+    /// Create a receiver that decodes with `ggwave`
+    ///
+    /// # Arguments
+    ///
+    /// * `ggwave` - The instance used for decoding; it must be able to receive
+    /// * `device` - Name of the input device to use, or `None` for the default device
+    pub fn new(ggwave: GGWave, device: Option<&str>) -> Self {
+        Self {
+            ggwave: Some(ggwave),
+            device: device.map(str::to_string),
+            on_error: None,
+            running: None,
+        }
+    }
+
+    /// Set the function to call with errors reported while running
+    ///
+    /// Without one, errors are ignored.
+    pub fn on_error<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(Error) + Send + 'static,
+    {
+        self.on_error = Some(Box::new(callback));
+        self
+    }
+
+    /// Check whether the receiver is listening
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    /// Open the input device and start decoding in the background
+    ///
+    /// # Arguments
+    ///
+    /// * `on_message` - Function to call on the decode thread with each decoded message
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating whether the receiver started, or
+    /// `Error::InvalidParameter` if it is already running
+    pub fn start<F>(&mut self, mut on_message: F) -> Result<()>
+    where
+        F: FnMut(String) + Send + 'static,
+    {
+        if self.running.is_some() {
+            return Err(Error::InvalidParameter("Receiver is already running"));
+        }
+        let ggwave = self
+            .ggwave
+            .as_ref()
+            .ok_or_else(|| Error::AudioError("Receiver lost its instance".to_string()))?;
+
+        let mut frames = FrameDecoder::new(ggwave)?;
+        let host = cpal::default_host();
+        let device = input_device(&host, self.device.as_deref())?;
+        let (stream, rx) = capture(&device, ggwave.parameters().sampleRateInp)?;
+
+        let ggwave = self.ggwave.take().expect("instance checked above");
+        let mut on_error = self.on_error.take();
+        let decoder = thread::spawn(move || {
+            let mut report = |error| {
+                if let Some(callback) = on_error.as_mut() {
+                    callback(error);
+                }
+            };
+
+            // Ends when the stream is dropped by `stop`
+            for captured in rx {
+                let samples = match captured {
+                    Ok(samples) => samples,
+                    Err(e) => {
+                        report(Error::AudioError(e));
+                        continue;
+                    }
+                };
+
+                let mut rest = &samples[..];
+                while !rest.is_empty() {
+                    rest = frames.fill(rest);
+                    if !frames.is_frame_full() {
+                        break;
+                    }
+
+                    match frames.process_frame(&ggwave) {
+                        Ok(0) => {}
+                        Ok(length) => match frames.text(length) {
+                            Ok(text) => on_message(text.to_string()),
+                            Err(e) => report(e),
+                        },
+                        Err(e) => report(e),
+                    }
+                }
+            }
+
+            (ggwave, on_error)
+        });
+
+        self.running = Some(Running { stream, decoder });
+        Ok(())
+    }
+
+    /// Close the input stream and wait for the decode thread to finish
+    ///
+    /// Does nothing if the receiver is not running. A stopped receiver can be
+    /// started again.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success, or `Error::AudioError` if the decode
+    /// thread panicked, in which case the receiver cannot be started again
+    pub fn stop(&mut self) -> Result<()> {
+        let Some(running) = self.running.take() else {
+            return Ok(());
+        };
+
+        drop(running.stream);
+        let (ggwave, on_error) = running
+            .decoder
+            .join()
+            .map_err(|_| Error::AudioError("Decode thread panicked".to_string()))?;
+        self.ggwave = Some(ggwave);
+        self.on_error = on_error;
+        Ok(())
+    }
+
+    /// Stop the receiver and return its instance
+    ///
+    /// # Returns
+    ///
+    /// The instance, or `None` if the decode thread panicked
+    pub fn into_inner(mut self) -> Option<GGWave> {
+        let _ = self.stop();
+        self.ggwave.take()
+    }
+}
+
+impl Drop for RealtimeReceiver {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+/// Encode text and play it on the default output device
+///
+/// The waveform is converted from the instance's output sample format to `f32`
+/// and played at its output sample rate. This function blocks until playback
+/// has finished.
+///
+/// # Arguments
+///
+/// * `ggwave` - The GGWave instance used for encoding
+/// * `text` - The text to transmit
+/// * `protocol_id` - The protocol to use for encoding
+/// * `volume` - The volume of the encoded audio (0-100)
+pub fn transmit(ggwave: &GGWave, text: &str, protocol_id: ProtocolId, volume: i32) -> Result<()> {
+    let params = ggwave.parameters();
+    let waveform = ggwave.encode(text, protocol_id, volume)?;
+    let samples = convert::bytes_to_f32(&waveform, params.sampleFormatOut)?;
+
+    let host = cpal::default_host();
+    let device = output_device(&host, None)?;
+    play(&device, samples, params.sampleRateOut)
+}
+
+/// Encode text at the recommended volume of its protocol and play it on the
+/// default output device
+///
+/// Like [`transmit`], with the volume given by
+/// [`protocols::recommended_volume`].
+///
+/// # Arguments
+///
+/// * `ggwave` - The GGWave instance used for encoding
+/// * `text` - The text to transmit
+/// * `protocol_id` - The protocol to use for encoding
+///
+/// # Examples
+///
+/// ```no_run
+/// use ggwave_rs::{GGWave, audio, protocols};
+///
+/// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+/// audio::transmit_recommended(&ggwave, "Hello, World!", protocols::ULTRASOUND_FAST)
+///     .expect("Failed to transmit message");
+/// ```
+pub fn transmit_recommended(ggwave: &GGWave, text: &str, protocol_id: ProtocolId) -> Result<()> {
+    transmit(
+        ggwave,
+        text,
+        protocol_id,
+        protocols::recommended_volume(protocol_id),
+    )
+}
+
+/// Transmit a message until an acknowledgement is heard, raising the volume
+/// after each unanswered try
+///
+/// Plays the message with [`transmit`] at `start_volume`, then listens on the
+/// default input device for up to [`ACK_TIMEOUT`]. Decoded messages that do
+/// not satisfy `ack` are ignored. If none does, the message is sent again
+/// [`ACK_VOLUME_STEP`] louder, capped at 100, up to `max_retries` more times.
+///
+/// The instance must be able to both transmit and receive. Backs
+/// [`GGWave::transmit_reliable`].
+///
+/// # Arguments
+///
+/// * `ggwave` - The GGWave instance used for encoding and decoding
+/// * `text` - The text to transmit
+/// * `protocol_id` - The protocol to use for encoding
+/// * `start_volume` - The volume of the first try (0-100)
+/// * `max_retries` - How many times to retransmit after the first try
+/// * `ack` - Returns whether a decoded message acknowledges the transmission
+///
+/// # Returns
+///
+/// A `Result` containing whether an acknowledgement was heard
+pub fn transmit_reliable<F>(
+    ggwave: &GGWave,
+    text: &str,
+    protocol_id: ProtocolId,
+    start_volume: i32,
+    max_retries: u32,
+    ack: F,
+) -> Result<bool>
+where
+    F: FnMut(&str) -> bool,
+{
+    retry_until_ack(
+        start_volume,
+        max_retries,
+        ack,
+        |volume| transmit(ggwave, text, protocol_id, volume),
+        |timeout| receive(ggwave, timeout),
+    )
+}
+
+/// The retry loop of [`transmit_reliable`], with the devices passed in
+///
+/// `send` transmits at the given volume and `receive` waits up to the given
+/// time for the next decoded message.
+fn retry_until_ack<A, S, R>(
+    start_volume: i32,
+    max_retries: u32,
+    mut ack: A,
+    mut send: S,
+    mut receive: R,
+) -> Result<bool>
+where
+    A: FnMut(&str) -> bool,
+    S: FnMut(i32) -> Result<()>,
+    R: FnMut(Duration) -> Result<Option<String>>,
+{
+    let mut volume = start_volume;
+    for _ in 0..=max_retries {
+        send(volume)?;
+
+        let deadline = Instant::now() + ACK_TIMEOUT;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match receive(remaining)? {
+                Some(message) if ack(&message) => return Ok(true),
+                Some(_) if !remaining.is_zero() => {}
+                _ => break,
+            }
+        }
+
+        volume = (volume + ACK_VOLUME_STEP).min(100);
+    }
+
+    Ok(false)
+}
+
+/// Play samples on the default output device while recording the default input device
+///
+/// Recording starts before playback and stops shortly after it has finished,
+/// which makes this suitable as the `playback_and_capture` argument of
+/// [`GGWave::assess_device`].
+///
+/// # Arguments
+///
+/// * `ggwave` - The GGWave instance whose sample rates are used
+/// * `samples` - Normalized mono samples to play at the output sample rate
+///
+/// # Returns
+///
+/// A `Result` containing the captured normalized mono samples at the input sample rate
+///
+/// # Examples
+///
+/// ```no_run
+/// use ggwave_rs::{GGWave, audio};
+///
+/// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+/// let report = ggwave
+///     .assess_device(|sweep| audio::play_and_capture(&ggwave, sweep))
+///     .expect("Failed to assess device");
+/// println!("Supported protocols: {:?}", report.supported_protocols);
+/// ```
+pub fn play_and_capture(ggwave: &GGWave, samples: &[f32]) -> Result<Vec<f32>> {
+    let params = ggwave.parameters();
+    let host = cpal::default_host();
+    let input = input_device(&host, None)?;
+    let output = output_device(&host, None)?;
+    let (stream, rx) = capture(&input, params.sampleRateInp)?;
+
+    play(&output, samples.to_vec(), params.sampleRateOut)?;
+    drop(stream);
+
+    let mut captured = Vec::new();
+    for chunk in rx.try_iter() {
+        captured.extend_from_slice(&chunk.map_err(Error::AudioError)?);
+    }
+
+    Ok(captured)
+}
+
+/// Listen on the default input device until a message is decoded or `timeout`
+/// elapses
+///
+/// Backs [`GGWave::listen_blocking`]. Captured audio is cut into frames the
+/// same way as by [`crate::StreamDecoder`].
+pub(crate) fn receive(ggwave: &GGWave, timeout: Duration) -> Result<Option<String>> {
+    let deadline = Instant::now() + timeout;
+    let mut frames = FrameDecoder::new(ggwave)?;
+
+    let host = cpal::default_host();
+    let device = input_device(&host, None)?;
+    let (_stream, rx) = capture(&device, ggwave.parameters().sampleRateInp)?;
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let captured = match rx.recv_timeout(remaining) {
+            Ok(captured) => captured.map_err(Error::AudioError)?,
+            Err(mpsc::RecvTimeoutError::Timeout) => return Ok(None),
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                return Err(Error::AudioError("Input stream closed".to_string()));
+            }
+        };
+
+        if let Some(text) = feed(ggwave, &mut frames, &captured)? {
+            return Ok(Some(text));
+        }
+    }
+}
+
+/// Play a random message on the default output device and decode it from the
+/// default input device
+///
+/// Backs [`GGWave::loopback_self_test`].
+pub(crate) fn loopback(ggwave: &GGWave) -> Result<bool> {
+    let params = ggwave.parameters();
+    let nonce = format!("selftest-{:08x}", nonce());
+    let protocol_id = protocols::AUDIBLE_FAST;
+    let waveform = ggwave.encode(
+        &nonce,
+        protocol_id,
+        protocols::recommended_volume(protocol_id),
+    )?;
+
+    let mut samples = convert::bytes_to_f32(&waveform, params.sampleFormatOut)?;
+    let padding = (params.sampleRateOut * LOOPBACK_PADDING_SECS) as usize;
+    samples.resize(samples.len() + padding, 0.0);
+
+    let mut frames = FrameDecoder::new(ggwave)?;
+    let captured = play_and_capture(ggwave, &samples)?;
+    Ok(feed(ggwave, &mut frames, &captured)?.is_some_and(|text| text == nonce))
+}
+
+/// Decode captured samples frame by frame, returning the first message
+fn feed(ggwave: &GGWave, frames: &mut FrameDecoder, samples: &[f32]) -> Result<Option<String>> {
+    let mut rest = samples;
+    while !rest.is_empty() {
+        rest = frames.fill(rest);
+        if !frames.is_frame_full() {
+            break;
+        }
+
+        let length = frames.process_frame(ggwave)?;
+        if length > 0 {
+            return frames.text(length).map(|text| Some(text.to_string()));
+        }
+    }
+
+    Ok(None)
+}
+
+/// A value that differs between runs, so a stale echo cannot pass the self-test
+fn nonce() -> u32 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|elapsed| elapsed.subsec_nanos() ^ elapsed.as_secs() as u32)
+        .unwrap_or(0)
+}
+
+/// Start capturing mono samples from an input device
+///
+/// Captured chunks arrive on the returned channel for as long as the returned
+/// stream is kept alive.
+fn capture(device: &cpal::Device, sample_rate: f32) -> Result<(cpal::Stream, Captured)> {
+    let (tx, rx) = mpsc::channel();
+    let err_tx = tx.clone();
+
+    let stream = device
+        .build_input_stream(
+            &stream_config(sample_rate),
+            move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                let _ = tx.send(Ok(data.to_vec()));
+            },
+            move |err| {
+                let _ = err_tx.send(Err(err.to_string()));
+            },
+            None,
+        )
+        .map_err(audio_error)?;
+    stream.play().map_err(audio_error)?;
+
+    Ok((stream, rx))
+}
+
+/// Play samples on an output device, blocking until all of them were consumed
+fn play(device: &cpal::Device, samples: Vec<f32>, sample_rate: f32) -> Result<()> {
+    let (done_tx, done_rx) = mpsc::channel::<std::result::Result<(), String>>();
+    let err_tx = done_tx.clone();
+    let timeout = Duration::from_secs_f32(samples.len() as f32 / sample_rate) + PLAYBACK_SLACK;
+    let mut position = 0;
+
+    let stream = device
+        .build_output_stream(
+            &stream_config(sample_rate),
+            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                for out in data.iter_mut() {
+                    *out = samples.get(position).copied().unwrap_or(0.0);
+                    position += 1;
+                }
+
+                if position >= samples.len() {
+                    let _ = done_tx.send(Ok(()));
+                }
+            },
+            move |err| {
+                let _ = err_tx.send(Err(err.to_string()));
+            },
+            None,
+        )
+        .map_err(audio_error)?;
+    stream.play().map_err(audio_error)?;
+
+    match done_rx.recv_timeout(timeout) {
+        Ok(result) => result.map_err(Error::AudioError)?,
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            return Err(Error::AudioError("Playback timed out".to_string()));
+        }
+        Err(e) => return Err(audio_error(e)),
+    }
+
+    thread::sleep(PLAYBACK_TAIL);
+    Ok(())
+}
+
+/// Find an input device by name, or the default input device
+fn input_device(host: &cpal::Host, name: Option<&str>) -> Result<cpal::Device> {
+    match name {
+        Some(name) => host
+            .input_devices()
+            .map_err(audio_error)?
+            .find(|device| device.name().is_ok_and(|n| n == name))
+            .ok_or_else(|| Error::AudioError(format!("Input device not found: {}", name))),
+        None => host
+            .default_input_device()
+            .ok_or_else(|| Error::AudioError("No input device available".to_string())),
+    }
+}
+
+/// Find an output device by name, or the default output device
+fn output_device(host: &cpal::Host, name: Option<&str>) -> Result<cpal::Device> {
+    match name {
+        Some(name) => host
+            .output_devices()
+            .map_err(audio_error)?
+            .find(|device| device.name().is_ok_and(|n| n == name))
+            .ok_or_else(|| Error::AudioError(format!("Output device not found: {}", name))),
+        None => host
+            .default_output_device()
+            .ok_or_else(|| Error::AudioError("No output device available".to_string())),
+    }
+}
+
+/// Mono stream configuration at the given sample rate
+fn stream_config(sample_rate: f32) -> cpal::StreamConfig {
+    cpal::StreamConfig {
+        channels: 1,
+        sample_rate: cpal::SampleRate(sample_rate as u32),
+        buffer_size: cpal::BufferSize::Default,
+    }
+}
+
+/// Map a cpal error into a crate error
+fn audio_error(err: impl std::fmt::Display) -> Error {
+    Error::AudioError(err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    #[ignore = "needs an audio input device; run with --ignored"]
+    fn test_realtime_receiver_start_stop() {
+        let _serial = crate::tests::serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let sink = errors.clone();
+        let mut receiver = RealtimeReceiver::new(ggwave, None)
+            .on_error(move |error| sink.lock().unwrap().push(error.to_string()));
+
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let sink = messages.clone();
+        receiver
+            .start(move |message| sink.lock().unwrap().push(message))
+            .expect("Failed to start receiver");
+        assert!(receiver.is_running());
+        assert!(matches!(
+            receiver.start(|_| {}),
+            Err(Error::InvalidParameter(_))
+        ));
+
+        thread::sleep(Duration::from_millis(200));
+        receiver.stop().expect("Failed to stop receiver");
+        assert!(!receiver.is_running());
+        assert!(errors.lock().unwrap().is_empty());
+        receiver.stop().expect("Stopping twice must succeed");
+
+        // The instance is handed back, so the receiver can be restarted
+        receiver.start(|_| {}).expect("Failed to restart receiver");
+        assert!(receiver.into_inner().is_some());
+    }
+
+    #[test]
+    fn test_retry_until_ack() {
+        let sent = RefCell::new(Vec::new());
+
+        // Acknowledged on the third try, after an unrelated message
+        let mut replies = vec![
+            None,
+            Some("noise".to_string()),
+            None,
+            Some("ack:hello".to_string()),
+        ]
+        .into_iter();
+        let acked = retry_until_ack(
+            90,
+            5,
+            |message| message == "ack:hello",
+            |volume| {
+                sent.borrow_mut().push(volume);
+                Ok(())
+            },
+            |_| Ok(replies.next().flatten()),
+        )
+        .expect("Retry loop failed");
+        assert!(acked);
+        assert_eq!(*sent.borrow(), [90, 100, 100]);
+
+        // Never acknowledged: one try plus every retry
+        sent.borrow_mut().clear();
+        let acked = retry_until_ack(
+            30,
+            2,
+            |_| true,
+            |volume| {
+                sent.borrow_mut().push(volume);
+                Ok(())
+            },
+            |_| Ok(None),
+        )
+        .expect("Retry loop failed");
+        assert!(!acked);
+        assert_eq!(*sent.borrow(), [30, 40, 50]);
+
+        // Errors while sending stop the loop
+        let result = retry_until_ack(
+            30,
+            2,
+            |_| true,
+            |_| Err(Error::AudioError("No output device available".to_string())),
+            |_| Ok(None),
+        );
+        assert!(matches!(result, Err(Error::AudioError(_))));
+    }
+}
@@ -0,0 +1,157 @@
+//! Transfer of data larger than a single transmission
+//!
+//! [`GGWave::send_large`](crate::GGWave::send_large) splits data into chunks
+//! that each fit in one transmission. Every chunk starts with a header holding
+//! its index and the total number of chunks, both as little-endian `u16`, so a
+//! [`ChunkReassembler`] can put the data back together whatever order the
+//! chunks arrive in.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{Error, Result};
+
+/// Size in bytes of the header in front of every chunk
+pub(crate) const HEADER_LEN: usize = 4;
+
+/// Add the chunk header to a piece of data
+pub(crate) fn chunk(index: u16, total: u16, data: &[u8]) -> Vec<u8> {
+    let mut chunk = Vec::with_capacity(HEADER_LEN + data.len());
+    chunk.extend_from_slice(&index.to_le_bytes());
+    chunk.extend_from_slice(&total.to_le_bytes());
+    chunk.extend_from_slice(data);
+    chunk
+}
+
+/// Collects decoded chunks until a whole transfer has been received
+///
+/// Duplicate chunks, e.g. from a sender that repeats the transfer, are
+/// ignored. A chunk claiming a different total than the previous ones starts a
+/// new transfer, dropping the chunks of the unfinished one.
+///
+/// # Examples
+///
+/// ```
+/// use ggwave_rs::{ChunkReassembler, GGWave, protocols};
+///
+/// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+/// let waveforms = ggwave
+///     .send_large(&[7u8; 300], protocols::AUDIBLE_FASTEST, 50)
+///     .expect("Failed to encode data");
+///
+/// let mut reassembler = ChunkReassembler::new();
+/// let mut buffer = vec![0u8; 1024];
+/// let mut data = None;
+/// for waveform in &waveforms {
+///     let chunk = ggwave.decode_binary(waveform, &mut buffer).expect("Failed to decode");
+///     data = reassembler.push(chunk).expect("Invalid chunk");
+/// }
+/// assert_eq!(data, Some(vec![7u8; 300]));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ChunkReassembler {
+    chunks: Vec<Option<Vec<u8>>>,
+    received: usize,
+}
+
+impl ChunkReassembler {
+    /// Create an empty reassembler
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a decoded chunk
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk` - A payload decoded from one of the waveforms of `send_large`
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the complete data once the last missing chunk is
+    /// received, after which the reassembler is empty again, or
+    /// `Error::InvalidParameter` if the chunk header is malformed
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Option<Vec<u8>>> {
+        if chunk.len() < HEADER_LEN {
+            return Err(Error::InvalidParameter("Chunk is shorter than its header"));
+        }
+
+        let index = u16::from_le_bytes([chunk[0], chunk[1]]) as usize;
+        let total = u16::from_le_bytes([chunk[2], chunk[3]]) as usize;
+        if index >= total {
+            return Err(Error::InvalidParameter("Chunk index is out of range"));
+        }
+
+        if total != self.chunks.len() {
+            self.chunks = vec![None; total];
+            self.received = 0;
+        }
+
+        let slot = &mut self.chunks[index];
+        if slot.is_none() {
+            *slot = Some(chunk[HEADER_LEN..].to_vec());
+            self.received += 1;
+        }
+
+        if self.received < total {
+            return Ok(None);
+        }
+
+        let data = self.chunks.drain(..).flatten().flatten().collect();
+        self.received = 0;
+        Ok(Some(data))
+    }
+
+    /// Number of distinct chunks received for the current transfer
+    pub fn received(&self) -> usize {
+        self.received
+    }
+
+    /// Total number of chunks of the current transfer, or 0 before the first
+    /// chunk
+    pub fn total(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Indices of the chunks of the current transfer still missing, which the
+    /// sender can be asked to repeat
+    pub fn missing(&self) -> Vec<usize> {
+        self.chunks
+            .iter()
+            .enumerate()
+            .filter(|(_, chunk)| chunk.is_none())
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Drop the chunks of the current transfer
+    pub fn reset(&mut self) {
+        self.chunks.clear();
+        self.received = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reassembler() {
+        let mut reassembler = ChunkReassembler::new();
+        assert_eq!(reassembler.push(&chunk(2, 3, b"ghi")).unwrap(), None);
+        assert_eq!(reassembler.push(&chunk(0, 3, b"abc")).unwrap(), None);
+        // Duplicates are ignored
+        assert_eq!(reassembler.push(&chunk(2, 3, b"ghi")).unwrap(), None);
+        assert_eq!(reassembler.received(), 2);
+        assert_eq!(reassembler.missing(), [1]);
+
+        assert_eq!(
+            reassembler.push(&chunk(1, 3, b"def")).unwrap().as_deref(),
+            Some(&b"abcdefghi"[..])
+        );
+        assert_eq!(reassembler.total(), 0);
+
+        assert!(reassembler.push(&chunk(3, 3, b"")).is_err());
+        assert!(reassembler.push(&[0, 0]).is_err());
+    }
+}
@@ -0,0 +1,231 @@
+//! Serde support for loading ggwave configuration from files
+//!
+//! The raw bindgen types cannot implement `Serialize`/`Deserialize`, so this
+//! module provides mirror types with readable field names and string names for
+//! protocols and sample formats, convertible to and from the raw types.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Error, GGWave, Parameters, ProtocolId, SampleFormat, protocols, sample_formats};
+
+/// A protocol id that serializes as its name, e.g. `"audible_fast"`
+///
+/// # Examples
+///
+/// ```
+/// use ggwave_rs::{ProtocolDef, protocols};
+///
+/// let protocol: ProtocolDef = "ultrasound_fast".parse().unwrap();
+/// assert_eq!(protocol.0, protocols::ULTRASOUND_FAST);
+/// assert_eq!(protocol.to_string(), "ultrasound_fast");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProtocolDef(pub ProtocolId);
+
+impl From<ProtocolId> for ProtocolDef {
+    fn from(protocol_id: ProtocolId) -> Self {
+        Self(protocol_id)
+    }
+}
+
+impl From<ProtocolDef> for ProtocolId {
+    fn from(protocol: ProtocolDef) -> Self {
+        protocol.0
+    }
+}
+
+impl fmt::Display for ProtocolDef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(protocols::name(self.0))
+    }
+}
+
+impl FromStr for ProtocolDef {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        protocols::from_name(s)
+            .map(Self)
+            .ok_or(Error::InvalidParameter("Unknown protocol name"))
+    }
+}
+
+impl Serialize for ProtocolDef {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ProtocolDef {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse()
+            .map_err(|_| D::Error::custom(format!("unknown protocol `{name}`")))
+    }
+}
+
+/// A sample format that serializes as its name, e.g. `"f32"`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SampleFormatDef(pub SampleFormat);
+
+impl From<SampleFormat> for SampleFormatDef {
+    fn from(format: SampleFormat) -> Self {
+        Self(format)
+    }
+}
+
+impl From<SampleFormatDef> for SampleFormat {
+    fn from(format: SampleFormatDef) -> Self {
+        format.0
+    }
+}
+
+impl fmt::Display for SampleFormatDef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(sample_formats::name(self.0))
+    }
+}
+
+impl FromStr for SampleFormatDef {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        sample_formats::NAMES
+            .iter()
+            .find(|(_, name)| name.eq_ignore_ascii_case(s))
+            .map(|(format, _)| Self(*format))
+            .ok_or(Error::InvalidSampleFormat)
+    }
+}
+
+impl Serialize for SampleFormatDef {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for SampleFormatDef {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse()
+            .map_err(|_| D::Error::custom(format!("unknown sample format `{name}`")))
+    }
+}
+
+/// Serializable mirror of [`Parameters`]
+///
+/// Missing fields take the ggwave defaults, so a config file only needs the
+/// values it changes.
+///
+/// # Examples
+///
+/// ```
+/// use ggwave_rs::{GGWave, ParametersDef, Parameters};
+///
+/// let def = ParametersDef {
+///     sample_rate: 44100.0,
+///     ..ParametersDef::default()
+/// };
+/// let params: Parameters = def.into();
+/// let ggwave = GGWave::new_with_params(params).expect("Failed to initialize GGWave");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParametersDef {
+    /// Fixed payload length in bytes, or 0 or less for variable-length payloads
+    pub payload_length: i32,
+    /// Sample rate of the audio passed to the decoder in Hz
+    pub input_sample_rate: f32,
+    /// Sample rate of the audio returned by the encoder in Hz
+    pub output_sample_rate: f32,
+    /// Processing sample rate in Hz
+    pub sample_rate: f32,
+    /// Number of samples per analysis frame
+    pub samples_per_frame: i32,
+    /// Threshold for detecting the sound markers
+    pub sound_marker_threshold: f32,
+    /// Sample format of the audio passed to the decoder
+    pub input_sample_format: SampleFormatDef,
+    /// Sample format of the audio returned by the encoder
+    pub output_sample_format: SampleFormatDef,
+    /// Operating mode flags, see [`operating_modes`](crate::operating_modes)
+    pub operating_mode: i32,
+}
+
+impl Default for ParametersDef {
+    fn default() -> Self {
+        GGWave::default_parameters().into()
+    }
+}
+
+impl From<Parameters> for ParametersDef {
+    fn from(params: Parameters) -> Self {
+        Self {
+            payload_length: params.payloadLength,
+            input_sample_rate: params.sampleRateInp,
+            output_sample_rate: params.sampleRateOut,
+            sample_rate: params.sampleRate,
+            samples_per_frame: params.samplesPerFrame,
+            sound_marker_threshold: params.soundMarkerThreshold,
+            input_sample_format: params.sampleFormatInp.into(),
+            output_sample_format: params.sampleFormatOut.into(),
+            operating_mode: params.operatingMode,
+        }
+    }
+}
+
+impl From<ParametersDef> for Parameters {
+    fn from(def: ParametersDef) -> Self {
+        Self {
+            payloadLength: def.payload_length,
+            sampleRateInp: def.input_sample_rate,
+            sampleRateOut: def.output_sample_rate,
+            sampleRate: def.sample_rate,
+            samplesPerFrame: def.samples_per_frame,
+            soundMarkerThreshold: def.sound_marker_threshold,
+            sampleFormatInp: def.input_sample_format.into(),
+            sampleFormatOut: def.output_sample_format.into(),
+            operatingMode: def.operating_mode,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serde_round_trip() {
+        let def = ParametersDef::from(Parameters::receiver_default());
+        let json = serde_json::to_string(&def).unwrap();
+        assert!(json.contains(r#""input_sample_format":"f32""#));
+        assert!(json.contains(r#""output_sample_format":"i16""#));
+        assert_eq!(serde_json::from_str::<ParametersDef>(&json).unwrap(), def);
+
+        let partial: ParametersDef = serde_json::from_str(r#"{"sample_rate": 44100.0}"#).unwrap();
+        assert_eq!(partial.sample_rate, 44100.0);
+        assert_eq!(
+            partial.samples_per_frame,
+            GGWave::default_parameters().samplesPerFrame
+        );
+
+        let ids: Vec<ProtocolDef> =
+            serde_json::from_str(r#"["audible_fast", "custom_3"]"#).unwrap();
+        assert_eq!(
+            ids,
+            [
+                ProtocolDef(protocols::AUDIBLE_FAST),
+                ProtocolDef(protocols::CUSTOM_3)
+            ]
+        );
+        assert_eq!(
+            serde_json::to_string(&ids).unwrap(),
+            r#"["audible_fast","custom_3"]"#
+        );
+        assert!(serde_json::from_str::<ProtocolDef>(r#""loud""#).is_err());
+    }
+}
@@ -0,0 +1,229 @@
+//! Conversions between the raw sample formats used by ggwave
+//!
+//! ggwave reads and writes audio as raw bytes whose layout is given by a
+//! `SampleFormat`. These helpers move samples between those layouts and
+//! normalized `f32` values in the range [-1.0, 1.0].
+
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::{Cursor, Read};
+
+#[cfg(feature = "std")]
+use hound::{WavReader, WavSpec, WavWriter};
+
+use crate::{Error, Result, SampleFormat, sample_formats};
+
+/// Size in bytes of a single sample in the given format
+///
+/// Like [`sample_formats::bytes_per_sample`], but an unknown format is an error.
+pub(crate) fn sample_size(format: SampleFormat) -> Result<usize> {
+    match sample_formats::bytes_per_sample(format) {
+        0 => Err(Error::InvalidSampleFormat),
+        size => Ok(size),
+    }
+}
+
+/// Size in bytes of a single sample in the given format, checking that
+/// `bytes` holds a whole number of samples
+///
+/// A partial trailing sample would otherwise be dropped, or read together
+/// with the bytes of its neighbor, without notice.
+pub(crate) fn aligned_sample_size(bytes: &[u8], format: SampleFormat) -> Result<usize> {
+    let size = sample_size(format)?;
+    if !bytes.len().is_multiple_of(size) {
+        return Err(Error::InvalidParameter(
+            "waveform length not aligned to sample size",
+        ));
+    }
+    Ok(size)
+}
+
+/// Average interleaved multi-channel samples into a mono signal
+///
+/// A trailing partial frame, where `samples.len()` is not a multiple of
+/// `channels`, is dropped. Mono input is returned unchanged, and an empty
+/// vector is returned when `channels` is 0.
+///
+/// # Examples
+///
+/// ```
+/// use ggwave_rs::downmix_to_mono;
+///
+/// let stereo = [0.5, 0.25, -0.5, -0.25, 1.0];
+/// assert_eq!(downmix_to_mono(&stereo, 2), [0.375, -0.375]);
+/// ```
+pub fn downmix_to_mono(samples: &[f32], channels: u16) -> Vec<f32> {
+    match channels {
+        0 => Vec::new(),
+        1 => samples.to_vec(),
+        _ => samples
+            .chunks_exact(channels as usize)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect(),
+    }
+}
+
+/// Convert raw bytes in the given format to normalized `f32` samples
+///
+/// Fails with `Error::InvalidParameter` if `bytes` ends in a partial sample.
+pub(crate) fn bytes_to_f32(bytes: &[u8], format: SampleFormat) -> Result<Vec<f32>> {
+    let size = aligned_sample_size(bytes, format)?;
+    let samples = bytes.chunks_exact(size).map(|b| match format {
+        sample_formats::U8 => (b[0] as f32 - 128.0) / 128.0,
+        sample_formats::I8 => b[0] as i8 as f32 / 128.0,
+        sample_formats::U16 => (u16::from_ne_bytes([b[0], b[1]]) as f32 - 32768.0) / 32768.0,
+        sample_formats::I16 => i16::from_ne_bytes([b[0], b[1]]) as f32 / 32768.0,
+        _ => f32::from_ne_bytes([b[0], b[1], b[2], b[3]]),
+    });
+
+    Ok(samples.collect())
+}
+
+/// Convert normalized `f32` samples to raw bytes in the given format
+///
+/// Samples are clamped to [-1.0, 1.0] before conversion to integer formats.
+pub(crate) fn f32_to_bytes(samples: &[f32], format: SampleFormat) -> Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(samples.len() * sample_size(format)?);
+    write_f32_bytes(samples, format, &mut bytes)?;
+    Ok(bytes)
+}
+
+/// Convert normalized `f32` samples to raw bytes, reusing the allocation of `bytes`
+pub(crate) fn write_f32_bytes(
+    samples: &[f32],
+    format: SampleFormat,
+    bytes: &mut Vec<u8>,
+) -> Result<()> {
+    sample_size(format)?;
+    bytes.clear();
+
+    for &sample in samples {
+        let clamped = sample.clamp(-1.0, 1.0);
+        match format {
+            sample_formats::U8 => bytes.push((clamped * 127.0 + 128.0) as u8),
+            sample_formats::I8 => bytes.push((clamped * 127.0) as i8 as u8),
+            sample_formats::U16 => {
+                bytes.extend_from_slice(&((clamped * 32767.0 + 32768.0) as u16).to_ne_bytes())
+            }
+            sample_formats::I16 => {
+                bytes.extend_from_slice(&((clamped * 32767.0) as i16).to_ne_bytes())
+            }
+            _ => bytes.extend_from_slice(&sample.to_ne_bytes()),
+        }
+    }
+
+    Ok(())
+}
+
+/// Convert raw bytes from one sample format to another
+///
+/// Every conversion between formats goes through normalized `f32`, so all
+/// paths share the same math: integer samples are divided by 128 or 32768
+/// (after removing the offset of unsigned formats), and `f32` samples are
+/// clamped to [-1.0, 1.0] and multiplied by 127 or 32767. Converting to the
+/// same format copies the bytes unchanged.
+pub(crate) fn convert_samples(
+    bytes: &[u8],
+    from: SampleFormat,
+    to: SampleFormat,
+) -> Result<Vec<u8>> {
+    if from == to {
+        aligned_sample_size(bytes, from)?;
+        return Ok(bytes.to_vec());
+    }
+
+    f32_to_bytes(&bytes_to_f32(bytes, from)?, to)
+}
+
+/// Read all samples of a WAV stream as normalized `f32` values
+#[cfg(feature = "std")]
+pub(crate) fn read_wav_samples<R: Read>(reader: &mut WavReader<R>) -> Result<Vec<f32>> {
+    wav_samples(reader)?.collect()
+}
+
+/// Iterate over the samples of a WAV stream as normalized `f32` values
+///
+/// Samples are read from the stream as the iterator is advanced.
+#[cfg(feature = "std")]
+pub(crate) fn wav_samples<R: Read>(
+    reader: &mut WavReader<R>,
+) -> Result<Box<dyn Iterator<Item = Result<f32>> + '_>> {
+    let spec = reader.spec();
+
+    match (spec.sample_format, spec.bits_per_sample) {
+        (hound::SampleFormat::Float, 32) => Ok(Box::new(
+            reader.samples::<f32>().map(|s| s.map_err(Error::from)),
+        )),
+        (hound::SampleFormat::Int, bits @ 1..=32) => {
+            let scale = (1u64 << (bits - 1)) as f32;
+            Ok(Box::new(reader.samples::<i32>().map(move |s| {
+                s.map(|v| v as f32 / scale).map_err(Error::from)
+            })))
+        }
+        _ => Err(Error::InvalidSampleFormat),
+    }
+}
+
+/// Write raw bytes in the given format to an in-memory mono WAV file
+///
+/// Backs [`crate::GGWave::raw_to_wav`], which documents how each format is stored.
+#[cfg(feature = "std")]
+pub(crate) fn write_wav(
+    raw_data: &[u8],
+    format: SampleFormat,
+    sample_rate: f32,
+) -> Result<Vec<u8>> {
+    let size = aligned_sample_size(raw_data, format)?;
+    // Float samples are stored as 16-bit integers
+    let bits_per_sample = if size == 1 { 8 } else { 16 };
+
+    // Create WAV spec
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate: sample_rate as u32,
+        bits_per_sample,
+        sample_format: hound::SampleFormat::Int,
+    };
+
+    let mut buffer = Vec::new();
+    let mut writer =
+        WavWriter::new(Cursor::new(&mut buffer), spec).map_err(Error::WavWriteFailed)?;
+
+    match format {
+        // Unsigned 8-bit, which is what 8-bit WAV files store
+        sample_formats::U8 => {
+            for &sample in raw_data {
+                writer.write_sample((sample ^ 0x80) as i8)?;
+            }
+        }
+        // Signed 8-bit, stored offset by 128 by the writer
+        sample_formats::I8 => {
+            for &sample in raw_data {
+                writer.write_sample(sample as i8)?;
+            }
+        }
+        // Unsigned 16-bit, shifted to signed
+        sample_formats::U16 => {
+            for bytes in raw_data.chunks_exact(size) {
+                let sample = u16::from_ne_bytes([bytes[0], bytes[1]]);
+                writer.write_sample((sample ^ 0x8000) as i16)?;
+            }
+        }
+        // Signed 16-bit
+        sample_formats::I16 => {
+            for bytes in raw_data.chunks_exact(size) {
+                writer.write_sample(i16::from_ne_bytes([bytes[0], bytes[1]]))?;
+            }
+        }
+        // Float32, scaled to signed 16-bit
+        _ => {
+            for bytes in raw_data.chunks_exact(size) {
+                let sample = f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                writer.write_sample((sample.clamp(-1.0, 1.0) * 32767.0) as i16)?;
+            }
+        }
+    }
+
+    writer.finalize()?;
+    Ok(buffer)
+}
@@ -0,0 +1,175 @@
+//! Definitions for the custom protocol slots
+//!
+//! ggwave reserves ten protocol ids, `CUSTOM_0` to `CUSTOM_9`, that are left
+//! undefined and disabled. A [`CustomProtocol`] describes the tone layout of
+//! such a protocol and is installed with
+//! [`GGWave::register_custom_protocol`](crate::GGWave::register_custom_protocol).
+
+use crate::{Error, Parameters, ProtocolId, Result, protocols};
+
+/// Number of frequency bins used by each tone group (one per 4-bit nibble)
+const BINS_PER_TONE: i32 = 16;
+
+/// Tone layout of a custom protocol
+///
+/// The defaults match `AUDIBLE_NORMAL`. Each transmitted byte is split into
+/// two 4-bit nibbles, each sent as one of 16 tones, so a protocol sending
+/// `bytes_per_tx` bytes per chunk uses `2 * bytes_per_tx` groups of 16
+/// frequency bins above `freq_start` (half as many for mono-tone protocols).
+///
+/// # Examples
+///
+/// ```
+/// use ggwave_rs::CustomProtocol;
+///
+/// // Start at bin 64 (3 kHz at 48 kHz / 1024 samples per frame) and send
+/// // 3 bytes every 6 frames
+/// let protocol = CustomProtocol::new()
+///     .freq_start(64)
+///     .frames_per_tx(6)
+///     .bytes_per_tx(3);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CustomProtocol {
+    freq_start: i32,
+    frames_per_tx: i32,
+    bytes_per_tx: i32,
+    mono_tone: bool,
+}
+
+impl CustomProtocol {
+    /// Create a protocol definition with the layout of `AUDIBLE_NORMAL`
+    pub fn new() -> Self {
+        Self {
+            freq_start: 40,
+            frames_per_tx: 9,
+            bytes_per_tx: 3,
+            mono_tone: false,
+        }
+    }
+
+    /// Set the FFT bin of the lowest frequency
+    ///
+    /// The frequency in Hz is `bin * sampleRate / samplesPerFrame`.
+    pub fn freq_start(mut self, bin: i32) -> Self {
+        self.freq_start = bin;
+        self
+    }
+
+    /// Set the number of frames each chunk of data is transmitted for
+    ///
+    /// More frames per chunk make the protocol slower but more robust.
+    pub fn frames_per_tx(mut self, frames: i32) -> Self {
+        self.frames_per_tx = frames;
+        self
+    }
+
+    /// Set the number of bytes sent in each chunk
+    pub fn bytes_per_tx(mut self, bytes: i32) -> Self {
+        self.bytes_per_tx = bytes;
+        self
+    }
+
+    /// Send a single tone at a time, like the `MT_*` protocols
+    pub fn mono_tone(mut self, mono_tone: bool) -> Self {
+        self.mono_tone = mono_tone;
+        self
+    }
+
+    /// Number of simultaneous tones
+    pub fn tones(&self) -> i32 {
+        2 * self.bytes_per_tx / self.extra()
+    }
+
+    /// Value of the `extra` field of the C++ protocol definition
+    pub(crate) fn extra(&self) -> i32 {
+        if self.mono_tone { 2 } else { 1 }
+    }
+
+    pub(crate) fn raw_freq_start(&self) -> i32 {
+        self.freq_start
+    }
+
+    pub(crate) fn raw_frames_per_tx(&self) -> i32 {
+        self.frames_per_tx
+    }
+
+    pub(crate) fn raw_bytes_per_tx(&self) -> i32 {
+        self.bytes_per_tx
+    }
+
+    /// Check the definition against a protocol id and instance parameters
+    pub(crate) fn validate(&self, protocol_id: ProtocolId, params: &Parameters) -> Result<()> {
+        if !(protocols::CUSTOM_0..=protocols::CUSTOM_9).contains(&protocol_id) {
+            return Err(Error::InvalidParameter(
+                "Protocol id must be between CUSTOM_0 and CUSTOM_9",
+            ));
+        }
+
+        if self.freq_start < 1 {
+            return Err(Error::InvalidParameter(
+                "Frequency start bin must be at least 1",
+            ));
+        }
+
+        if self.frames_per_tx < 1 {
+            return Err(Error::InvalidParameter(
+                "Frames per chunk must be at least 1",
+            ));
+        }
+
+        if self.bytes_per_tx < 1 || self.tones() < 1 {
+            return Err(Error::InvalidParameter(
+                "Bytes per chunk must be at least 1",
+            ));
+        }
+
+        if self.freq_start + self.tones() * BINS_PER_TONE > params.samplesPerFrame / 2 {
+            return Err(Error::InvalidParameter(
+                "Custom protocol tones exceed the Nyquist frequency",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for CustomProtocol {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GGWave;
+
+    #[test]
+    fn test_validate() {
+        let params = GGWave::default_parameters();
+        let protocol = CustomProtocol::new().freq_start(64).frames_per_tx(6);
+
+        assert!(protocol.validate(protocols::CUSTOM_0, &params).is_ok());
+        assert!(protocol.validate(protocols::CUSTOM_9, &params).is_ok());
+        assert!(
+            protocol
+                .validate(protocols::AUDIBLE_NORMAL, &params)
+                .is_err()
+        );
+        assert!(protocol.validate(protocols::COUNT, &params).is_err());
+
+        assert!(
+            protocol
+                .frames_per_tx(0)
+                .validate(protocols::CUSTOM_0, &params)
+                .is_err()
+        );
+        assert!(
+            protocol
+                .freq_start(params.samplesPerFrame / 2)
+                .validate(protocols::CUSTOM_0, &params)
+                .is_err()
+        );
+    }
+}
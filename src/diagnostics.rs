@@ -0,0 +1,175 @@
+//! Hardware self-test based on a frequency sweep
+//!
+//! A stepped sine sweep is played through the device under test and captured
+//! back. The level of each step in the capture gives the frequency response of
+//! the playback/capture chain, from which the usable frequency range and the
+//! protocols whose tones fit inside it are derived.
+
+use std::f32::consts::PI;
+use std::ops::RangeInclusive;
+
+use crate::{Parameters, ProtocolId, protocols, signal};
+
+/// Spacing between two probe frequencies of the sweep in Hz
+const PROBE_STEP_HZ: f32 = 250.0;
+
+/// Fraction of the Nyquist frequency covered by the sweep
+const SWEEP_NYQUIST_FRACTION: f32 = 0.95;
+
+/// Number of frames each probe tone lasts
+const TONE_FRAMES: usize = 4;
+
+/// Number of silent frames between probe tones
+const GAP_FRAMES: usize = 1;
+
+/// Amplitude of the probe tones
+const SWEEP_AMPLITUDE: f32 = 0.5;
+
+/// Number of samples faded in and out at each tone edge to avoid clicks
+const FADE_SAMPLES: usize = 64;
+
+/// Maximum attenuation relative to the loudest probe for a frequency to be usable
+const USABLE_DROP_DB: f32 = 20.0;
+
+/// Minimum absolute level for a frequency to be usable
+const MIN_USABLE_DB: f32 = -60.0;
+
+/// Level of a single probe frequency in the captured sweep
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrequencyResponse {
+    /// Probe frequency in Hz
+    pub frequency: f32,
+    /// Captured level relative to the played level in dB
+    pub level_db: f32,
+}
+
+/// Result of [`GGWave::assess_device`](crate::GGWave::assess_device)
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceReport {
+    /// Measured level for each probe frequency, in ascending frequency order
+    pub response: Vec<FrequencyResponse>,
+    /// Contiguous frequency range in Hz around the loudest probe that stays
+    /// within 20 dB of it, or `None` if nothing was captured
+    pub usable_range: Option<RangeInclusive<f32>>,
+    /// Attenuation in dB per octave above the usable range, or `None` if the
+    /// range extends to the top of the sweep
+    pub rolloff_db_per_octave: Option<f32>,
+    /// Built-in protocols whose whole frequency band lies in the usable range
+    pub supported_protocols: Vec<ProtocolId>,
+}
+
+impl DeviceReport {
+    /// Check whether the device can carry the given protocol
+    pub fn supports(&self, protocol_id: ProtocolId) -> bool {
+        self.supported_protocols.contains(&protocol_id)
+    }
+}
+
+/// Probe frequencies covered by the sweep for the given parameters
+fn probe_frequencies(params: &Parameters) -> Vec<f32> {
+    let nyquist = params.sampleRateOut.min(params.sampleRateInp) / 2.0;
+    let max_frequency = nyquist * SWEEP_NYQUIST_FRACTION;
+
+    (1..)
+        .map(|step| step as f32 * PROBE_STEP_HZ)
+        .take_while(|&frequency| frequency <= max_frequency)
+        .collect()
+}
+
+/// Generate the stepped sine sweep at the output sample rate
+pub(crate) fn sweep(params: &Parameters) -> Vec<f32> {
+    let frame_len = params.samplesPerFrame.max(1) as usize;
+    let tone_len = TONE_FRAMES * frame_len;
+    let gap_len = GAP_FRAMES * frame_len;
+    let probes = probe_frequencies(params);
+
+    let mut samples = Vec::with_capacity(gap_len + probes.len() * (tone_len + gap_len));
+    samples.resize(gap_len, 0.0);
+
+    for frequency in probes {
+        let step = 2.0 * PI * frequency / params.sampleRateOut;
+        samples.extend((0..tone_len).map(|i| {
+            let edge = i.min(tone_len - 1 - i);
+            let fade = (edge as f32 / FADE_SAMPLES as f32).min(1.0);
+            SWEEP_AMPLITUDE * fade * (step * i as f32).sin()
+        }));
+        samples.resize(samples.len() + gap_len, 0.0);
+    }
+
+    samples
+}
+
+/// Measure the captured sweep and derive the device report
+pub(crate) fn analyze(params: &Parameters, captured: &[f32]) -> DeviceReport {
+    let frame_len = params.samplesPerFrame.max(1) as usize;
+
+    // Each tone spans several frames, so the loudest frame for a probe lies
+    // fully inside its tone regardless of the capture latency
+    let response: Vec<FrequencyResponse> = probe_frequencies(params)
+        .into_iter()
+        .map(|frequency| {
+            let amplitude = captured
+                .chunks_exact(frame_len)
+                .map(|frame| signal::goertzel(frame, frequency, params.sampleRateInp))
+                .fold(0.0f32, f32::max);
+
+            FrequencyResponse {
+                frequency,
+                level_db: 20.0 * (amplitude / SWEEP_AMPLITUDE).max(1e-9).log10(),
+            }
+        })
+        .collect();
+
+    let usable = usable_span(&response);
+    let usable_range = usable.map(|(low, high)| response[low].frequency..=response[high].frequency);
+
+    let rolloff_db_per_octave = usable.and_then(|(_, high)| {
+        let edge = response[high];
+        let top = *response.last()?;
+        (top.frequency > edge.frequency)
+            .then(|| (edge.level_db - top.level_db) / (top.frequency / edge.frequency).log2())
+    });
+
+    let supported_protocols = (0..protocols::COUNT)
+        .filter(|&protocol_id| {
+            let (Some(band), Some(range)) = (protocols::band(protocol_id, params), &usable_range)
+            else {
+                return false;
+            };
+            range.contains(&band.start) && range.contains(&band.end)
+        })
+        .collect();
+
+    DeviceReport {
+        response,
+        usable_range,
+        rolloff_db_per_octave,
+        supported_protocols,
+    }
+}
+
+/// Indices of the contiguous usable probes around the loudest one
+fn usable_span(response: &[FrequencyResponse]) -> Option<(usize, usize)> {
+    let (peak, peak_level) = response
+        .iter()
+        .enumerate()
+        .map(|(i, r)| (i, r.level_db))
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+
+    let threshold = (peak_level - USABLE_DROP_DB).max(MIN_USABLE_DB);
+    if peak_level < threshold {
+        return None;
+    }
+
+    let is_usable = |r: &FrequencyResponse| r.level_db >= threshold;
+    let low = response[..peak]
+        .iter()
+        .rposition(|r| !is_usable(r))
+        .map_or(0, |i| i + 1);
+    let high = response[peak..]
+        .iter()
+        .position(|r| !is_usable(r))
+        .map_or(response.len() - 1, |i| peak + i - 1);
+
+    Some((low, high))
+}
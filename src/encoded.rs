@@ -0,0 +1,313 @@
+//! Encoded audio that carries its own format
+//!
+//! The waveform returned by [`GGWave::encode`] is raw bytes whose layout
+//! depends on the instance that produced it. An [`EncodedAudio`] keeps the
+//! sample format and rate next to the bytes, so it can be converted or saved
+//! without access to that instance.
+
+use alloc::vec::Vec;
+
+use crate::{Error, GGWave, Result, SampleFormat, convert, sample_formats};
+
+/// A waveform together with the format needed to interpret it
+///
+/// Returned by [`GGWave::encode_full`].
+///
+/// # Examples
+///
+/// ```
+/// use ggwave_rs::{GGWave, protocols};
+///
+/// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+/// let audio = ggwave
+///     .encode_full("Hello, World!", protocols::AUDIBLE_FAST, 50)
+///     .expect("Failed to encode text");
+///
+/// let samples = audio.as_f32().expect("Failed to convert samples");
+/// assert_eq!(samples.len(), audio.sample_count);
+/// println!("{:.2} s at {} Hz", audio.duration_secs(), audio.sample_rate);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncodedAudio {
+    /// The raw waveform in `format`
+    pub bytes: Vec<u8>,
+    /// Sample rate of the waveform in Hz
+    pub sample_rate: f32,
+    /// Sample format of `bytes`
+    pub format: SampleFormat,
+    /// Number of samples in `bytes`
+    pub sample_count: usize,
+}
+
+impl EncodedAudio {
+    /// Wrap a waveform produced by `ggwave`
+    pub(crate) fn new(ggwave: &GGWave, bytes: Vec<u8>) -> Result<Self> {
+        let params = ggwave.parameters();
+        let sample_count = bytes.len() / convert::sample_size(params.sampleFormatOut)?;
+
+        Ok(Self {
+            bytes,
+            sample_rate: params.sampleRateOut,
+            format: params.sampleFormatOut,
+            sample_count,
+        })
+    }
+
+    /// Duration of the waveform in seconds
+    pub fn duration_secs(&self) -> f32 {
+        self.sample_count as f32 / self.sample_rate
+    }
+
+    /// Convert the waveform to normalized `f32` samples
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing samples in the range [-1.0, 1.0],
+    /// `Error::InvalidSampleFormat` if the format is unknown, or
+    /// `Error::InvalidParameter` if `bytes` ends in a partial sample
+    pub fn as_f32(&self) -> Result<Vec<f32>> {
+        convert::bytes_to_f32(&self.bytes, self.format)
+    }
+
+    /// Convert the waveform to signed 16-bit samples
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the samples, `Error::InvalidSampleFormat` if the
+    /// format is unknown, or `Error::InvalidParameter` if `bytes` ends in a
+    /// partial sample
+    pub fn as_i16(&self) -> Result<Vec<i16>> {
+        let bytes = convert::convert_samples(&self.bytes, self.format, sample_formats::I16)?;
+        Ok(bytes
+            .chunks_exact(2)
+            .map(|b| i16::from_ne_bytes([b[0], b[1]]))
+            .collect())
+    }
+
+    /// Split the waveform into chunks of normalized `f32` samples
+    ///
+    /// Each chunk holds `frames_per_chunk` samples, except the last one which
+    /// holds the remainder, so the chunks can be pushed straight into the
+    /// buffer of an output callback.
+    ///
+    /// # Arguments
+    ///
+    /// * `frames_per_chunk` - Number of samples per chunk
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing an iterator over the chunks, or
+    /// `Error::InvalidParameter` if `frames_per_chunk` is 0 or `bytes` ends in
+    /// a partial sample
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let audio = ggwave
+    ///     .encode_full("Hello, World!", protocols::AUDIBLE_FAST, 50)
+    ///     .expect("Failed to encode text");
+    ///
+    /// for chunk in audio.chunks_f32(1024).expect("Failed to split samples") {
+    ///     assert!(chunk.len() <= 1024);
+    /// }
+    /// ```
+    pub fn chunks_f32(
+        &self,
+        frames_per_chunk: usize,
+    ) -> Result<impl Iterator<Item = Vec<f32>> + '_> {
+        if frames_per_chunk == 0 {
+            return Err(Error::InvalidParameter("Chunk size must be at least 1"));
+        }
+
+        let size = convert::aligned_sample_size(&self.bytes, self.format)?;
+        Ok(self
+            .bytes
+            .chunks(frames_per_chunk * size)
+            .map(|chunk| convert::bytes_to_f32(chunk, self.format).unwrap_or_default()))
+    }
+
+    #[cfg(feature = "std")]
+    /// Convert the waveform to WAV format in memory
+    ///
+    /// The samples are stored as by [`GGWave::raw_to_wav`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a `Vec<u8>` with the WAV data
+    pub fn to_wav(&self) -> Result<Vec<u8>> {
+        convert::write_wav(&self.bytes, self.format, self.sample_rate)
+    }
+
+    #[cfg(feature = "std")]
+    /// Read the raw waveform through [`std::io::Read`]
+    pub fn into_reader(self) -> EncodedAudioReader {
+        EncodedAudioReader {
+            audio: self,
+            position: 0,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+/// Reader over the raw bytes of an [`EncodedAudio`]
+///
+/// Returned by [`GGWave::encode_reader`] and [`EncodedAudio::into_reader`].
+/// Each read continues where the previous one stopped, so the waveform can
+/// be handed to anything that consumes a [`std::io::Read`].
+///
+/// # Examples
+///
+/// ```
+/// use ggwave_rs::{GGWave, protocols};
+///
+/// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+/// let mut reader = ggwave
+///     .encode_reader("Hello, World!", protocols::AUDIBLE_FAST, 50)
+///     .expect("Failed to encode text");
+///
+/// let mut sink = Vec::new();
+/// std::io::copy(&mut reader, &mut sink).expect("Failed to copy waveform");
+/// assert_eq!(sink.len(), reader.audio().bytes.len());
+/// ```
+#[derive(Debug, Clone)]
+pub struct EncodedAudioReader {
+    audio: EncodedAudio,
+    position: usize,
+}
+
+#[cfg(feature = "std")]
+impl EncodedAudioReader {
+    /// The audio being read, with the format needed to interpret its bytes
+    pub fn audio(&self) -> &EncodedAudio {
+        &self.audio
+    }
+
+    /// Number of bytes read so far
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Number of bytes left to read
+    pub fn remaining(&self) -> usize {
+        self.audio.bytes.len() - self.position
+    }
+
+    /// Take back the audio, regardless of how much of it was read
+    pub fn into_inner(self) -> EncodedAudio {
+        self.audio
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::io::Read for EncodedAudioReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let rest = &self.audio.bytes[self.position..];
+        let len = rest.len().min(buf.len());
+        buf[..len].copy_from_slice(&rest[..len]);
+        self.position += len;
+        Ok(len)
+    }
+}
+
+impl From<EncodedAudio> for Vec<u8> {
+    fn from(audio: EncodedAudio) -> Self {
+        audio.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocols;
+
+    #[test]
+    fn test_encode_full() {
+        let _serial = crate::tests::serial();
+        let ggwave = GGWave::builder()
+            .output_sample_format(sample_formats::F32)
+            .build()
+            .expect("Failed to initialize GGWave");
+
+        let audio = ggwave
+            .encode_full("Self-describing", protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text");
+        let waveform = ggwave
+            .encode("Self-describing", protocols::AUDIBLE_FAST, 50)
+            .unwrap();
+
+        assert_eq!(audio.bytes, waveform);
+        assert_eq!(audio.format, sample_formats::F32);
+        assert_eq!(audio.sample_rate, ggwave.parameters().sampleRateOut);
+        assert_eq!(audio.sample_count, waveform.len() / 4);
+
+        let samples = audio.as_f32().unwrap();
+        assert_eq!(samples.len(), audio.sample_count);
+        let ints = audio.as_i16().unwrap();
+        assert_eq!(ints.len(), audio.sample_count);
+        assert_eq!(ints[100], (samples[100].clamp(-1.0, 1.0) * 32767.0) as i16);
+
+        #[cfg(feature = "std")]
+        assert_eq!(
+            audio.to_wav().unwrap(),
+            ggwave.raw_to_wav(&waveform).unwrap()
+        );
+        assert_eq!(Vec::from(audio), waveform);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_encode_reader() {
+        use std::io::Read;
+
+        let _serial = crate::tests::serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let waveform = ggwave
+            .encode("Streamed reads", protocols::AUDIBLE_FAST, 50)
+            .unwrap();
+        let mut reader = ggwave
+            .encode_reader("Streamed reads", protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text");
+        assert_eq!(reader.remaining(), waveform.len());
+
+        // A small buffer takes many reads, each continuing the last
+        let mut first = [0u8; 100];
+        assert_eq!(reader.read(&mut first).unwrap(), 100);
+        assert_eq!(reader.position(), 100);
+        assert_eq!(&first[..], &waveform[..100]);
+
+        let mut rest = Vec::new();
+        std::io::copy(&mut reader, &mut rest).unwrap();
+        assert_eq!([&first[..], &rest[..]].concat(), waveform);
+        assert_eq!(reader.remaining(), 0);
+        assert_eq!(reader.read(&mut first).unwrap(), 0);
+        assert_eq!(reader.into_inner().bytes, waveform);
+    }
+
+    #[test]
+    fn test_chunks_f32() {
+        let _serial = crate::tests::serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let audio = ggwave
+            .encode_full("Chunked playback", protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text");
+        let samples = audio.as_f32().unwrap();
+
+        // 1000 does not divide the sample count, so the last chunk is short
+        assert!(!audio.sample_count.is_multiple_of(1000));
+        let chunks: Vec<Vec<f32>> = audio.chunks_f32(1000).unwrap().collect();
+        assert!(chunks.iter().all(|chunk| chunk.len() <= 1000));
+        assert_eq!(
+            chunks.iter().map(Vec::len).sum::<usize>(),
+            audio.sample_count
+        );
+        assert_eq!(chunks.concat(), samples);
+
+        assert!(matches!(
+            audio.chunks_f32(0).err(),
+            Some(Error::InvalidParameter(_))
+        ));
+    }
+}
@@ -0,0 +1,196 @@
+//! Repeated encoding into a reusable buffer
+//!
+//! [`GGWave::encode`] allocates a new waveform on every call. Broadcasting
+//! many messages in a loop only needs one buffer, large enough for the longest
+//! of them, which an [`Encoder`] keeps between calls.
+
+use alloc::vec::Vec;
+
+use crate::{GGWave, ProtocolId, Result};
+
+/// Encoder that owns its [`GGWave`] instance and a growable waveform buffer
+///
+/// The buffer only grows when a waveform needs more room than any previous
+/// one, so encoding messages of similar length does not allocate after the
+/// first call.
+///
+/// # Examples
+///
+/// ```
+/// use ggwave_rs::{Encoder, GGWave, protocols};
+///
+/// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+/// let mut encoder = Encoder::new(ggwave);
+///
+/// for text in ["first", "second", "third"] {
+///     let waveform = encoder
+///         .encode_reuse(text, protocols::AUDIBLE_FAST, 50)
+///         .expect("Failed to encode text");
+///     // Play or send `waveform` before the next message overwrites it
+/// }
+/// ```
+pub struct Encoder {
+    ggwave: GGWave,
+    buffer: Vec<u8>,
+}
+
+impl Encoder {
+    /// Create an encoder that encodes with `ggwave`
+    ///
+    /// # Arguments
+    ///
+    /// * `ggwave` - The instance to encode with; it must be able to transmit
+    pub fn new(ggwave: GGWave) -> Self {
+        Self {
+            ggwave,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// The instance used for encoding
+    pub fn ggwave(&self) -> &GGWave {
+        &self.ggwave
+    }
+
+    /// Take back the instance, dropping the buffer
+    pub fn into_inner(self) -> GGWave {
+        self.ggwave
+    }
+
+    /// Size in bytes of the buffer currently kept for waveforms
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    /// Encode text into the internal buffer
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to encode
+    /// * `protocol_id` - The protocol to use for encoding
+    /// * `volume` - The volume of the encoded audio (0-100)
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the encoded audio data, valid until the next call
+    pub fn encode_reuse(
+        &mut self,
+        text: &str,
+        protocol_id: ProtocolId,
+        volume: i32,
+    ) -> Result<&[u8]> {
+        let payload = text.as_bytes();
+        let size = self
+            .ggwave
+            .payload_buffer_size(payload, protocol_id, volume)?;
+
+        // Resizing within the capacity does not allocate
+        self.buffer.resize(size, 0);
+        let written =
+            self.ggwave
+                .encode_payload_into(payload, protocol_id, volume, &mut self.buffer)?;
+
+        Ok(&self.buffer[..written])
+    }
+
+    /// Encode text and write the waveform to a sink
+    ///
+    /// The waveform is encoded into the internal buffer and written straight
+    /// to `writer`, so a long batch of messages can be streamed to a file or
+    /// socket while holding at most one waveform in memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to encode
+    /// * `protocol_id` - The protocol to use for encoding
+    /// * `volume` - The volume of the encoded audio (0-100)
+    /// * `writer` - The sink to write the encoded audio data to
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the number of bytes written
+    #[cfg(feature = "std")]
+    pub fn encode_to_writer<W: std::io::Write>(
+        &mut self,
+        text: &str,
+        protocol_id: ProtocolId,
+        volume: i32,
+        writer: &mut W,
+    ) -> Result<usize> {
+        let waveform = self.encode_reuse(text, protocol_id, volume)?;
+        writer.write_all(waveform)?;
+        Ok(waveform.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocols;
+
+    #[test]
+    fn test_encode_reuse() {
+        let _serial = crate::tests::serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let expected = ggwave
+            .encode("Reused", protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text");
+
+        let mut encoder = Encoder::new(ggwave);
+        let first = encoder
+            .encode_reuse("Reused", protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text");
+        assert_eq!(first, &expected[..]);
+        let capacity = encoder.capacity();
+
+        // Shorter and equal-length messages fit in the existing buffer
+        for text in ["Short", "Reused", "Again!"] {
+            encoder
+                .encode_reuse(text, protocols::AUDIBLE_FAST, 50)
+                .expect("Failed to encode text");
+            assert_eq!(encoder.capacity(), capacity);
+        }
+
+        let mut buffer = vec![0u8; 1024];
+        let waveform = encoder
+            .encode_reuse("Last one", protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text")
+            .to_vec();
+        assert_eq!(
+            encoder.ggwave().decode(&waveform, &mut buffer).unwrap(),
+            "Last one"
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_encode_to_writer() {
+        let _serial = crate::tests::serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let texts = ["first", "second", "third"];
+
+        let mut expected = Vec::new();
+        for text in texts {
+            expected.extend(ggwave.encode(text, protocols::AUDIBLE_FAST, 50).unwrap());
+        }
+
+        let mut stream = Vec::new();
+        let mut total = 0;
+        for text in texts {
+            total += ggwave
+                .encode_to_writer(text, protocols::AUDIBLE_FAST, 50, &mut stream)
+                .expect("Failed to encode text");
+        }
+        assert_eq!(total, stream.len());
+        assert_eq!(stream, expected);
+
+        let mut encoder = Encoder::new(ggwave);
+        let mut stream = Vec::new();
+        for text in texts {
+            encoder
+                .encode_to_writer(text, protocols::AUDIBLE_FAST, 50, &mut stream)
+                .expect("Failed to encode text");
+        }
+        assert_eq!(stream, expected);
+    }
+}
@@ -0,0 +1,107 @@
+//! Export of encoded audio to compressed formats, and reading it back
+//!
+//! Samples are passed as normalized mono `f32` values. FLAC output is
+//! lossless and can be decoded again; Ogg Vorbis is lossy and may distort the
+//! tones enough to make the message undecodable.
+//!
+//! The FLAC encoder and decoder are pure Rust. Ogg Vorbis goes through
+//! `vorbis_rs`, which binds the C libvorbis and needs a C compiler to build.
+
+use crate::{Error, Result};
+
+/// Number of samples handed to the Vorbis encoder at once
+#[cfg(feature = "ogg")]
+const VORBIS_BLOCK_SIZE: usize = 4096;
+
+/// Encode mono samples as a 16-bit FLAC stream
+#[cfg(feature = "flac")]
+pub(crate) fn to_flac(samples: &[f32], sample_rate: u32) -> Result<Vec<u8>> {
+    use flacenc::component::BitRepr;
+    use flacenc::error::Verify;
+
+    let samples: Vec<i32> = samples
+        .iter()
+        .map(|&s| (s.clamp(-1.0, 1.0) * 32767.0) as i32)
+        .collect();
+
+    let config = flacenc::config::Encoder::default()
+        .into_verified()
+        .map_err(|(_, err)| export_error(err))?;
+    let source = flacenc::source::MemSource::from_samples(&samples, 1, 16, sample_rate as usize);
+    let stream = flacenc::encode_with_fixed_block_size(&config, source, config.block_size)
+        .map_err(debug_export_error)?;
+
+    let mut sink = flacenc::bitsink::ByteSink::new();
+    stream.write(&mut sink).map_err(export_error)?;
+    Ok(sink.as_slice().to_vec())
+}
+
+/// Encode mono samples as an Ogg Vorbis stream
+#[cfg(feature = "ogg")]
+pub(crate) fn to_ogg(samples: &[f32], sample_rate: u32) -> Result<Vec<u8>> {
+    use std::num::{NonZeroU8, NonZeroU32};
+
+    let sample_rate = NonZeroU32::new(sample_rate)
+        .ok_or(Error::InvalidParameter("Sample rate must be positive"))?;
+
+    let mut encoder = vorbis_rs::VorbisEncoderBuilder::new(sample_rate, NonZeroU8::MIN, Vec::new())
+        .map_err(export_error)?
+        .build()
+        .map_err(export_error)?;
+
+    for block in samples.chunks(VORBIS_BLOCK_SIZE) {
+        encoder.encode_audio_block([block]).map_err(export_error)?;
+    }
+
+    encoder.finish().map_err(export_error)
+}
+
+/// Read a mono FLAC stream as normalized samples and its sample rate
+#[cfg(feature = "flac")]
+pub(crate) fn from_flac(data: &[u8]) -> Result<(Vec<f32>, u32)> {
+    let mut reader = claxon::FlacReader::new(data).map_err(export_error)?;
+    let info = reader.streaminfo();
+
+    if info.channels != 1 {
+        return Err(Error::InvalidParameter("FLAC data must be mono"));
+    }
+
+    let scale = (1u32 << (info.bits_per_sample - 1)) as f32;
+    let samples: Vec<f32> = reader
+        .samples()
+        .map(|s| s.map(|s| s as f32 / scale))
+        .collect::<std::result::Result<_, _>>()
+        .map_err(export_error)?;
+
+    Ok((samples, info.sample_rate))
+}
+
+/// Read a mono Ogg Vorbis stream as normalized samples and its sample rate
+#[cfg(feature = "ogg")]
+pub(crate) fn from_ogg(data: &[u8]) -> Result<(Vec<f32>, u32)> {
+    let mut decoder = vorbis_rs::VorbisDecoder::<&[u8]>::new(data).map_err(export_error)?;
+
+    if decoder.channels().get() != 1 {
+        return Err(Error::InvalidParameter("Ogg Vorbis data must be mono"));
+    }
+
+    let sample_rate = decoder.sampling_frequency().get();
+    let mut samples = Vec::new();
+    while let Some(block) = decoder.decode_audio_block().map_err(export_error)? {
+        samples.extend_from_slice(block.samples()[0]);
+    }
+
+    Ok((samples, sample_rate))
+}
+
+/// Map an encoder or decoder error into a crate error
+fn export_error(err: impl std::fmt::Display) -> Error {
+    Error::ExportFailed(err.to_string())
+}
+
+/// Map an error that only implements `Debug`, such as flacenc's
+/// `EncodeError`, into a crate error
+#[cfg(feature = "flac")]
+fn debug_export_error(err: impl std::fmt::Debug) -> Error {
+    Error::ExportFailed(format!("{:?}", err))
+}
@@ -7,6 +7,11 @@
 //!
 //! All functions in this module are unsafe and require proper understanding of the
 //! underlying C API and memory management. Use with caution.
+//!
+//! The C library keeps its instance table and protocol tables in global state.
+//! `GGWave` and the functions in [`helpers`] serialize access to it with an
+//! internal lock; calling the raw functions directly bypasses that lock, so do
+//! not mix them with `GGWave` instances used on other threads.
 
 /// Constants for ggwave configuration and operation
 pub mod constants {
@@ -19,10 +24,19 @@ pub mod constants {
     /// Maximum length for fixed-length encoding in bytes
     pub const MAX_LENGTH_FIXED: usize = 64;
 
-    /// Default number of marker frames
+    /// Number of frames of the sound markers that open and close a
+    /// variable-length transmission
+    ///
+    /// This is a compile-time constant of the C++ library and cannot be set
+    /// per instance. Fixed-length instances send no markers at all, see
+    /// `GGWaveBuilder::fixed_payload_length`.
     pub const DEFAULT_MARKER_FRAMES: usize = 16;
 
-    /// Default encoded data offset
+    /// Number of frames between the start marker and the data of a
+    /// variable-length transmission
+    ///
+    /// Like the marker frames, this is fixed at compile time and is 0 for
+    /// fixed-length instances.
     pub const DEFAULT_ENCODED_DATA_OFFSET: usize = 3;
 
     /// Minimum allowed volume level (0-100)
@@ -34,14 +48,39 @@ pub mod constants {
     /// Default volume level for encoding
     pub const DEFAULT_VOLUME: i32 = 50;
 
+    /// Sound marker threshold set by `GGWaveBuilder::new` and
+    /// `Parameters::receiver_default`
+    ///
+    /// ggwave itself defaults to 3.0, which misses the markers of quiet
+    /// messages. 0.5 favours sensitivity: it suits clean or cabled links and
+    /// rooms without much noise, and is the lowest threshold that
+    /// `GGWave::calibrate_marker_threshold` recommends.
+    pub const DEFAULT_SOUND_MARKER_THRESHOLD: f32 = 0.5;
+
     /// Default sample rate for audio processing
     pub const DEFAULT_SAMPLE_RATE: f32 = 48000.0;
 
+    /// Lowest input, output and processing sample rate accepted by ggwave
+    pub const MIN_SAMPLE_RATE: f32 = 1000.0;
+
+    /// Highest input, output and processing sample rate accepted by ggwave
+    pub const MAX_SAMPLE_RATE: f32 = 96000.0;
+
+    /// Largest number of samples per frame accepted by ggwave
+    pub const MAX_SAMPLES_PER_FRAME: i32 = 1024;
+
     /// Minimum recommended buffer size for decoding in bytes
     pub const MIN_DECODE_BUFFER_SIZE: usize = 1024;
+
+    /// Frequency in Hz above which tones are inaudible to most adults
+    pub const INAUDIBLE_FREQUENCY: f32 = 18000.0;
 }
 
 /// Advanced options for configuring ggwave instances
+///
+/// These are not operating mode flags and the C library does not read them.
+/// Never combine them with `operatingMode`: `USE_THREADING` has the value of
+/// the TX bit.
 pub mod options {
     /// Use interpolation for waveform generation
     ///
@@ -61,6 +100,8 @@ pub mod options {
 
 // Re-export all bindgen-generated items
 pub use super::{
+    // Type of the operating mode constants, wrapped by
+    // `operating_modes::OperatingMode` and `operating_modes::Flags`
     _bindgen_ty_1,
 
     // Constants - Max instances
@@ -137,14 +178,16 @@ pub use super::{
 /// Helper functions for working with ggwave parameters
 pub mod helpers {
     use super::*;
-    use std::ffi::c_void;
+    use crate::protocol_mask;
+    use core::ffi::c_void;
 
     /// Safely initialize ggwave with default parameters
     ///
     /// # Returns
     ///
-    /// The ggwave instance or 0 if initialization failed
+    /// The ggwave instance, or -1 if initialization failed
     pub unsafe fn init_default() -> ggwave_Instance {
+        let _global = crate::global_write();
         unsafe {
             let params = ggwave_getDefaultParameters();
             ggwave_init(params)
@@ -172,6 +215,7 @@ pub mod helpers {
         let payload_buffer = text.as_ptr() as *const c_void;
         let payload_size = text.len() as i32;
 
+        let _global = crate::global_read();
         unsafe {
             ggwave_encode(
                 instance,
@@ -179,13 +223,21 @@ pub mod helpers {
                 payload_size,
                 protocol_id,
                 volume,
-                std::ptr::null_mut(),
+                core::ptr::null_mut(),
                 1, // query size in bytes
             )
         }
     }
 
-    /// Check if a ggwave instance is valid
+    /// Check if a value returned by `ggwave_init` is an instance
+    ///
+    /// `ggwave_init` returns -1 on failure. On success it returns the next id
+    /// of a process-wide counter, which starts at 0 and is never reused, so any
+    /// non-negative value is an instance. Only `GGWAVE_MAX_INSTANCES` instances
+    /// can be alive at once, but their ids are not bounded by it.
+    ///
+    /// This only checks the value; it cannot tell whether the instance has
+    /// been freed since.
     ///
     /// # Arguments
     ///
@@ -195,7 +247,7 @@ pub mod helpers {
     ///
     /// `true` if the instance is valid, `false` otherwise
     pub unsafe fn is_valid_instance(instance: ggwave_Instance) -> bool {
-        instance > 0 && instance <= GGWAVE_MAX_INSTANCES as i32
+        instance >= 0
     }
 
     /// Get the sample rate for a ggwave protocol
@@ -226,16 +278,27 @@ pub mod helpers {
     /// * `protocol_ids` - Array of protocol IDs to modify
     /// * `enabled` - Whether to enable or disable the protocols
     /// * `is_rx` - If true, modify reception protocols, otherwise transmission
+    ///
+    /// Like [`GGWave::toggle_rx_protocol`](crate::GGWave::toggle_rx_protocol),
+    /// this changes the process-wide tables, so only instances created
+    /// afterwards are affected. Prefer `GGWaveBuilder::rx_protocols` and
+    /// `GGWaveBuilder::tx_protocols`.
     pub unsafe fn toggle_protocols(protocol_ids: &[ggwave_ProtocolId], enabled: bool, is_rx: bool) {
-        let enabled_val = if enabled { 1 } else { 0 };
-
+        let _global = crate::global_write();
+        let mut mask = if is_rx {
+            protocol_mask::global_rx()
+        } else {
+            protocol_mask::global_tx()
+        };
         for &protocol_id in protocol_ids {
-            unsafe {
-                if is_rx {
-                    ggwave_rxToggleProtocol(protocol_id, enabled_val);
-                } else {
-                    ggwave_txToggleProtocol(protocol_id, enabled_val);
-                }
+            mask.set(protocol_id, enabled);
+        }
+
+        unsafe {
+            if is_rx {
+                protocol_mask::apply_rx(mask);
+            } else {
+                protocol_mask::apply_tx(mask);
             }
         }
     }
@@ -0,0 +1,75 @@
+// Extensions to the ggwave C API that need access to the C++ internals
+
+#include "ggwave/ggwave.h"
+
+#include <cstdio>
+
+#if defined(__APPLE__) || defined(__FreeBSD__) || defined(__NetBSD__) || defined(__OpenBSD__) || defined(__DragonFly__)
+#define GGWAVE_RS_FUNOPEN
+#elif defined(__GLIBC__)
+#define GGWAVE_RS_FOPENCOOKIE
+#endif
+
+extern "C" {
+typedef void (*ggwave_rs_LogCallback)(const char * data, int size);
+}
+
+namespace {
+
+ggwave_rs_LogCallback g_logCallback = nullptr;
+
+#if defined(GGWAVE_RS_FOPENCOOKIE)
+ssize_t writeLog(void *, const char * data, size_t size) {
+    g_logCallback(data, (int) size);
+    return (ssize_t) size;
+}
+#elif defined(GGWAVE_RS_FUNOPEN)
+int writeLog(void *, const char * data, int size) {
+    g_logCallback(data, size);
+    return size;
+}
+#endif
+
+void setProtocol(GGWave::Protocol & protocol, int freqStart, int framesPerTx, int bytesPerTx, int extra) {
+    protocol.name        = "Custom";
+    protocol.freqStart   = freqStart;
+    protocol.framesPerTx = framesPerTx;
+    protocol.bytesPerTx  = bytesPerTx;
+    protocol.extra       = extra;
+}
+
+}
+
+extern "C" {
+
+// Define a protocol in both the rx and tx protocol tables
+//
+// Like the other protocol functions of the C API, this only affects instances
+// created afterwards. The enabled state of the protocol is left unchanged.
+void ggwave_rs_setProtocol(ggwave_ProtocolId protocolId, int freqStart, int framesPerTx, int bytesPerTx, int extra) {
+    setProtocol(GGWave::Protocols::rx()[protocolId], freqStart, framesPerTx, bytesPerTx, extra);
+    setProtocol(GGWave::Protocols::tx()[protocolId], freqStart, framesPerTx, bytesPerTx, extra);
+}
+
+// Open a line-buffered stream passing everything written to it to `callback`
+//
+// The stream can be handed to ggwave_setLogFile to receive the log output of
+// the library. Returns NULL where the C library cannot create custom streams.
+FILE * ggwave_rs_openLogStream(ggwave_rs_LogCallback callback) {
+    g_logCallback = callback;
+
+    FILE * stream = nullptr;
+#if defined(GGWAVE_RS_FOPENCOOKIE)
+    cookie_io_functions_t functions = { nullptr, writeLog, nullptr, nullptr };
+    stream = fopencookie(nullptr, "w", functions);
+#elif defined(GGWAVE_RS_FUNOPEN)
+    stream = funopen(nullptr, nullptr, writeLog, nullptr, nullptr);
+#endif
+
+    if (stream != nullptr) {
+        setvbuf(stream, nullptr, _IOLBF, 0);
+    }
+    return stream;
+}
+
+}
@@ -1,3 +1,4 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![allow(non_upper_case_globals)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
@@ -19,6 +20,13 @@
 //! - Customizable parameters for transmission
 //! - Export encoded audio to WAV format
 //!
+//! ## `no_std`
+//!
+//! The `std` feature is enabled by default. Without it the crate only needs
+//! `alloc`: encoding, decoding, the builder and the protocol settings work as
+//! usual, while WAV and file I/O, network decoding, instance pools and the
+//! signal analysis behind `decode_detailed` and friends are left out.
+//!
 //! ## Example
 //!
 //! ```rust
@@ -46,20 +54,145 @@
 // Include the generated bindings
 include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
 
-use std::ffi::c_void;
-use std::io::Cursor;
+// Extensions to the C API, compiled from src/ggwave_ext.cpp
+unsafe extern "C" {
+    fn ggwave_rs_setProtocol(
+        protocolId: ggwave_ProtocolId,
+        freqStart: core::ffi::c_int,
+        framesPerTx: core::ffi::c_int,
+        bytesPerTx: core::ffi::c_int,
+        extra: core::ffi::c_int,
+    );
+
+    #[cfg(feature = "std")]
+    fn ggwave_rs_openLogStream(
+        callback: unsafe extern "C" fn(data: *const core::ffi::c_char, size: core::ffi::c_int),
+    ) -> *mut libc::FILE;
+}
+
+extern crate alloc;
+
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ffi::c_void;
+use core::marker::PhantomData;
+use core::ops::Range;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+#[cfg(feature = "std")]
+use std::io::{BufReader, Cursor, Read};
+#[cfg(feature = "std")]
+use std::net::TcpStream;
+#[cfg(feature = "std")]
 use std::path::Path;
-use std::ptr;
-use std::sync::Once;
-use std::sync::atomic::{AtomicBool, Ordering};
 
 use ffi::constants;
-use hound::{WavSpec, WavWriter};
+#[cfg(feature = "std")]
+use hound::{WavReader, WavSpec};
 
 // Static initialization
-static INIT: Once = Once::new();
 static INITIALIZED: AtomicBool = AtomicBool::new(false);
 
+/// Maximum number of instances that can be alive at once in a process
+///
+/// The C library keeps its instances in a fixed-size table, so creating an
+/// instance fails with `Error::InstanceLimitReached` while this many exist.
+/// [`GGWavePool`] hands out at most this many, and
+/// [`GGWave::live_instance_count`] tells how many are in use.
+pub const MAX_INSTANCES: usize = GGWAVE_MAX_INSTANCES as usize;
+
+/// Number of instances owned by `GGWave` values
+static LIVE_INSTANCES: AtomicUsize = AtomicUsize::new(0);
+
+/// Frames by which a reception may differ from the expected length of its
+/// protocol and still count as the closest match
+#[cfg(feature = "std")]
+const PROTOCOL_FRAME_TOLERANCE: usize = 3;
+
+/// Frames of the start marker that ggwave hears before it starts recording a
+/// reception
+#[cfg(feature = "std")]
+const RX_START_LAG_FRAMES: usize = 3;
+
+/// Largest frame accepted when decoding length-prefixed sample streams
+#[cfg(feature = "std")]
+pub(crate) const MAX_STREAM_FRAME_BYTES: usize = 1 << 20;
+
+/// Check the length prefix of a stream frame against `MAX_STREAM_FRAME_BYTES`
+#[cfg(feature = "std")]
+pub(crate) fn stream_frame_len(len: u32) -> Result<usize> {
+    let len = len as usize;
+    if len > MAX_STREAM_FRAME_BYTES {
+        return Err(Error::InvalidParameter(
+            "Stream frame exceeds the maximum frame size",
+        ));
+    }
+    Ok(len)
+}
+
+// The C library keeps its instance table, protocol tables and log file in
+// global state. Creating and freeing instances and changing the global tables
+// take the write lock; calls that only look up an existing instance take the
+// read lock. The protocol settings are shared by every instance in the
+// process, so toggling them on one thread while another creates an instance
+// is safe, but which settings that instance gets depends on the order.
+static GLOBAL_STATE: sync::GlobalLock = sync::GlobalLock::new();
+
+/// Lock the global C state for a call that only reads it
+fn global_read() -> sync::ReadGuard {
+    GLOBAL_STATE.read()
+}
+
+/// Lock the global C state for a call that modifies it
+fn global_write() -> sync::WriteGuard {
+    GLOBAL_STATE.write()
+}
+
+/// The log stream opened by [`GGWave::set_debug_mode`] or
+/// [`GGWave::set_log_callback`], closed once the C library stops using it
+static LOG_FILE: AtomicPtr<libc::FILE> = AtomicPtr::new(ptr::null_mut());
+
+/// Closure receiving the log lines written to the stream of
+/// [`GGWave::set_log_callback`]
+#[cfg(feature = "std")]
+#[allow(clippy::type_complexity)]
+static LOG_CALLBACK: std::sync::Mutex<Option<Box<dyn Fn(&str) + Send>>> =
+    std::sync::Mutex::new(None);
+
+/// Send the log output of the C library to `file`, or disable it for null,
+/// closing the previous log stream if this crate opened it
+///
+/// The write lock on the global state must be held.
+unsafe fn replace_log_file(file: *mut libc::FILE) {
+    let previous = LOG_FILE.swap(file, Ordering::AcqRel);
+    unsafe {
+        ggwave_setLogFile(file as *mut c_void);
+        if !previous.is_null() {
+            libc::fclose(previous);
+        }
+    }
+}
+
+/// Pass what the C library writes to the stream of
+/// [`GGWave::set_log_callback`] to the closure, line by line
+#[cfg(feature = "std")]
+unsafe extern "C" fn log_trampoline(data: *const core::ffi::c_char, size: core::ffi::c_int) {
+    let bytes = unsafe { core::slice::from_raw_parts(data as *const u8, size.max(0) as usize) };
+    let text = String::from_utf8_lossy(bytes);
+
+    let callback = LOG_CALLBACK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(callback) = callback.as_ref() {
+        for line in text.lines() {
+            callback(line);
+        }
+    }
+}
+
 //
 // Public types
 //
@@ -69,6 +202,82 @@ pub use ggwave_Parameters as Parameters;
 pub use ggwave_ProtocolId as ProtocolId;
 pub use ggwave_SampleFormat as SampleFormat;
 
+impl Parameters {
+    /// Parameters of the proven receiver configuration used by `example_rx`
+    ///
+    /// 48 kHz, 1024 samples per frame, a sound marker threshold of 0.5,
+    /// `F32` input for microphone captures, `I16` output and
+    /// [`operating_modes::RX_AND_TX`]. Start from this when decoding live
+    /// audio; [`GGWaveBuilder::rx_preset`] builds an instance with it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, Parameters};
+    ///
+    /// let ggwave = GGWave::new_with_params(Parameters::receiver_default())
+    ///     .expect("Failed to initialize GGWave");
+    /// ```
+    pub fn receiver_default() -> Self {
+        let mut params = unsafe { ggwave_getDefaultParameters() };
+
+        params.sampleRate = 48000.0;
+        params.sampleRateInp = 48000.0;
+        params.sampleRateOut = 48000.0;
+        params.samplesPerFrame = 1024;
+        params.soundMarkerThreshold = constants::DEFAULT_SOUND_MARKER_THRESHOLD;
+        params.sampleFormatInp = sample_formats::F32;
+        params.sampleFormatOut = sample_formats::I16;
+        params.operatingMode = operating_modes::RX_AND_TX;
+
+        params
+    }
+}
+
+/// Describe parameters in a readable form, one labelled field per line
+///
+/// The bindgen `Debug` output of [`Parameters`] shows the C field names and
+/// raw integers. This labels the fields like `ParametersDef` does, adds units
+/// and names the sample formats and operating mode flags.
+///
+/// # Examples
+///
+/// ```
+/// use ggwave_rs::{Parameters, describe_parameters};
+///
+/// let description = describe_parameters(&Parameters::receiver_default());
+/// assert!(description.contains("input_sample_format:    f32"));
+/// println!("{}", description);
+/// ```
+pub fn describe_parameters(params: &Parameters) -> String {
+    let payload_length = if params.payloadLength > 0 {
+        format!("{} bytes", params.payloadLength)
+    } else {
+        "variable".to_string()
+    };
+
+    format!(
+        "payload_length:         {}\n\
+         input_sample_rate:      {} Hz\n\
+         output_sample_rate:     {} Hz\n\
+         sample_rate:            {} Hz\n\
+         samples_per_frame:      {}\n\
+         sound_marker_threshold: {}\n\
+         input_sample_format:    {}\n\
+         output_sample_format:   {}\n\
+         operating_mode:         {}",
+        payload_length,
+        params.sampleRateInp,
+        params.sampleRateOut,
+        params.sampleRate,
+        params.samplesPerFrame,
+        params.soundMarkerThreshold,
+        sample_formats::name(params.sampleFormatInp),
+        sample_formats::name(params.sampleFormatOut),
+        operating_modes::describe(params.operatingMode),
+    )
+}
+
 /// Raw FFI bindings to the ggwave C API
 ///
 /// # Safety
@@ -77,45 +286,161 @@ pub use ggwave_SampleFormat as SampleFormat;
 /// Use the safe wrapper functions provided by the `GGWave` struct when possible.
 pub mod ffi;
 
+#[cfg(feature = "analysis")]
+pub mod analysis;
+
 #[cfg(feature = "async")]
 pub mod async_impl;
 
+#[cfg(feature = "audio")]
+pub mod audio;
+
+mod checksum;
+mod chunking;
+#[cfg(feature = "serde")]
+mod config;
+mod convert;
+mod custom_protocol;
+#[cfg(feature = "std")]
+mod diagnostics;
+mod encoded;
+mod encoder;
+#[cfg(any(feature = "flac", feature = "ogg"))]
+mod export;
+#[cfg(feature = "metrics")]
+mod metrics;
+#[cfg(feature = "std")]
+mod pool;
+mod preset;
+mod protocol_mask;
+mod resample;
+mod sequence;
+#[cfg(feature = "std")]
+mod signal;
+#[cfg(feature = "std")]
+mod stream_decoder;
+mod sync;
+mod transport;
+mod volume;
+
+pub use chunking::ChunkReassembler;
+#[cfg(feature = "serde")]
+pub use config::{ParametersDef, ProtocolDef, SampleFormatDef};
+pub use convert::downmix_to_mono;
+pub use custom_protocol::CustomProtocol;
+#[cfg(feature = "std")]
+pub use diagnostics::{DeviceReport, FrequencyResponse};
+pub use encoded::EncodedAudio;
+#[cfg(feature = "std")]
+pub use encoded::EncodedAudioReader;
+pub use encoder::Encoder;
+#[cfg(feature = "metrics")]
+pub use metrics::DecodeMetrics;
+#[cfg(feature = "std")]
+pub use pool::{GGWavePool, PooledGGWave};
+pub use preset::Preset;
+pub use protocol_mask::ProtocolMask;
+pub use resample::Resampler;
+pub use sequence::{SequenceManifest, SequencePart};
+#[cfg(feature = "std")]
+pub use stream_decoder::{DecodeIter, StreamDecoder};
+pub use transport::TransportEncoding;
+pub use volume::Volume;
+
+/// Size in bytes of the checksum sent by [`GGWave::encode_checked`]
+pub(crate) const CHECKSUM_LEN: usize = 4;
+
+/// Verify the checksum of a payload sent with [`GGWave::encode_checked`]
+///
+/// Returns the data behind the checksum, or `Error::ChecksumMismatch`.
+pub(crate) fn checked_data(payload: &[u8]) -> Result<&[u8]> {
+    if payload.len() < CHECKSUM_LEN {
+        return Err(Error::ChecksumMismatch);
+    }
+
+    let (expected, data) = payload.split_at(CHECKSUM_LEN);
+    let mut crc = checksum::Crc32::new();
+    crc.update(data);
+
+    if crc.finish().to_le_bytes() != expected {
+        return Err(Error::ChecksumMismatch);
+    }
+    Ok(data)
+}
+
 /// Error type for ggwave operations
+///
+/// The C decoder returns the payload length on success and 0 when the audio
+/// holds no complete message, which is reported as `NoMessage` by the
+/// functions that cannot return `None`. Negative codes are real failures and
+/// become `DecodeFailed`:
+///
+/// * `-1` - a message was detected but could not be decoded, or the audio
+///   was not a whole number of samples
+///
+/// A payload larger than the caller's buffer is reported as `BufferTooSmall`
+/// with the payload's length, like on the encode path.
 #[derive(Debug)]
 pub enum Error {
     /// Encoding failed with specific error code
     EncodeFailed(i32),
     /// Decoding failed with specific error code
     DecodeFailed(i32),
-    /// Failed to write WAV file
+    /// The audio does not contain a complete message
+    NoMessage,
+    /// Failed to read or write WAV data
+    #[cfg(feature = "std")]
     WavWriteFailed(hound::Error),
     /// Invalid sample format
     InvalidSampleFormat,
     /// I/O error
+    #[cfg(feature = "std")]
     IoError(std::io::Error),
     /// UTF-8 conversion error
-    Utf8Error(std::str::Utf8Error),
+    Utf8Error(core::str::Utf8Error),
     /// Invalid parameter
     InvalidParameter(&'static str),
     /// Initialization failed
     InitializationFailed,
+    /// Every instance slot of the C library is in use
+    InstanceLimitReached { max: usize },
     /// Buffer too small
     BufferTooSmall { required: usize, provided: usize },
     /// Text too long for encoding
     TextTooLong { length: usize, max: usize },
+    /// Every instance of a pool is in use
+    PoolExhausted { capacity: usize },
+    /// A decoded payload does not match its checksum
+    ChecksumMismatch,
+    /// Encoded waveform exceeds the configured maximum number of samples
+    OutputTooLarge { samples: usize, max: usize },
+    /// Audio device error
+    #[cfg(feature = "audio")]
+    AudioError(String),
+    /// Failed to write or read a compressed audio format
+    #[cfg(any(feature = "flac", feature = "ogg"))]
+    ExportFailed(String),
 }
 
-impl std::fmt::Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Error::EncodeFailed(code) => write!(f, "Failed to encode data, error code: {}", code),
             Error::DecodeFailed(code) => write!(f, "Failed to decode data, error code: {}", code),
-            Error::WavWriteFailed(e) => write!(f, "WAV write error: {}", e),
+            Error::NoMessage => write!(f, "No message found in the audio data"),
+            #[cfg(feature = "std")]
+            Error::WavWriteFailed(e) => write!(f, "WAV error: {}", e),
             Error::InvalidSampleFormat => write!(f, "Invalid sample format"),
+            #[cfg(feature = "std")]
             Error::IoError(e) => write!(f, "IO error: {}", e),
             Error::Utf8Error(e) => write!(f, "UTF-8 conversion error: {}", e),
             Error::InvalidParameter(msg) => write!(f, "Invalid parameter: {}", msg),
             Error::InitializationFailed => write!(f, "Failed to initialize GGWave"),
+            Error::InstanceLimitReached { max } => write!(
+                f,
+                "All {} GGWave instance slots are in use, drop an instance first",
+                max
+            ),
             Error::BufferTooSmall { required, provided } => write!(
                 f,
                 "Buffer too small, required: {} bytes, provided: {} bytes",
@@ -126,44 +451,83 @@ impl std::fmt::Display for Error {
                 "Text too long for encoding, length: {} bytes, max: {} bytes",
                 length, max
             ),
+            Error::ChecksumMismatch => write!(f, "Decoded payload does not match its checksum"),
+            Error::PoolExhausted { capacity } => {
+                write!(f, "All {} pooled GGWave instances are in use", capacity)
+            }
+            Error::OutputTooLarge { samples, max } => write!(
+                f,
+                "Encoded waveform too large, samples: {}, max: {} samples",
+                samples, max
+            ),
+            #[cfg(feature = "audio")]
+            Error::AudioError(msg) => write!(f, "Audio device error: {}", msg),
+            #[cfg(any(feature = "flac", feature = "ogg"))]
+            Error::ExportFailed(msg) => write!(f, "Compressed audio error: {}", msg),
         }
     }
 }
 
-impl std::error::Error for Error {}
+impl core::error::Error for Error {}
 
+#[cfg(feature = "std")]
 impl From<hound::Error> for Error {
     fn from(err: hound::Error) -> Self {
         Error::WavWriteFailed(err)
     }
 }
 
+#[cfg(feature = "std")]
 impl From<std::io::Error> for Error {
     fn from(err: std::io::Error) -> Self {
         Error::IoError(err)
     }
 }
 
-impl From<std::str::Utf8Error> for Error {
-    fn from(err: std::str::Utf8Error) -> Self {
+impl From<core::str::Utf8Error> for Error {
+    fn from(err: core::str::Utf8Error) -> Self {
         Error::Utf8Error(err)
     }
 }
 
 /// Result type for ggwave operations
-pub type Result<T> = std::result::Result<T, Error>;
+pub type Result<T> = core::result::Result<T, Error>;
 
 /// Builder for GGWave parameters
 ///
 /// This struct allows for configuring a GGWave instance in a fluent manner.
+///
+/// # Sample rates
+///
+/// ggwave works with three sample rates. The processing rate (`sampleRate`)
+/// is the one tones are generated and analyzed at, so it decides the actual
+/// frequencies of every protocol. The input and output rates (`sampleRateInp`
+/// and `sampleRateOut`) are the rates of the audio passed to the decoder and
+/// returned by the encoder; ggwave resamples between them and the processing
+/// rate internally.
+///
+/// [`sample_rate`](Self::sample_rate) sets all three, which is what most
+/// applications want. [`input_sample_rate`](Self::input_sample_rate) and
+/// [`output_sample_rate`](Self::output_sample_rate) only change the device
+/// side, and [`processing_sample_rate`](Self::processing_sample_rate) only the
+/// processing side. Sender and receiver must use the same processing rate and
+/// samples per frame to understand each other, whatever their device rates.
 pub struct GGWaveBuilder {
     params: Parameters,
+    max_output_samples: Option<usize>,
+    rx_protocols: Option<ProtocolMask>,
+    tx_protocols: Option<ProtocolMask>,
+    transport: TransportEncoding,
+    #[cfg(feature = "std")]
+    marker_noise: Option<Vec<f32>>,
 }
 
 impl GGWaveBuilder {
     /// Create a new builder with modified default parameters
     ///
-    /// Uses parameter values that reliably work across different systems.
+    /// Uses parameter values that reliably work across different systems:
+    /// 16 kHz and 512 samples per frame. For decoding live microphone input,
+    /// [`rx_preset`](Self::rx_preset) is usually a better starting point.
     pub fn new() -> Self {
         let mut params = unsafe { ggwave_getDefaultParameters() };
 
@@ -172,12 +536,70 @@ impl GGWaveBuilder {
         params.sampleRateInp = 16000.0;
         params.sampleRateOut = 16000.0;
         params.samplesPerFrame = 512;
-        params.soundMarkerThreshold = 0.5;
+        params.soundMarkerThreshold = constants::DEFAULT_SOUND_MARKER_THRESHOLD;
+
+        Self {
+            params,
+            max_output_samples: None,
+            rx_protocols: None,
+            tx_protocols: None,
+            transport: TransportEncoding::Raw,
+            #[cfg(feature = "std")]
+            marker_noise: None,
+        }
+    }
 
-        Self { params }
+    /// Create a builder with the proven receiver configuration
+    ///
+    /// Starts from [`Parameters::receiver_default`]: 48 kHz, 1024 samples per
+    /// frame, threshold 0.5, `F32` input, `I16` output and RX_AND_TX. This is
+    /// the configuration `example_rx` uses to decode live audio, and any
+    /// setting can still be overridden.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::GGWaveBuilder;
+    ///
+    /// let ggwave = GGWaveBuilder::rx_preset()
+    ///     .build()
+    ///     .expect("Failed to initialize GGWave");
+    /// ```
+    pub fn rx_preset() -> Self {
+        Self {
+            params: Parameters::receiver_default(),
+            ..Self::new()
+        }
+    }
+
+    /// Apply the parameters of a [`Preset`]
+    ///
+    /// Sets the sample rates, samples per frame and sample formats to values
+    /// known to work for the preset's use, so call it before overriding any of
+    /// them. Encode with [`Preset::protocol`] to use the protocol it is tuned
+    /// for.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWaveBuilder, Preset};
+    ///
+    /// let ggwave = GGWaveBuilder::new()
+    ///     .preset(Preset::UltrasoundBeacon)
+    ///     .max_output_samples(48000 * 5)
+    ///     .build()
+    ///     .expect("Failed to initialize GGWave");
+    /// ```
+    pub fn preset(mut self, preset: Preset) -> Self {
+        preset.apply(&mut self.params);
+        self
     }
 
     /// Set the sample rate for input, output, and processing
+    ///
+    /// See [Sample rates](Self#sample-rates) for how the three rates relate.
+    /// Every rate must be between [`constants::MIN_SAMPLE_RATE`] and
+    /// [`constants::MAX_SAMPLE_RATE`], or [`build`](Self::build) fails.
     pub fn sample_rate(mut self, rate: f32) -> Self {
         self.params.sampleRate = rate;
         self.params.sampleRateInp = rate;
@@ -186,18 +608,42 @@ impl GGWaveBuilder {
     }
 
     /// Set the input sample rate
+    ///
+    /// This is the rate of the audio passed to the decoder. The processing
+    /// rate is left unchanged and the input is resampled to it.
     pub fn input_sample_rate(mut self, rate: f32) -> Self {
         self.params.sampleRateInp = rate;
         self
     }
 
     /// Set the output sample rate
+    ///
+    /// This is the rate of the audio returned by the encoder. The processing
+    /// rate is left unchanged and the output is resampled from it.
     pub fn output_sample_rate(mut self, rate: f32) -> Self {
         self.params.sampleRateOut = rate;
         self
     }
 
+    /// Set the processing sample rate only
+    ///
+    /// The processing rate decides the frequencies of the protocol tones, so
+    /// it must match on both ends of a transmission. Input and output rates
+    /// are left unchanged.
+    pub fn processing_sample_rate(mut self, rate: f32) -> Self {
+        self.params.sampleRate = rate;
+        self
+    }
+
     /// Set samples per frame
+    ///
+    /// This is the FFT size of the decoder, so it sets the width of the
+    /// frequency bins protocol tones are placed on. It must be at most
+    /// [`constants::MAX_SAMPLES_PER_FRAME`], and large enough that the tones
+    /// of at least one enabled built-in protocol sit below half of it: 176
+    /// for the `DT` and `MT` protocols, 272 for the audible ones and 832 for
+    /// the ultrasound ones. Otherwise [`build`](Self::build) fails with
+    /// `Error::InvalidParameter`.
     pub fn samples_per_frame(mut self, samples: i32) -> Self {
         self.params.samplesPerFrame = samples;
         self
@@ -216,18 +662,123 @@ impl GGWaveBuilder {
     }
 
     /// Set sound marker threshold
+    ///
+    /// Every transmission starts and ends with a sound marker, where each
+    /// pair of adjacent frequency bins of the protocol has one bin louder
+    /// than the other. A marker is detected when the louder bins exceed the
+    /// others by this ratio. Too low a threshold lets background noise pass
+    /// as a marker, which starts the decoder on garbage and can hide the real
+    /// message that follows; too high a threshold misses the markers of quiet
+    /// or distorted messages altogether. Values between 0.5 and 10.0 are
+    /// sensible: low ones for clean or cabled links, high ones for noisy
+    /// rooms. The default is
+    /// [`constants::DEFAULT_SOUND_MARKER_THRESHOLD`], the low end of that
+    /// range. [`auto_marker_threshold`](Self::auto_marker_threshold) picks one
+    /// from a recording of the room.
     pub fn sound_marker_threshold(mut self, threshold: f32) -> Self {
         self.params.soundMarkerThreshold = threshold;
         self
     }
 
+    #[cfg(feature = "std")]
+    /// Set the sound marker threshold from a recording of background noise
+    ///
+    /// The threshold is computed by [`GGWave::calibrate_marker_threshold`]
+    /// when the instance is built, so it accounts for the final sample rates,
+    /// samples per frame and rx protocols. Replaces any threshold set with
+    /// [`sound_marker_threshold`](Self::sound_marker_threshold).
+    ///
+    /// # Arguments
+    ///
+    /// * `noise` - Normalized mono samples at the input sample rate, recorded
+    ///   while nothing was transmitting
+    pub fn auto_marker_threshold(mut self, noise: &[f32]) -> Self {
+        self.marker_noise = Some(noise.to_vec());
+        self
+    }
+
     /// Set operating mode
-    pub fn operating_mode(mut self, mode: i32) -> Self {
-        self.params.operatingMode = mode;
+    ///
+    /// This replaces every flag, including those set by
+    /// [`use_dss`](Self::use_dss) and [`tx_only_tones`](Self::tx_only_tones),
+    /// so call it first. [`base_mode`](Self::base_mode) changes the direction
+    /// alone.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - An [`OperatingMode`](operating_modes::OperatingMode), an
+    ///   `OperatingMode | Flags` combination or raw [`operating_modes`] bits
+    pub fn operating_mode(mut self, mode: impl Into<i32>) -> Self {
+        self.params.operatingMode = mode.into();
+        self
+    }
+
+    /// Set whether the instance receives, transmits or both, keeping the
+    /// modifier flags
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The direction; [`OperatingMode::TxOnlyTones`] also enables
+    ///   tone-only transmission, which the other modes leave as it is
+    ///
+    /// [`OperatingMode::TxOnlyTones`]: operating_modes::OperatingMode::TxOnlyTones
+    pub fn base_mode(mut self, mode: operating_modes::OperatingMode) -> Self {
+        let direction = operating_modes::RX_AND_TX;
+        self.params.operatingMode = (self.params.operatingMode & !direction) | i32::from(mode);
+        self
+    }
+
+    /// Add operating mode flags, keeping those already set
+    ///
+    /// # Arguments
+    ///
+    /// * `flags` - [`Flags`](operating_modes::Flags) to OR into the mode,
+    ///   combined with `|`
+    pub fn with_mode_flags(mut self, flags: operating_modes::Flags) -> Self {
+        self.params.operatingMode |= i32::from(flags);
+        self
+    }
+
+    /// Enable or disable Direct Sequence Spread, keeping the other flags
+    ///
+    /// Both ends of a transmission must agree on this setting.
+    ///
+    /// The payload is spread with a fixed sequence built into ggwave rather
+    /// than a random one, so there is no seed to set: encoding the same text
+    /// with the same parameters and protocol always gives the same waveform,
+    /// with or without DSS.
+    pub fn use_dss(self, enabled: bool) -> Self {
+        self.operating_mode_flag(operating_modes::USE_DSS, enabled)
+    }
+
+    /// Enable or disable tone-only transmission, keeping the other flags
+    ///
+    /// Such instances only generate the tones of a message, see
+    /// [`operating_modes::TX_ONLY_TONES`].
+    pub fn tx_only_tones(self, enabled: bool) -> Self {
+        self.operating_mode_flag(operating_modes::TX_ONLY_TONES, enabled)
+    }
+
+    /// Set or clear one operating mode flag
+    fn operating_mode_flag(mut self, flag: i32, enabled: bool) -> Self {
+        if enabled {
+            self.params.operatingMode |= flag;
+        } else {
+            self.params.operatingMode &= !flag;
+        }
         self
     }
 
     /// Set fixed payload length
+    ///
+    /// Fixed-length transmissions carry no sound markers, which makes them
+    /// about `2 * DEFAULT_MARKER_FRAMES + DEFAULT_ENCODED_DATA_OFFSET` frames
+    /// shorter. This is the only way to change the markers: ggwave does not
+    /// let their length be configured.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `length` is not between 1 and `MAX_LENGTH_FIXED`
     pub fn fixed_payload_length(mut self, length: i32) -> Self {
         if length <= 0 || length > constants::MAX_LENGTH_FIXED as i32 {
             panic!(
@@ -239,16 +790,103 @@ impl GGWaveBuilder {
         self
     }
 
+    /// Limit the number of samples a single encode may produce
+    ///
+    /// Encoding text whose waveform would exceed the limit fails with
+    /// `Error::OutputTooLarge` before any audio is generated. There is no limit
+    /// by default.
+    pub fn max_output_samples(mut self, samples: usize) -> Self {
+        self.max_output_samples = Some(samples);
+        self
+    }
+
+    /// Set how payloads are represented on the air
+    ///
+    /// With [`TransportEncoding::Base64`] or [`TransportEncoding::Hex`] every
+    /// payload is sent as ASCII text and decoded back to the original bytes,
+    /// so binary data survives receivers that only handle text. This reduces
+    /// [`GGWave::max_payload_len`] by a quarter or a half. Defaults to
+    /// [`TransportEncoding::Raw`].
+    pub fn transport_encoding(mut self, encoding: TransportEncoding) -> Self {
+        self.transport = encoding;
+        self
+    }
+
+    /// Set the protocols the instance can receive
+    ///
+    /// Only applies to this instance. Defaults to the protocols enabled
+    /// process-wide when the instance is built.
+    pub fn rx_protocols(mut self, mask: ProtocolMask) -> Self {
+        self.rx_protocols = Some(mask);
+        self
+    }
+
+    /// Set the protocols the instance can transmit
+    ///
+    /// Only applies to this instance. Defaults to the protocols enabled
+    /// process-wide when the instance is built.
+    pub fn tx_protocols(mut self, mask: ProtocolMask) -> Self {
+        self.tx_protocols = Some(mask);
+        self
+    }
+
+    /// Get the parameters configured so far
+    ///
+    /// A threshold requested with `auto_marker_threshold` is only computed by
+    /// [`into_parameters`](Self::into_parameters) and [`build`](Self::build).
+    pub fn parameters(&self) -> &Parameters {
+        &self.params
+    }
+
+    /// Take the configured parameters, to create several instances from them
+    ///
+    /// The result can be passed to [`GGWave::new_with_params`] or
+    /// [`GGWavePool::new`]. Settings that are not part of [`Parameters`] are
+    /// dropped: the rx and tx protocols, the maximum output sample count and
+    /// the transport encoding. Instances created from the result use the
+    /// protocols enabled process-wide, the default output limit and raw
+    /// transport. Unlike [`build`](Self::build), the sample rates are not
+    /// checked until an instance is created.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, GGWaveBuilder};
+    ///
+    /// let params = GGWaveBuilder::rx_preset().sample_rate(44100.0).into_parameters();
+    /// let first = GGWave::new_with_params(params).expect("Failed to initialize GGWave");
+    /// let second = GGWave::new_with_params(params).expect("Failed to initialize GGWave");
+    /// assert_eq!(second.parameters().sampleRateInp, 44100.0);
+    /// ```
+    pub fn into_parameters(self) -> Parameters {
+        self.calibrated_params()
+    }
+
     /// Build a GGWave instance with the configured parameters
     pub fn build(self) -> Result<GGWave> {
-        unsafe {
-            let instance = ggwave_init(self.params);
-            if instance < 0 {
-                Err(Error::InitializationFailed)
-            } else {
-                Ok(GGWave { instance })
-            }
+        let params = self.calibrated_params();
+        let mut ggwave = GGWave::init(params, self.rx_protocols, self.tx_protocols)?;
+        ggwave.max_output_samples = self.max_output_samples;
+        ggwave.transport = self.transport;
+        Ok(ggwave)
+    }
+
+    #[cfg(feature = "std")]
+    /// The parameters with the sound marker threshold calibrated, if requested
+    fn calibrated_params(&self) -> Parameters {
+        let mut params = self.params;
+        if let Some(noise) = &self.marker_noise {
+            let rx_protocols = self.rx_protocols.unwrap_or_else(protocol_mask::global_rx);
+            params.soundMarkerThreshold =
+                GGWave::marker_threshold_for(&params, rx_protocols, noise);
         }
+        params
+    }
+
+    #[cfg(not(feature = "std"))]
+    /// The parameters; calibrating the sound marker threshold needs `std`
+    fn calibrated_params(&self) -> Parameters {
+        self.params
     }
 }
 
@@ -258,14 +896,76 @@ impl Default for GGWaveBuilder {
     }
 }
 
+/// A decoded message with details about how it was received
+///
+/// Returned by [`GGWave::decode_detailed`] and [`GGWave::decode_all`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodeResult {
+    /// The decoded text
+    pub text: String,
+    /// The protocol that carried the message
+    pub protocol: ProtocolId,
+    /// RMS level of the received transmission, relative to full scale
+    pub received_volume: Option<f32>,
+    /// Length of the decoded payload in bytes
+    pub length: usize,
+    /// Position in samples where the transmission starts, from the start of
+    /// the decoded audio
+    pub offset: usize,
+    /// How cleanly the protocol's tones stood out from the noise, from 0.0
+    /// for a band full of noise to 1.0 for clean tones
+    ///
+    /// A decode with a low confidence was close to failing, so the sender can
+    /// be asked to repeat the message louder or closer. The C library does not
+    /// report a quality metric, so this is estimated from the spectrum of the
+    /// received transmission. It is 0.0 for custom protocols, whose tone layout
+    /// is not known.
+    pub confidence: f32,
+    /// Whether a noticeable share of the received samples were at full scale
+    ///
+    /// Clipping distorts the tones and is a common reason for failed decodes
+    /// of live captures; lowering the input gain fixes it.
+    pub clipped: bool,
+}
+
 /// Main GGWave interface for audio-based data transmission
 ///
 /// This struct provides a safe interface to the ggwave C API, allowing for
 /// encoding and decoding of data using audio.
+///
+/// # Thread safety
+///
+/// `GGWave` is `Send` but not `Sync`: an instance can be moved to another
+/// thread, but decoding updates per-instance state in the C library, so it
+/// must not be used from several threads at once. Share it behind a `Mutex`
+/// (or use [`GGWavePool`]) when several threads need access.
+///
+/// The protocol toggles and frequency settings of the C library are global
+/// rather than per instance. Changing them from one thread affects every
+/// instance created afterwards, including instances created on other threads.
+/// Use [`GGWaveBuilder::rx_protocols`] and [`GGWaveBuilder::tx_protocols`] to
+/// choose the protocols of a single instance.
 pub struct GGWave {
     instance: ggwave_Instance,
+    params: Parameters,
+    max_output_samples: Option<usize>,
+    rx_protocols: ProtocolMask,
+    tx_protocols: ProtocolMask,
+    transport: TransportEncoding,
+    #[cfg(feature = "metrics")]
+    metrics: core::cell::Cell<DecodeMetrics>,
+    // Opt out of the auto `Send`/`Sync` impls, see the `Send` impl below
+    _not_sync: PhantomData<*const ()>,
 }
 
+// SAFETY: The instance handle is an index into the C library's instance table
+// and all per-instance state lives behind it, so nothing ties an instance to
+// the thread that created it. The table itself and the global protocol tables
+// and log file are shared by all instances; every access to them goes through
+// `GLOBAL_STATE`, which serializes creation, destruction and global settings
+// against per-instance calls on other threads.
+unsafe impl Send for GGWave {}
+
 impl GGWave {
     /// Get the raw ggwave instance handle for advanced use cases
     ///
@@ -291,15 +991,55 @@ impl GGWave {
 
     /// Create a GGWave instance from an existing raw instance
     ///
+    /// The C API cannot report the parameters of an existing instance, so the
+    /// default parameters and the currently enabled protocols are recorded for it.
+    ///
     /// # Safety
     ///
     /// The provided instance must be a valid ggwave instance created with `ggwave_init`.
     /// The instance will be owned by the returned GGWave and will be freed when dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `instance` is not a valid handle, see
+    /// [`try_from_raw_instance`](Self::try_from_raw_instance)
     pub unsafe fn from_raw_instance(instance: ffi::ggwave_Instance) -> Self {
-        if instance < 0 {
-            panic!("Invalid ggwave instance");
+        unsafe { Self::try_from_raw_instance(instance) }.expect("Invalid ggwave instance")
+    }
+
+    /// Create a GGWave instance from an existing raw instance, checking the handle
+    ///
+    /// Like [`from_raw_instance`](Self::from_raw_instance), but a handle that
+    /// cannot be an instance, such as the -1 `ggwave_init` returns on failure,
+    /// is reported as an error. Validity is decided by
+    /// [`ffi::helpers::is_valid_instance`].
+    ///
+    /// # Safety
+    ///
+    /// A valid handle must refer to a live instance created with `ggwave_init`
+    /// and not owned by anything else. It will be freed when the returned
+    /// GGWave is dropped.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the instance, or `Error::InvalidParameter` if the
+    /// handle is invalid
+    pub unsafe fn try_from_raw_instance(instance: ffi::ggwave_Instance) -> Result<Self> {
+        if !unsafe { ffi::helpers::is_valid_instance(instance) } {
+            return Err(Error::InvalidParameter("invalid instance handle"));
         }
-        Self { instance }
+        LIVE_INSTANCES.fetch_add(1, Ordering::Relaxed);
+        Ok(Self {
+            instance,
+            params: unsafe { ggwave_getDefaultParameters() },
+            max_output_samples: None,
+            rx_protocols: protocol_mask::global_rx(),
+            tx_protocols: protocol_mask::global_tx(),
+            transport: TransportEncoding::Raw,
+            #[cfg(feature = "metrics")]
+            metrics: Default::default(),
+            _not_sync: PhantomData,
+        })
     }
 
     /// Create a new GGWave instance with modified default parameters
@@ -315,23 +1055,11 @@ impl GGWave {
     /// ```
     pub fn new() -> Result<Self> {
         // Initialize global state if needed
-        INIT.call_once(|| {
-            // Any global initialization can go here
-            INITIALIZED.store(true, Ordering::SeqCst);
-        });
-
-        unsafe {
-            // Start with default parameters
-            let params = ggwave_getDefaultParameters();
+        INITIALIZED.store(true, Ordering::SeqCst);
 
-            // Initialize with modified parameters
-            let instance = ggwave_init(params);
-            if instance < 0 {
-                Err(Error::InitializationFailed)
-            } else {
-                Ok(Self { instance })
-            }
-        }
+        // Start with default parameters
+        let params = unsafe { ggwave_getDefaultParameters() };
+        Self::new_with_params(params)
     }
 
     /// Start building a GGWave instance with custom parameters
@@ -374,21 +1102,26 @@ impl GGWave {
             ));
         }
 
-        unsafe {
-            let mut params = ggwave_getDefaultParameters();
-            params.payloadLength = payload_length;
-            params.operatingMode = operating_mode;
-            let instance = ggwave_init(params);
-            if instance < 0 {
-                Err(Error::InitializationFailed)
-            } else {
-                Ok(Self { instance })
-            }
-        }
+        let mut params = unsafe { ggwave_getDefaultParameters() };
+        params.payloadLength = payload_length;
+        params.operatingMode = operating_mode;
+        Self::new_with_params(params)
     }
 
     /// Create a new GGWave instance with custom parameters
     ///
+    /// The sample rates and samples per frame are checked against the limits
+    /// documented on [`GGWaveBuilder::sample_rate`] and
+    /// [`GGWaveBuilder::samples_per_frame`] before the C library sees them.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the instance, `Error::InvalidParameter` naming
+    /// the offending parameter, `Error::InstanceLimitReached` if all
+    /// [`MAX_INSTANCES`] instance slots are in use, or
+    /// `Error::InitializationFailed` if the C library rejects the parameters
+    /// anyway
+    ///
     /// # Examples
     ///
     /// ```
@@ -400,177 +1133,272 @@ impl GGWave {
     ///     .expect("Failed to initialize GGWave");
     /// ```
     pub fn new_with_params(params: Parameters) -> Result<Self> {
-        unsafe {
-            let instance = ggwave_init(params);
-            if instance < 0 {
-                Err(Error::InitializationFailed)
-            } else {
-                Ok(Self { instance })
-            }
-        }
+        Self::init(params, None, None)
     }
 
-    /// Get default parameters for ggwave
+    /// Create a GGWave instance matching the configuration of an audio stream
+    ///
+    /// Input and output use the stream's sample rate and sample format, so the
+    /// raw samples of the stream can be passed to the decoder and the encoded
+    /// audio written to the stream as-is.
+    ///
+    /// # Arguments
+    ///
+    /// * `sample_rate` - The sample rate of the stream in Hz
+    /// * `channels` - The number of channels; ggwave only handles mono
+    /// * `format` - The sample format of the stream
+    /// * `operating_mode` - The operating mode, see [`operating_modes`]
     ///
     /// # Returns
     ///
-    /// A `Parameters` struct with default values
+    /// `Error::InvalidParameter` for multi-channel streams and
+    /// `Error::InvalidSampleFormat` for unknown formats
     ///
     /// # Examples
     ///
     /// ```
-    /// use ggwave_rs::GGWave;
+    /// use ggwave_rs::{GGWave, operating_modes, sample_formats};
     ///
-    /// let params = GGWave::default_parameters();
-    /// println!("Default sample rate: {}", params.sampleRate);
+    /// let ggwave = GGWave::from_audio_config(44100.0, 1, sample_formats::I16, operating_modes::RX)
+    ///     .expect("Failed to initialize GGWave");
     /// ```
-    pub fn default_parameters() -> Parameters {
-        unsafe { ggwave_getDefaultParameters() }
-    }
+    pub fn from_audio_config(
+        sample_rate: f32,
+        channels: u16,
+        format: SampleFormat,
+        operating_mode: i32,
+    ) -> Result<Self> {
+        if channels != 1 {
+            return Err(Error::InvalidParameter("Only mono audio is supported"));
+        }
 
-    /// Check if the instance is configured for fixed-length payloads
-    fn is_fixed_length(&self) -> bool {
-        unsafe {
-            let params = ggwave_getDefaultParameters();
-            params.payloadLength > 0
+        if !(sample_rate.is_finite() && sample_rate > 0.0) {
+            return Err(Error::InvalidParameter("Sample rate must be positive"));
         }
+
+        convert::sample_size(format)?;
+
+        let mut params = unsafe { ggwave_getDefaultParameters() };
+        params.sampleRate = sample_rate;
+        params.sampleRateInp = sample_rate;
+        params.sampleRateOut = sample_rate;
+        params.sampleFormatInp = format;
+        params.sampleFormatOut = format;
+        params.operatingMode = operating_mode;
+        Self::new_with_params(params)
     }
 
-    /// Calculate the required buffer size for encoding text
+    #[cfg(feature = "std")]
+    /// Create a GGWave instance matching the format of a WAV file
     ///
-    /// # Arguments
-    ///
-    /// * `text` - The text to encode
-    /// * `protocol_id` - The protocol to use for encoding
-    /// * `volume` - The volume of the encoded audio (0-100)
+    /// Equivalent to [`GGWave::from_audio_config`] with the sample rate,
+    /// channel count and sample format of `spec`. 8-bit, 16-bit and 32-bit
+    /// float samples are supported.
     ///
-    /// # Returns
+    /// # Arguments
     ///
-    /// A `Result` containing the required buffer size in bytes
-    pub fn calculate_encode_buffer_size(
-        &self,
-        text: &str,
-        protocol_id: ProtocolId,
-        volume: i32,
-    ) -> Result<usize> {
-        let max_length = if self.is_fixed_length() {
-            unsafe { ggwave_getDefaultParameters().payloadLength as usize }
-        } else {
-            constants::MAX_LENGTH_VARIABLE
+    /// * `spec` - The format of the WAV file
+    /// * `operating_mode` - The operating mode, see [`operating_modes`]
+    pub fn from_wav_spec(spec: &WavSpec, operating_mode: i32) -> Result<Self> {
+        let format = match (spec.sample_format, spec.bits_per_sample) {
+            (hound::SampleFormat::Int, 8) => sample_formats::U8,
+            (hound::SampleFormat::Int, 16) => sample_formats::I16,
+            (hound::SampleFormat::Float, 32) => sample_formats::F32,
+            _ => return Err(Error::InvalidSampleFormat),
         };
 
-        if text.len() > max_length {
-            return Err(Error::TextTooLong {
-                length: text.len(),
-                max: max_length,
-            });
+        Self::from_audio_config(
+            spec.sample_rate as f32,
+            spec.channels,
+            format,
+            operating_mode,
+        )
+    }
+
+    /// Create an instance, switching the global protocol tables to the given
+    /// masks while the C library copies them
+    ///
+    /// Every constructor ends up here, so this is the one place the result of
+    /// `ggwave_init` is checked, see [`ffi::helpers::is_valid_instance`], and
+    /// the one place the instance limit is enforced. Instances are created and
+    /// freed under the global write lock, so the live count cannot change
+    /// between the check and `ggwave_init`.
+    fn init(
+        params: Parameters,
+        rx_protocols: Option<ProtocolMask>,
+        tx_protocols: Option<ProtocolMask>,
+    ) -> Result<Self> {
+        let _global = global_write();
+        let rx_protocols = rx_protocols.unwrap_or_else(protocol_mask::global_rx);
+        let tx_protocols = tx_protocols.unwrap_or_else(protocol_mask::global_tx);
+        Self::check_parameters(&params, rx_protocols, tx_protocols)?;
+        if LIVE_INSTANCES.load(Ordering::Relaxed) >= MAX_INSTANCES {
+            return Err(Error::InstanceLimitReached { max: MAX_INSTANCES });
         }
 
         unsafe {
-            let payload_buffer = text.as_ptr() as *const c_void;
-            let payload_size = text.len() as i32;
-
-            let waveform_size = ggwave_encode(
-                self.instance,
-                payload_buffer,
-                payload_size,
-                protocol_id,
-                volume,
-                ptr::null_mut(),
-                1, // query size in bytes
-            );
+            let previous_rx = protocol_mask::apply_rx(rx_protocols);
+            let previous_tx = protocol_mask::apply_tx(tx_protocols);
+            let instance = ggwave_init(params);
+            protocol_mask::apply_rx(previous_rx);
+            protocol_mask::apply_tx(previous_tx);
 
-            if waveform_size <= 0 {
-                Err(Error::EncodeFailed(waveform_size))
+            if !ffi::helpers::is_valid_instance(instance) {
+                Err(Error::InitializationFailed)
             } else {
-                Ok(waveform_size as usize)
+                LIVE_INSTANCES.fetch_add(1, Ordering::Relaxed);
+                Ok(Self {
+                    instance,
+                    params,
+                    max_output_samples: None,
+                    rx_protocols,
+                    tx_protocols,
+                    transport: TransportEncoding::Raw,
+                    #[cfg(feature = "metrics")]
+                    metrics: Default::default(),
+                    _not_sync: PhantomData,
+                })
             }
         }
     }
 
-    /// Encode text into a provided buffer
+    /// Check parameters that `ggwave_init` would reject or that would leave
+    /// no protocol usable, so the error can say what is wrong
+    fn check_parameters(
+        params: &Parameters,
+        rx_protocols: ProtocolMask,
+        tx_protocols: ProtocolMask,
+    ) -> Result<()> {
+        let rates = [
+            params.sampleRate,
+            params.sampleRateInp,
+            params.sampleRateOut,
+        ];
+        if !rates
+            .iter()
+            .all(|rate| (constants::MIN_SAMPLE_RATE..=constants::MAX_SAMPLE_RATE).contains(rate))
+        {
+            return Err(Error::InvalidParameter(
+                "Sample rates must be between 1000 and 96000 Hz",
+            ));
+        }
+
+        if !(1..=constants::MAX_SAMPLES_PER_FRAME).contains(&params.samplesPerFrame) {
+            return Err(Error::InvalidParameter(
+                "Samples per frame must be between 1 and 1024",
+            ));
+        }
+
+        // Tones sit on FFT bins of the frame and must stay below its Nyquist bin
+        let mut enabled = Vec::new();
+        if params.operatingMode & operating_modes::RX != 0 {
+            enabled.extend(rx_protocols.iter());
+        }
+        if params.operatingMode & operating_modes::TX != 0 {
+            enabled.extend(tx_protocols.iter());
+        }
+        let top_bins: Vec<i32> = enabled
+            .into_iter()
+            .filter_map(|id| protocols::bins(id).map(|bins| bins.end))
+            .collect();
+        if !top_bins.is_empty()
+            && !top_bins
+                .iter()
+                .any(|&top| top <= params.samplesPerFrame / 2)
+        {
+            return Err(Error::InvalidParameter(
+                "Samples per frame too small for the enabled protocols",
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    /// Recommend a sound marker threshold for a recording of background noise
+    ///
+    /// The recording is analyzed the way the decoder looks for sound markers
+    /// on the marker bins of this instance's rx protocols, and the returned
+    /// threshold is comfortably above the highest marker-like ratio the noise
+    /// produced, clamped to 0.5-10.0. See
+    /// [`GGWaveBuilder::sound_marker_threshold`] for what the threshold does.
+    /// A second or two of the room with nothing transmitting is enough.
     ///
     /// # Arguments
     ///
-    /// * `text` - The text to encode
-    /// * `protocol_id` - The protocol to use for encoding
-    /// * `volume` - The volume of the encoded audio (0-100)
-    /// * `buffer` - The buffer to encode into
+    /// * `noise_samples` - Normalized mono samples at the input sample rate
     ///
     /// # Returns
     ///
-    /// A `Result` containing the number of bytes written to the buffer
+    /// The recommended threshold, or
+    /// [`constants::DEFAULT_SOUND_MARKER_THRESHOLD`] if the recording is
+    /// silent
     ///
     /// # Examples
     ///
     /// ```
-    /// use ggwave_rs::{GGWave, protocols};
+    /// use ggwave_rs::GGWave;
     ///
     /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
-    /// let text = "Hello, World!";
-    ///
-    /// // Calculate needed buffer size
-    /// let size = ggwave.calculate_encode_buffer_size(text, protocols::AUDIBLE_NORMAL, 50)
-    ///     .expect("Failed to calculate buffer size");
-    ///
-    /// // Allocate buffer
-    /// let mut buffer = vec![0u8; size];
-    ///
-    /// // Encode into the buffer
-    /// let bytes_written = ggwave.encode_into_buffer(text, protocols::AUDIBLE_NORMAL, 50, &mut buffer)
-    ///     .expect("Failed to encode text");
+    /// // One second recorded while nothing was transmitting
+    /// let noise = vec![0.0f32; 48000];
     ///
-    /// assert!(bytes_written <= buffer.len());
+    /// let threshold = ggwave.calibrate_marker_threshold(&noise);
+    /// let tuned = GGWave::builder()
+    ///     .sound_marker_threshold(threshold)
+    ///     .build()
+    ///     .expect("Failed to initialize GGWave");
     /// ```
-    pub fn encode_into_buffer(
-        &self,
-        text: &str,
-        protocol_id: ProtocolId,
-        volume: i32,
-        buffer: &mut [u8],
-    ) -> Result<usize> {
-        let required_size = self.calculate_encode_buffer_size(text, protocol_id, volume)?;
-
-        if buffer.len() < required_size {
-            return Err(Error::BufferTooSmall {
-                required: required_size,
-                provided: buffer.len(),
-            });
-        }
-
-        unsafe {
-            let payload_buffer = text.as_ptr() as *const c_void;
-            let payload_size = text.len() as i32;
+    pub fn calibrate_marker_threshold(&self, noise_samples: &[f32]) -> f32 {
+        Self::marker_threshold_for(&self.params, self.rx_protocols, noise_samples)
+    }
 
-            let result = ggwave_encode(
-                self.instance,
-                payload_buffer,
-                payload_size,
-                protocol_id,
-                volume,
-                buffer.as_mut_ptr() as *mut c_void,
-                0, // perform actual encoding
-            );
+    #[cfg(feature = "std")]
+    /// Recommend a sound marker threshold for the given parameters and rx protocols
+    fn marker_threshold_for(params: &Parameters, rx_protocols: ProtocolMask, noise: &[f32]) -> f32 {
+        let (starts, bin_width) = Self::marker_starts(params, rx_protocols);
+        signal::recommend_marker_threshold(noise, &starts, bin_width, params.sampleRateInp)
+            .unwrap_or(constants::DEFAULT_SOUND_MARKER_THRESHOLD)
+    }
 
-            if result < 0 {
-                Err(Error::EncodeFailed(result))
-            } else {
-                Ok(result as usize)
-            }
-        }
+    #[cfg(feature = "std")]
+    /// Frequencies of the first marker bin of the given rx protocols, and the
+    /// width of a bin, in Hz
+    ///
+    /// Protocols with bins above the Nyquist frequency of the input, which
+    /// cannot be measured there, are left out.
+    fn marker_starts(params: &Parameters, rx_protocols: ProtocolMask) -> (Vec<f32>, f32) {
+        let bin_width = params.sampleRate / params.samplesPerFrame.max(1) as f32;
+        let nyquist = params.sampleRateInp / 2.0;
+        let mut starts: Vec<f32> = rx_protocols
+            .iter()
+            .filter_map(protocols::bins)
+            .filter(|bins| (bins.end as f32 * bin_width) < nyquist)
+            .map(|bins| bins.start as f32 * bin_width)
+            .collect();
+        starts.dedup();
+        (starts, bin_width)
     }
 
-    /// Encode text to raw audio data with heap allocation
+    #[cfg(feature = "std")]
+    /// Check cheaply whether a recording holds a transmission
+    ///
+    /// Looks for the sound marker that opens and closes every transmission on
+    /// the marker bins of this instance's rx protocols, using the instance's
+    /// sound marker threshold, without running the decoder. This is much
+    /// cheaper than [`GGWave::decode`], so long recordings can be cut into
+    /// blocks and only the blocks that contain a marker decoded. A block that
+    /// holds only the middle of a long transmission has no marker, so blocks
+    /// should be at least as long as the longest expected message.
     ///
     /// # Arguments
     ///
-    /// * `text` - The text to encode
-    /// * `protocol_id` - The protocol to use for encoding
-    /// * `volume` - The volume of the encoded audio (0-100)
+    /// * `samples` - Normalized mono samples at the input sample rate
     ///
     /// # Returns
     ///
-    /// A `Result` containing a `Vec<u8>` with the encoded audio data
+    /// `true` if enough consecutive frames of `samples` pass as a sound marker
+    /// to rule out a chance match in noise
     ///
     /// # Examples
     ///
@@ -578,181 +1406,178 @@ impl GGWave {
     /// use ggwave_rs::{GGWave, protocols};
     ///
     /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
-    /// let waveform = ggwave.encode("Hello, World!", protocols::AUDIBLE_NORMAL, 50)
+    /// assert!(!ggwave.contains_signal(&vec![0.0; 48000]));
+    ///
+    /// let audio = ggwave
+    ///     .encode_full("Hello, World!", protocols::AUDIBLE_FAST, 50)
     ///     .expect("Failed to encode text");
+    /// assert!(ggwave.contains_signal(&audio.as_f32().unwrap()));
     /// ```
-    pub fn encode(&self, text: &str, protocol_id: ProtocolId, volume: i32) -> Result<Vec<u8>> {
-        let size = self.calculate_encode_buffer_size(text, protocol_id, volume)?;
-        let mut buffer = vec![0u8; size];
-        let written = self.encode_into_buffer(text, protocol_id, volume, &mut buffer)?;
+    pub fn contains_signal(&self, samples: &[f32]) -> bool {
+        let (starts, bin_width) = Self::marker_starts(&self.params, self.rx_protocols);
+        signal::contains_marker(
+            samples,
+            &starts,
+            bin_width,
+            self.params.sampleRateInp,
+            self.params.soundMarkerThreshold,
+        )
+    }
 
-        // Trim the buffer to the actual size if needed
-        if written < buffer.len() {
-            buffer.truncate(written);
-        }
+    /// Get the parameters this instance was created with
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::GGWave;
+    ///
+    /// let ggwave = GGWave::builder()
+    ///     .sample_rate(48000.0)
+    ///     .build()
+    ///     .expect("Failed to initialize GGWave");
+    /// assert_eq!(ggwave.parameters().sampleRateInp, 48000.0);
+    /// ```
+    pub fn parameters(&self) -> &Parameters {
+        &self.params
+    }
 
-        Ok(buffer)
+    /// Get the protocols this instance can receive
+    pub fn rx_protocols(&self) -> ProtocolMask {
+        self.rx_protocols
     }
 
-    /// Decode raw audio data to text using a provided buffer
-    ///
-    /// # Arguments
+    /// Get the protocols this instance can transmit
+    pub fn tx_protocols(&self) -> ProtocolMask {
+        self.tx_protocols
+    }
+
+    /// Create an independent instance with the same parameters and protocols
     ///
-    /// * `waveform` - The raw audio data to decode
-    /// * `buffer` - Buffer to store the decoded payload
+    /// The new instance has its own receive state and uses one of the
+    /// [`MAX_INSTANCES`] slots, so it can be used on another thread at the
+    /// same time as this one. The maximum output sample count is kept too.
     ///
     /// # Returns
     ///
-    /// A `Result` containing the decoded text as a string slice
+    /// A `Result` containing the new instance, or
+    /// `Error::InstanceLimitReached` if all instance slots are in use
     ///
     /// # Examples
     ///
     /// ```
-    /// use ggwave_rs::{GGWave, protocols};
+    /// use ggwave_rs::GGWave;
     ///
     /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
-    /// let waveform = ggwave.encode("Hello, World!", protocols::AUDIBLE_NORMAL, 50)
-    ///     .expect("Failed to encode text");
-    ///
-    /// let mut buffer = vec![0u8; 1024];
-    /// let decoded = ggwave.decode(&waveform, &mut buffer)
-    ///     .expect("Failed to decode waveform");
-    ///
-    /// assert_eq!(decoded, "Hello, World!");
+    /// let copy = ggwave.try_clone().expect("Failed to clone GGWave");
+    /// assert_ne!(copy.raw_instance(), ggwave.raw_instance());
+    /// assert_eq!(copy.parameters().sampleRateOut, ggwave.parameters().sampleRateOut);
     /// ```
-    pub fn decode<'a>(&self, waveform: &[u8], buffer: &'a mut [u8]) -> Result<&'a str> {
-        unsafe {
-            let waveform_buffer = waveform.as_ptr() as *const c_void;
-            let waveform_size = waveform.len() as i32;
+    pub fn try_clone(&self) -> Result<Self> {
+        self.clone_with_parameters(self.params)
+    }
 
-            let result = ggwave_ndecode(
-                self.instance,
-                waveform_buffer,
-                waveform_size,
-                buffer.as_mut_ptr() as *mut c_void,
-                buffer.len() as i32,
-            );
+    /// Create an instance with other parameters but the protocols, output
+    /// limit and transport encoding of this one
+    fn clone_with_parameters(&self, params: Parameters) -> Result<Self> {
+        let mut clone = Self::init(params, Some(self.rx_protocols), Some(self.tx_protocols))?;
+        clone.max_output_samples = self.max_output_samples;
+        clone.transport = self.transport;
+        Ok(clone)
+    }
 
-            if result < 0 {
-                Err(Error::DecodeFailed(result))
-            } else {
-                // Return slice to valid data
-                std::str::from_utf8(&buffer[..result as usize]).map_err(Error::Utf8Error)
-            }
-        }
+    /// Get default parameters for ggwave
+    ///
+    /// # Returns
+    ///
+    /// A `Parameters` struct with default values
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::GGWave;
+    ///
+    /// let params = GGWave::default_parameters();
+    /// println!("Default sample rate: {}", params.sampleRate);
+    /// ```
+    pub fn default_parameters() -> Parameters {
+        unsafe { ggwave_getDefaultParameters() }
     }
 
-    /// Decode raw audio data to text with heap allocation
+    /// Get the number of instances currently alive in this process
     ///
-    /// # Arguments
+    /// Counts every `GGWave` value, including those held by pools and
+    /// decoders, but not instances created directly through [`ffi`]. At most
+    /// [`MAX_INSTANCES`] can be alive at once.
     ///
-    /// * `waveform` - The raw audio data to decode
-    /// * `max_payload_size` - The maximum size of the decoded payload
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, MAX_INSTANCES};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let free = MAX_INSTANCES - GGWave::live_instance_count();
+    /// println!("{} more instances can be created", free);
+    /// ```
+    pub fn live_instance_count() -> usize {
+        LIVE_INSTANCES.load(Ordering::Relaxed)
+    }
+
+    /// Check if the instance is configured for fixed-length payloads
+    fn is_fixed_length(&self) -> bool {
+        self.params.payloadLength > 0
+    }
+
+    /// Get the maximum payload length this instance can encode
     ///
     /// # Returns
     ///
-    /// A `Result` containing the decoded text
+    /// The fixed payload length if one is configured, otherwise
+    /// [`constants::MAX_LENGTH_VARIABLE`], reduced by the overhead of the
+    /// [`TransportEncoding`]
     ///
     /// # Examples
     ///
     /// ```
-    /// use ggwave_rs::{GGWave, protocols};
+    /// use ggwave_rs::{GGWave, operating_modes};
     ///
     /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
-    /// let waveform = ggwave.encode("Hello, World!", protocols::AUDIBLE_NORMAL, 50)
-    ///     .expect("Failed to encode text");
-    ///
-    /// let decoded = ggwave.decode_to_string(&waveform, 1024)
-    ///     .expect("Failed to decode waveform");
+    /// assert_eq!(ggwave.max_payload_len(), 140);
     ///
-    /// assert_eq!(decoded, "Hello, World!");
+    /// let fixed = GGWave::new_with_fixed_payload(16, operating_modes::RX_AND_TX)
+    ///     .expect("Failed to initialize GGWave");
+    /// assert_eq!(fixed.max_payload_len(), 16);
     /// ```
-    pub fn decode_to_string(&self, waveform: &[u8], max_payload_size: usize) -> Result<String> {
-        let mut buffer = vec![0u8; max_payload_size];
-        let decoded = self.decode(waveform, &mut buffer)?;
-        Ok(decoded.to_string())
+    pub fn max_payload_len(&self) -> usize {
+        let capacity = if self.is_fixed_length() {
+            self.params.payloadLength as usize
+        } else {
+            constants::MAX_LENGTH_VARIABLE
+        };
+        self.transport.max_payload_len(capacity)
     }
 
-    /// Get the current output sample format
-    ///
-    /// # Returns
+    /// Get how payloads are represented on the air
     ///
-    /// The current output sample format
-    pub fn get_output_sample_format(&self) -> SampleFormat {
-        unsafe { ggwave_getDefaultParameters().sampleFormatOut }
+    /// Set with [`GGWaveBuilder::transport_encoding`].
+    pub fn transport_encoding(&self) -> TransportEncoding {
+        self.transport
     }
 
-    /// Convert raw audio data to WAV format in memory
+    /// Check whether a text fits in a single transmission of this instance
     ///
     /// # Arguments
     ///
-    /// * `raw_data` - The raw audio data to convert
+    /// * `text` - The text to check
     ///
     /// # Returns
     ///
-    /// A `Result` containing a `Vec<u8>` with the WAV data
-    pub fn raw_to_wav(&self, raw_data: &[u8]) -> Result<Vec<u8>> {
-        let params = unsafe { ggwave_getDefaultParameters() };
-        let sample_rate = params.sampleRateOut as u32;
-        let format = params.sampleFormatOut;
-
-        // Create WAV spec
-        let spec = WavSpec {
-            channels: 1,
-            sample_rate,
-            bits_per_sample: 16,
-            sample_format: hound::SampleFormat::Int,
-        };
-
-        let mut buffer = Vec::new();
-        let mut writer =
-            WavWriter::new(Cursor::new(&mut buffer), spec).map_err(Error::WavWriteFailed)?;
-
-        match format {
-            // Float32 format
-            ggwave_SampleFormat_GGWAVE_SAMPLE_FORMAT_F32 => {
-                let samples = unsafe {
-                    std::slice::from_raw_parts(
-                        raw_data.as_ptr() as *const f32,
-                        raw_data.len() / std::mem::size_of::<f32>(),
-                    )
-                };
-
-                for &sample in samples {
-                    let sample_i16 = (sample.clamp(-1.0, 1.0) * 32767.0) as i16;
-                    writer.write_sample(sample_i16)?;
-                }
-            }
-            // Int16 format
-            ggwave_SampleFormat_GGWAVE_SAMPLE_FORMAT_I16 => {
-                let samples = unsafe {
-                    std::slice::from_raw_parts(
-                        raw_data.as_ptr() as *const i16,
-                        raw_data.len() / std::mem::size_of::<i16>(),
-                    )
-                };
-
-                for &sample in samples {
-                    writer.write_sample(sample)?;
-                }
-            }
-            // Other formats (best effort)
-            _ => {
-                let samples = unsafe {
-                    std::slice::from_raw_parts(raw_data.as_ptr() as *const i16, raw_data.len() / 2)
-                };
-
-                for &sample in samples {
-                    writer.write_sample(sample)?;
-                }
-            }
-        }
-
-        writer.finalize()?;
-        Ok(buffer)
+    /// `true` if the text is not empty and at most [`GGWave::max_payload_len`]
+    /// bytes long
+    pub fn can_encode(&self, text: &str) -> bool {
+        !text.is_empty() && text.len() <= self.max_payload_len()
     }
 
-    /// Encode text and convert to WAV format
+    /// Calculate the required buffer size for encoding text
     ///
     /// # Arguments
     ///
@@ -762,58 +1587,49 @@ impl GGWave {
     ///
     /// # Returns
     ///
-    /// A `Result` containing a `Vec<u8>` with the WAV data
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use ggwave_rs::{GGWave, protocols};
-    /// use std::fs;
-    ///
-    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
-    /// let wav_data = ggwave.encode_to_wav("Hello, World!", protocols::AUDIBLE_NORMAL, 50)
-    ///     .expect("Failed to encode text to WAV");
-    ///
-    /// fs::write("hello.wav", wav_data).expect("Failed to write WAV file");
-    /// ```
-    pub fn encode_to_wav(
+    /// A `Result` containing the required buffer size in bytes, or
+    /// `Error::TextTooLong` if the text is longer than
+    /// [`GGWave::max_payload_len`], which is the fixed payload length for
+    /// fixed-length instances
+    pub fn calculate_encode_buffer_size(
         &self,
         text: &str,
         protocol_id: ProtocolId,
         volume: i32,
-    ) -> Result<Vec<u8>> {
-        let raw_data = self.encode(text, protocol_id, volume)?;
-        self.raw_to_wav(&raw_data)
+    ) -> Result<usize> {
+        self.payload_buffer_size(text.as_bytes(), protocol_id, volume)
     }
 
-    /// Save raw audio data to a WAV file
-    ///
-    /// # Arguments
-    ///
-    /// * `raw_data` - The raw audio data to save
-    /// * `path` - The path to save the WAV file to
-    ///
-    /// # Returns
-    ///
-    /// A `Result` indicating success or failure
-    pub fn save_raw_to_wav<P: AsRef<Path>>(&self, raw_data: &[u8], path: P) -> Result<()> {
-        let wav_data = self.raw_to_wav(raw_data)?;
-        std::fs::write(path, wav_data)?;
-        Ok(())
+    /// Size in bytes of the encoded waveform of a payload, within the output limit
+    fn payload_buffer_size(
+        &self,
+        payload: &[u8],
+        protocol_id: ProtocolId,
+        volume: i32,
+    ) -> Result<usize> {
+        let size = self.query_encode_size(payload, protocol_id, volume)?;
+
+        if let Some(max) = self.max_output_samples {
+            let samples = size / convert::sample_size(self.params.sampleFormatOut)?;
+            if samples > max {
+                return Err(Error::OutputTooLarge { samples, max });
+            }
+        }
+
+        Ok(size)
     }
 
-    /// Encode text and save directly to a WAV file
+    /// Calculate the number of samples the encoded waveform of a text will have
     ///
     /// # Arguments
     ///
     /// * `text` - The text to encode
     /// * `protocol_id` - The protocol to use for encoding
     /// * `volume` - The volume of the encoded audio (0-100)
-    /// * `path` - The path to save the WAV file to
     ///
     /// # Returns
     ///
-    /// A `Result` indicating success or failure
+    /// A `Result` containing the number of samples at the output sample rate
     ///
     /// # Examples
     ///
@@ -821,444 +1637,5925 @@ impl GGWave {
     /// use ggwave_rs::{GGWave, protocols};
     ///
     /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
-    /// ggwave.encode_to_wav_file("Hello, World!", protocols::AUDIBLE_NORMAL, 50, "hello.wav")
-    ///     .expect("Failed to encode and save WAV file");
+    /// let samples = ggwave.encoded_sample_count("Hello, World!", protocols::AUDIBLE_FAST, 50)
+    ///     .expect("Failed to calculate sample count");
+    ///
+    /// let waveform = ggwave.encode("Hello, World!", protocols::AUDIBLE_FAST, 50)
+    ///     .expect("Failed to encode text");
+    /// assert_eq!(samples * 4, waveform.len()); // F32 output
     /// ```
-    pub fn encode_to_wav_file<P: AsRef<Path>>(
+    pub fn encoded_sample_count(
         &self,
         text: &str,
         protocol_id: ProtocolId,
         volume: i32,
-        path: P,
-    ) -> Result<()> {
-        let raw_data = self.encode(text, protocol_id, volume)?;
-        self.save_raw_to_wav(&raw_data, path)
+    ) -> Result<usize> {
+        let size = self.query_encode_size(text.as_bytes(), protocol_id, volume)?;
+        Ok(size / convert::sample_size(self.params.sampleFormatOut)?)
     }
 
-    /// Toggle reception of a specific protocol
-    ///
-    /// # Arguments
-    ///
-    /// * `protocol_id` - The protocol to toggle
-    /// * `enabled` - Whether to enable or disable the protocol
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use ggwave_rs::{GGWave, protocols};
-    ///
-    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
-    /// // Disable reception of ultrasound protocols
-    /// ggwave.toggle_rx_protocol(protocols::ULTRASOUND_NORMAL, false);
-    /// ggwave.toggle_rx_protocol(protocols::ULTRASOUND_FAST, false);
-    /// ggwave.toggle_rx_protocol(protocols::ULTRASOUND_FASTEST, false);
-    /// ```
-    pub fn toggle_rx_protocol(&self, protocol_id: ProtocolId, enabled: bool) {
-        unsafe {
-            ggwave_rxToggleProtocol(protocol_id, if enabled { 1 } else { 0 });
+    /// Query the size in bytes of the encoded waveform of a payload
+    fn query_encode_size(
+        &self,
+        payload: &[u8],
+        protocol_id: ProtocolId,
+        volume: i32,
+    ) -> Result<usize> {
+        if !(constants::MIN_VOLUME..=constants::MAX_VOLUME).contains(&volume) {
+            return Err(Error::InvalidParameter("Volume must be 0-100"));
         }
-    }
 
-    /// Toggle transmission of a specific protocol
-    ///
-    /// # Arguments
-    ///
-    /// * `protocol_id` - The protocol to toggle
-    /// * `enabled` - Whether to enable or disable the protocol
-    pub fn toggle_tx_protocol(&self, protocol_id: ProtocolId, enabled: bool) {
-        unsafe {
-            ggwave_txToggleProtocol(protocol_id, if enabled { 1 } else { 0 });
+        if payload.is_empty() {
+            return Err(Error::InvalidParameter("Text must not be empty"));
         }
-    }
 
-    /// Set the starting frequency for a reception protocol
-    ///
-    /// # Arguments
-    ///
-    /// * `protocol_id` - The protocol to modify
-    /// * `freq_start` - The starting frequency in Hz
-    pub fn set_rx_protocol_freq_start(&self, protocol_id: ProtocolId, freq_start: i32) {
-        unsafe {
-            ggwave_rxProtocolSetFreqStart(protocol_id, freq_start);
+        let max_length = self.max_payload_len();
+
+        if payload.len() > max_length {
+            return Err(Error::TextTooLong {
+                length: payload.len(),
+                max: max_length,
+            });
+        }
+
+        let payload = self.transport.encode(payload);
+        let waveform_size = {
+            let _global = global_read();
+            unsafe {
+                ggwave_encode(
+                    self.instance,
+                    payload.as_ptr() as *const c_void,
+                    payload.len() as i32,
+                    protocol_id,
+                    volume,
+                    ptr::null_mut(),
+                    1, // query size in bytes
+                )
+            }
+        };
+
+        if waveform_size <= 0 {
+            Err(Error::EncodeFailed(waveform_size))
+        } else {
+            Ok(waveform_size as usize)
         }
     }
 
-    /// Set the starting frequency for a transmission protocol
+    /// Encode text into a provided buffer
     ///
     /// # Arguments
     ///
-    /// * `protocol_id` - The protocol to modify
-    /// * `freq_start` - The starting frequency in Hz
-    pub fn set_tx_protocol_freq_start(&self, protocol_id: ProtocolId, freq_start: i32) {
-        unsafe {
-            ggwave_txProtocolSetFreqStart(protocol_id, freq_start);
-        }
-    }
-
-    /// Get the duration in frames for reception
+    /// * `text` - The text to encode
+    /// * `protocol_id` - The protocol to use for encoding
+    /// * `volume` - The volume of the encoded audio (0-100)
+    /// * `buffer` - The buffer to encode into
     ///
     /// # Returns
     ///
-    /// The duration in frames
-    pub fn rx_duration_frames(&self) -> i32 {
-        unsafe { ggwave_rxDurationFrames(self.instance) }
-    }
-
-    /// Set debug mode and optionally redirect logs to a file
+    /// A `Result` containing the number of bytes written to the buffer
     ///
-    /// # Arguments
+    /// # Examples
     ///
-    /// * `debug_file` - Optional path to a log file, or None to disable logging
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
     ///
-    /// # Safety
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let text = "Hello, World!";
     ///
-    /// This function is marked safe but internally uses unsafe operations to interact
-    /// with C file handling. The file path must be valid and accessible.
-    pub fn set_debug_mode(&self, debug_file: Option<&str>) {
-        unsafe {
-            match debug_file {
-                Some(path) => {
-                    // Try to open the file in C
-                    let c_str = std::ffi::CString::new(path).unwrap();
-                    let mode = std::ffi::CString::new("w").unwrap();
-                    let file_ptr = libc::fopen(c_str.as_ptr(), mode.as_ptr());
-                    if !file_ptr.is_null() {
-                        ggwave_setLogFile(file_ptr as *mut c_void);
-                    }
-                }
-                None => {
-                    // Disable logging
-                    ggwave_setLogFile(std::ptr::null_mut());
-                }
-            }
-        }
-    }
-
-    /// Enables all reception protocols
+    /// // Calculate needed buffer size
+    /// let size = ggwave.calculate_encode_buffer_size(text, protocols::AUDIBLE_NORMAL, 50)
+    ///     .expect("Failed to calculate buffer size");
     ///
-    /// This is a convenience method to enable all available protocols for reception.
-    pub fn enable_all_rx_protocols(&self) {
-        for protocol_id in 0..protocols::COUNT {
-            self.toggle_rx_protocol(protocol_id, true);
-        }
+    /// // Allocate buffer
+    /// let mut buffer = vec![0u8; size];
+    ///
+    /// // Encode into the buffer
+    /// let bytes_written = ggwave.encode_into_buffer(text, protocols::AUDIBLE_NORMAL, 50, &mut buffer)
+    ///     .expect("Failed to encode text");
+    ///
+    /// assert!(bytes_written <= buffer.len());
+    /// ```
+    pub fn encode_into_buffer(
+        &self,
+        text: &str,
+        protocol_id: ProtocolId,
+        volume: i32,
+        buffer: &mut [u8],
+    ) -> Result<usize> {
+        self.encode_payload_into(text.as_bytes(), protocol_id, volume, buffer)
     }
 
-    /// Decode raw audio data to binary data
+    /// Encode text into a provided buffer at a validated volume
     ///
-    /// This variant of decode is useful when the data being transmitted is not UTF-8 text.
+    /// Like [`GGWave::encode_into_buffer`], but the volume is a [`Volume`],
+    /// which is always in range.
     ///
     /// # Arguments
     ///
-    /// * `waveform` - The raw audio data to decode
-    /// * `buffer` - Buffer to store the decoded payload
+    /// * `text` - The text to encode
+    /// * `protocol_id` - The protocol to use for encoding
+    /// * `volume` - The volume of the encoded audio
+    /// * `buffer` - The buffer to write the encoded audio data to
     ///
     /// # Returns
     ///
-    /// A `Result` containing a slice of the decoded binary data
-    pub fn decode_binary<'a>(&self, waveform: &[u8], buffer: &'a mut [u8]) -> Result<&'a [u8]> {
+    /// A `Result` containing the number of bytes written to the buffer
+    pub fn encode_into_buffer_v(
+        &self,
+        text: &str,
+        protocol_id: ProtocolId,
+        volume: impl Into<Volume>,
+        buffer: &mut [u8],
+    ) -> Result<usize> {
+        self.encode_into_buffer(text, protocol_id, volume.into().into(), buffer)
+    }
+
+    /// Encode a payload of arbitrary bytes into a provided buffer
+    fn encode_payload_into(
+        &self,
+        payload: &[u8],
+        protocol_id: ProtocolId,
+        volume: i32,
+        buffer: &mut [u8],
+    ) -> Result<usize> {
+        let required_size = self.payload_buffer_size(payload, protocol_id, volume)?;
+
+        if buffer.len() < required_size {
+            return Err(Error::BufferTooSmall {
+                required: required_size,
+                provided: buffer.len(),
+            });
+        }
+
+        let payload = self.transport.encode(payload);
+        let _global = global_read();
         unsafe {
-            let result = ggwave_ndecode(
+            let payload_buffer = payload.as_ptr() as *const c_void;
+            let payload_size = payload.len() as i32;
+
+            let result = ggwave_encode(
                 self.instance,
-                waveform.as_ptr() as *const c_void,
-                waveform.len() as i32,
+                payload_buffer,
+                payload_size,
+                protocol_id,
+                volume,
                 buffer.as_mut_ptr() as *mut c_void,
-                buffer.len() as i32,
+                0, // perform actual encoding
             );
 
             if result < 0 {
-                Err(Error::DecodeFailed(result))
+                Err(Error::EncodeFailed(result))
             } else {
-                Ok(&buffer[..result as usize])
+                Ok(result as usize)
             }
         }
     }
 
-    /// Memory-efficient continuous audio decoder
-    ///
-    /// This method is designed for real-time continuous audio processing where
-    /// audio is being processed in chunks, such as from a microphone input.
+    /// Encode text to raw audio data with heap allocation
     ///
     /// # Arguments
     ///
-    /// * `audio_chunk` - New chunk of audio data to process
-    /// * `decode_buffer` - Buffer to store decoded payload if found
+    /// * `text` - The text to encode
+    /// * `protocol_id` - The protocol to use for encoding
+    /// * `volume` - The volume of the encoded audio (0-100)
     ///
     /// # Returns
     ///
-    /// A `Result` containing an Option with the decoded string if something was found
-    pub fn process_audio_chunk<'a>(
-        &self,
-        audio_chunk: &[u8],
-        decode_buffer: &'a mut [u8],
-    ) -> Result<Option<&'a str>> {
-        unsafe {
-            let result = ggwave_decode(
-                self.instance,
-                audio_chunk.as_ptr() as *const c_void,
-                audio_chunk.len() as i32,
-                decode_buffer.as_mut_ptr() as *mut c_void,
-            );
-
-            if result < 0 {
-                // No data found or error
-                if result < 0 {
-                    Err(Error::DecodeFailed(result))
-                } else {
-                    Ok(None) // No data decoded, but no error
-                }
-            } else {
-                // Something was decoded
-                match std::str::from_utf8(&decode_buffer[..result as usize]) {
-                    Ok(s) => Ok(Some(s)),
-                    Err(e) => Err(Error::Utf8Error(e)),
-                }
-            }
-        }
+    /// A `Result` containing a `Vec<u8>` with the encoded audio data
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let waveform = ggwave.encode("Hello, World!", protocols::AUDIBLE_NORMAL, 50)
+    ///     .expect("Failed to encode text");
+    /// ```
+    pub fn encode(&self, text: &str, protocol_id: ProtocolId, volume: i32) -> Result<Vec<u8>> {
+        self.encode_payload(text.as_bytes(), protocol_id, volume)
     }
 
-    /// Estimate the duration of the encoded audio in seconds
+    /// Encode text to audio data that records its own format
+    ///
+    /// Like [`GGWave::encode`], but the waveform is returned together with the
+    /// output sample rate and format of this instance, so it can be converted
+    /// with [`EncodedAudio::as_f32`] or [`EncodedAudio::to_wav`] elsewhere.
     ///
     /// # Arguments
     ///
-    /// * `protocol_id` - The protocol used for encoding
-    /// * `text_length` - The length of the text in bytes
+    /// * `text` - The text to encode
+    /// * `protocol_id` - The protocol to use for encoding
+    /// * `volume` - The volume of the encoded audio (0-100)
     ///
     /// # Returns
     ///
-    /// Estimated duration in seconds
-    pub fn estimate_duration(&self, protocol_id: ProtocolId, text_length: usize) -> f32 {
-        // This is an approximation based on the protocol and text length
-        // Get parameters from the instance, but we don't need the sample rate for this calculation
-        unsafe { ggwave_getDefaultParameters() };
-
-        // Each protocol has different timing characteristics
-        let seconds_per_byte = match protocol_id {
-            id if id == protocols::AUDIBLE_FASTEST
-                || id == protocols::ULTRASOUND_FASTEST
-                || id == protocols::DT_FASTEST
-                || id == protocols::MT_FASTEST =>
-            {
-                0.01
-            }
-
-            id if id == protocols::AUDIBLE_FAST
-                || id == protocols::ULTRASOUND_FAST
-                || id == protocols::DT_FAST
-                || id == protocols::MT_FAST =>
-            {
-                0.02
-            }
-
-            _ => 0.03, // Normal speed protocols
-        };
-
-        // Base duration plus per-byte duration
-        let base_duration = 0.2; // Protocol overhead
-        base_duration + (text_length as f32 * seconds_per_byte)
+    /// A `Result` containing the encoded audio
+    pub fn encode_full(
+        &self,
+        text: &str,
+        protocol_id: ProtocolId,
+        volume: i32,
+    ) -> Result<EncodedAudio> {
+        EncodedAudio::new(self, self.encode(text, protocol_id, volume)?)
     }
-}
 
-impl Default for GGWave {
-    fn default() -> Self {
+    #[cfg(feature = "std")]
+    /// Encode text to audio data read through [`std::io::Read`]
+    ///
+    /// The waveform is encoded up front and handed out by the reader as it is
+    /// read, so it can be piped with [`std::io::copy`] into any sink.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to encode
+    /// * `protocol_id` - The protocol to use for encoding
+    /// * `volume` - The volume of the encoded audio (0-100)
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a reader over the encoded audio data
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let mut reader = ggwave
+    ///     .encode_reader("Hello, World!", protocols::AUDIBLE_FAST, 50)
+    ///     .expect("Failed to encode text");
+    ///
+    /// let mut sink = std::io::sink();
+    /// std::io::copy(&mut reader, &mut sink).expect("Failed to copy waveform");
+    /// ```
+    pub fn encode_reader(
+        &self,
+        text: &str,
+        protocol_id: ProtocolId,
+        volume: i32,
+    ) -> Result<EncodedAudioReader> {
+        Ok(self.encode_full(text, protocol_id, volume)?.into_reader())
+    }
+
+    /// Encode text to audio data at another output sample rate
+    ///
+    /// ggwave fixes the output rate when an instance is created, so for any
+    /// rate other than this instance's `sampleRateOut` a short-lived
+    /// transmit-only copy with that output rate is created for the call and
+    /// dropped afterwards. That costs an instance initialization per call and
+    /// needs a free instance slot, so it fails with
+    /// `Error::InstanceLimitReached` while [`MAX_INSTANCES`] instances are
+    /// alive, for example when a [`GGWavePool`] has handed them all out. The
+    /// processing rate is unchanged, so the waveform carries the same tones
+    /// and lasts as long; only the number of samples differs. Build a
+    /// dedicated instance with
+    /// [`GGWaveBuilder::output_sample_rate`] to encode at a rate repeatedly.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to encode
+    /// * `protocol_id` - The protocol to use for encoding
+    /// * `volume` - The volume of the encoded audio (0-100)
+    /// * `sample_rate` - The output sample rate in Hz
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the encoded audio at `sample_rate`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let audio = ggwave
+    ///     .encode_at_rate("Hello, World!", protocols::AUDIBLE_FAST, 50, 44100.0)
+    ///     .expect("Failed to encode text");
+    /// assert_eq!(audio.sample_rate, 44100.0);
+    /// ```
+    pub fn encode_at_rate(
+        &self,
+        text: &str,
+        protocol_id: ProtocolId,
+        volume: i32,
+        sample_rate: f32,
+    ) -> Result<EncodedAudio> {
+        if sample_rate == self.params.sampleRateOut {
+            return self.encode_full(text, protocol_id, volume);
+        }
+
+        let mut params = self.params;
+        params.sampleRateOut = sample_rate;
+        // The copy only encodes, so skip allocating the decoder
+        if params.operatingMode & operating_modes::TX != 0 {
+            params.operatingMode &= !operating_modes::RX;
+        }
+
+        self.clone_with_parameters(params)?
+            .encode_full(text, protocol_id, volume)
+    }
+
+    #[cfg(feature = "std")]
+    /// Encode text and write the waveform to a sink
+    ///
+    /// Unlike collecting the results of [`GGWave::encode`], only one waveform
+    /// is held in memory at a time, so output can be streamed to a file or
+    /// socket. For long batches, [`Encoder::encode_to_writer`] also reuses the
+    /// waveform buffer between messages.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to encode
+    /// * `protocol_id` - The protocol to use for encoding
+    /// * `volume` - The volume of the encoded audio (0-100)
+    /// * `writer` - The sink to write the encoded audio data to
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the number of bytes written
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    /// use std::io::BufWriter;
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let file = std::fs::File::create("messages.raw").expect("Failed to create file");
+    /// let mut writer = BufWriter::new(file);
+    ///
+    /// for text in ["first", "second", "third"] {
+    ///     ggwave.encode_to_writer(text, protocols::AUDIBLE_FAST, 50, &mut writer)
+    ///         .expect("Failed to encode text");
+    /// }
+    /// ```
+    pub fn encode_to_writer<W: std::io::Write>(
+        &self,
+        text: &str,
+        protocol_id: ProtocolId,
+        volume: i32,
+        writer: &mut W,
+    ) -> Result<usize> {
+        let waveform = self.encode(text, protocol_id, volume)?;
+        writer.write_all(&waveform)?;
+        Ok(waveform.len())
+    }
+
+    /// Encode text to raw audio data at a validated volume
+    ///
+    /// Like [`GGWave::encode`], but the volume is a [`Volume`], so an
+    /// out-of-range volume is caught when the `Volume` is created instead of
+    /// when encoding.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to encode
+    /// * `protocol_id` - The protocol to use for encoding
+    /// * `volume` - The volume of the encoded audio
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a `Vec<u8>` with the encoded audio data
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, Volume, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let waveform = ggwave.encode_v("Hello, World!", protocols::AUDIBLE_NORMAL, Volume::DEFAULT)
+    ///     .expect("Failed to encode text");
+    /// ```
+    pub fn encode_v(
+        &self,
+        text: &str,
+        protocol_id: ProtocolId,
+        volume: impl Into<Volume>,
+    ) -> Result<Vec<u8>> {
+        self.encode(text, protocol_id, volume.into().into())
+    }
+
+    /// Encode a payload of arbitrary bytes to raw audio data
+    fn encode_payload(
+        &self,
+        payload: &[u8],
+        protocol_id: ProtocolId,
+        volume: i32,
+    ) -> Result<Vec<u8>> {
+        let size = self.payload_buffer_size(payload, protocol_id, volume)?;
+        let mut buffer = vec![0u8; size];
+        let written = self.encode_payload_into(payload, protocol_id, volume, &mut buffer)?;
+
+        // Trim the buffer to the actual size if needed
+        if written < buffer.len() {
+            buffer.truncate(written);
+        }
+
+        Ok(buffer)
+    }
+
+    /// Encode text to raw audio data in a specific sample format
+    ///
+    /// The waveform is generated in the instance's output sample format and then
+    /// converted to `format`, so a single instance can produce audio for consumers
+    /// that expect different formats.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to encode
+    /// * `protocol_id` - The protocol to use for encoding
+    /// * `volume` - The volume of the encoded audio (0-100)
+    /// * `format` - The sample format of the returned audio data
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a `Vec<u8>` with the encoded audio data in `format`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols, sample_formats};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let waveform = ggwave
+    ///     .encode_as_format("Hello, World!", protocols::AUDIBLE_NORMAL, 50, sample_formats::I16)
+    ///     .expect("Failed to encode text");
+    /// ```
+    pub fn encode_as_format(
+        &self,
+        text: &str,
+        protocol_id: ProtocolId,
+        volume: i32,
+        format: SampleFormat,
+    ) -> Result<Vec<u8>> {
+        let waveform = self.encode(text, protocol_id, volume)?;
+
+        if format == self.params.sampleFormatOut {
+            return Ok(waveform);
+        }
+
+        convert::convert_samples(&waveform, self.params.sampleFormatOut, format)
+    }
+
+    #[cfg(feature = "std")]
+    /// Encode text with silence before and after the transmission
+    ///
+    /// Audio devices can take a moment to start, which cuts off the beginning
+    /// of a waveform pushed straight into a playback buffer. The silence is in
+    /// the instance's output sample format and at its output sample rate, so
+    /// it can be played like any other encoded waveform.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to encode
+    /// * `protocol_id` - The protocol to use for encoding
+    /// * `volume` - The volume of the encoded audio (0-100)
+    /// * `lead_ms` - Milliseconds of silence before the transmission
+    /// * `trail_ms` - Milliseconds of silence after the transmission
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the padded waveform
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let waveform = ggwave
+    ///     .encode_with_padding("Hello, World!", protocols::AUDIBLE_FAST, 50, 200, 100)
+    ///     .expect("Failed to encode text");
+    /// ```
+    pub fn encode_with_padding(
+        &self,
+        text: &str,
+        protocol_id: ProtocolId,
+        volume: i32,
+        lead_ms: u32,
+        trail_ms: u32,
+    ) -> Result<Vec<u8>> {
+        let format = self.params.sampleFormatOut;
+        let silence = |ms: u32| {
+            let samples = (self.params.sampleRateOut as f64 * ms as f64 / 1000.0).round() as usize;
+            // Zero is not the midpoint of the unsigned formats
+            convert::f32_to_bytes(&vec![0.0; samples], format)
+        };
+
+        let waveform = self.encode(text, protocol_id, volume)?;
+        let lead = silence(lead_ms)?;
+        let trail = silence(trail_ms)?;
+
+        let mut padded = Vec::with_capacity(lead.len() + waveform.len() + trail.len());
+        padded.extend_from_slice(&lead);
+        padded.extend_from_slice(&waveform);
+        padded.extend_from_slice(&trail);
+        Ok(padded)
+    }
+
+    /// Encode text and mix it into an existing track
+    ///
+    /// The encoded samples are added to the track starting at `at_sample` and
+    /// the sums are clamped to [-1.0, 1.0]; the rest of the track is left
+    /// untouched. The track must be at the instance's output sample rate.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to encode
+    /// * `protocol_id` - The protocol to use for encoding
+    /// * `volume` - The volume of the encoded audio (0-100)
+    /// * `track` - Normalized samples to mix the transmission into
+    /// * `at_sample` - Position in the track where the transmission starts
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the number of samples mixed in, or
+    /// `Error::BufferTooSmall` if the transmission does not fit in the track
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// // Ten seconds of background music
+    /// let mut track = vec![0.0f32; 10 * 48000];
+    ///
+    /// ggwave.mix_into("Hello, World!", protocols::AUDIBLE_FAST, 20, &mut track, 48000)
+    ///     .expect("Failed to mix");
+    /// ```
+    pub fn mix_into(
+        &self,
+        text: &str,
+        protocol_id: ProtocolId,
+        volume: i32,
+        track: &mut [f32],
+        at_sample: usize,
+    ) -> Result<usize> {
+        let waveform = self.encode(text, protocol_id, volume)?;
+        let samples = convert::bytes_to_f32(&waveform, self.params.sampleFormatOut)?;
+
+        let required = at_sample.saturating_add(samples.len());
+        if required > track.len() {
+            return Err(Error::BufferTooSmall {
+                required,
+                provided: track.len(),
+            });
+        }
+
+        for (out, sample) in track[at_sample..required].iter_mut().zip(&samples) {
+            *out = (*out + sample).clamp(-1.0, 1.0);
+        }
+
+        Ok(samples.len())
+    }
+
+    /// Encode text and scale the waveform to a given peak amplitude
+    ///
+    /// The peak amplitude of a waveform depends on the protocol as well as the
+    /// volume, which makes it hard to mix with other audio. This encodes at
+    /// full volume and scales the samples so their peak equals `target_peak`.
+    /// A silent waveform is returned unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to encode
+    /// * `protocol_id` - The protocol to use for encoding
+    /// * `target_peak` - The peak amplitude of the result (0.0-1.0)
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the normalized samples, ready for a playback buffer
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let samples = ggwave
+    ///     .encode_normalized("Hello, World!", protocols::AUDIBLE_FAST, 0.5)
+    ///     .expect("Failed to encode text");
+    /// ```
+    pub fn encode_normalized(
+        &self,
+        text: &str,
+        protocol_id: ProtocolId,
+        target_peak: f32,
+    ) -> Result<Vec<f32>> {
+        if !(0.0..=1.0).contains(&target_peak) {
+            return Err(Error::InvalidParameter("Target peak must be 0.0-1.0"));
+        }
+
+        let waveform = self.encode(text, protocol_id, 100)?;
+        let mut samples = convert::bytes_to_f32(&waveform, self.params.sampleFormatOut)?;
+
+        let peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        if peak > 0.0 {
+            let gain = target_peak / peak;
+            samples.iter_mut().for_each(|s| *s *= gain);
+        }
+
+        Ok(samples)
+    }
+
+    /// Encode binary data with a CRC-32 checksum
+    ///
+    /// The checksum is sent in the 4 bytes before the data, so at most
+    /// `max_payload_len() - 4` bytes of data fit in a transmission. Use
+    /// [`GGWave::decode_checked`] to receive it. Instances with a fixed payload
+    /// length always send a full payload, so the data is zero-padded to that
+    /// length before the checksum is computed.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The data to encode
+    /// * `protocol_id` - The protocol to use for encoding
+    /// * `volume` - The volume of the encoded audio (0-100)
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the encoded audio data
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let waveform = ggwave.encode_checked(b"\x01\x02\x03", protocols::AUDIBLE_FAST, 50)
+    ///     .expect("Failed to encode data");
+    ///
+    /// let mut buffer = vec![0u8; 1024];
+    /// let data = ggwave.decode_checked(&waveform, &mut buffer)
+    ///     .expect("Failed to decode waveform");
+    /// assert_eq!(data, [1, 2, 3]);
+    /// ```
+    pub fn encode_checked(
+        &self,
+        data: &[u8],
+        protocol_id: ProtocolId,
+        volume: i32,
+    ) -> Result<Vec<u8>> {
+        let max = self.max_payload_len().saturating_sub(CHECKSUM_LEN);
+        if data.len() > max {
+            return Err(Error::TextTooLong {
+                length: data.len(),
+                max,
+            });
+        }
+
+        let mut payload = vec![0u8; CHECKSUM_LEN];
+        payload.extend_from_slice(data);
+        if self.is_fixed_length() {
+            payload.resize(self.max_payload_len(), 0);
+        }
+
+        let mut crc = checksum::Crc32::new();
+        crc.update(&payload[CHECKSUM_LEN..]);
+        payload[..CHECKSUM_LEN].copy_from_slice(&crc.finish().to_le_bytes());
+
+        self.encode_payload(&payload, protocol_id, volume)
+    }
+
+    /// Decode data sent with [`GGWave::encode_checked`] and verify its checksum
+    ///
+    /// # Arguments
+    ///
+    /// * `waveform` - The raw audio data to decode
+    /// * `buffer` - Buffer to store the decoded payload, including the checksum
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the data, or `Error::ChecksumMismatch` if the
+    /// payload was corrupted on the way
+    pub fn decode_checked(&self, waveform: &[u8], buffer: &mut [u8]) -> Result<Vec<u8>> {
+        let payload = self.decode_binary(waveform, buffer)?;
+        checked_data(payload).map(<[u8]>::to_vec)
+    }
+
+    /// Split data too large for one transmission into several waveforms
+    ///
+    /// Each waveform carries one chunk of at most `max_payload_len() - 4`
+    /// bytes of data behind a header with its index and the number of chunks.
+    /// Decode them with [`GGWave::decode_binary`] and feed the payloads to a
+    /// [`ChunkReassembler`], in any order.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The data to send
+    /// * `protocol_id` - The protocol to use for encoding
+    /// * `volume` - The volume of the encoded audio (0-100)
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing one waveform per chunk, in order, or
+    /// `Error::InvalidParameter` for instances with a fixed payload length,
+    /// whose padding would be mistaken for data
+    pub fn send_large(
+        &self,
+        data: &[u8],
+        protocol_id: ProtocolId,
+        volume: i32,
+    ) -> Result<Vec<Vec<u8>>> {
+        if self.is_fixed_length() {
+            return Err(Error::InvalidParameter(
+                "Chunked transfers need variable-length payloads",
+            ));
+        }
+        if data.is_empty() {
+            return Err(Error::InvalidParameter("Data must not be empty"));
+        }
+
+        let chunk_len = self.max_payload_len() - chunking::HEADER_LEN;
+        let total = u16::try_from(data.len().div_ceil(chunk_len))
+            .map_err(|_| Error::InvalidParameter("Data needs more than 65535 chunks"))?;
+
+        data.chunks(chunk_len)
+            .enumerate()
+            .map(|(index, piece)| {
+                let chunk = chunking::chunk(index as u16, total, piece);
+                self.encode_payload(&chunk, protocol_id, volume)
+            })
+            .collect()
+    }
+
+    /// Encode several payloads back to back into a single waveform
+    ///
+    /// Each part is encoded as its own transmission, so a receiver decodes
+    /// them one by one. The returned manifest gives the byte range of every
+    /// part in the combined waveform, which lets a sender report progress
+    /// while playing it, and a checksum over all payloads, which lets a
+    /// receiver that knows the manifest verify it got the whole sequence.
+    ///
+    /// # Arguments
+    ///
+    /// * `parts` - The payloads to encode, in transmission order
+    /// * `protocol_id` - The protocol to use for encoding
+    /// * `volume` - The volume of the encoded audio (0-100)
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the combined waveform and its manifest
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let parts: [&[u8]; 2] = [b"part one", b"part two"];
+    /// let (waveform, manifest) = ggwave
+    ///     .encode_sequence(&parts, protocols::AUDIBLE_FAST, 50)
+    ///     .expect("Failed to encode sequence");
+    ///
+    /// assert_eq!(manifest.len(), 2);
+    /// assert_eq!(manifest.waveform_length(), waveform.len());
+    /// assert!(manifest.verify(&parts));
+    /// ```
+    pub fn encode_sequence(
+        &self,
+        parts: &[&[u8]],
+        protocol_id: ProtocolId,
+        volume: i32,
+    ) -> Result<(Vec<u8>, SequenceManifest)> {
+        let mut waveform = Vec::new();
+        let mut manifest_parts = Vec::with_capacity(parts.len());
+        let mut crc = checksum::Crc32::new();
+
+        for part in parts {
+            let encoded = self.encode_payload(part, protocol_id, volume)?;
+
+            manifest_parts.push(SequencePart {
+                offset: waveform.len(),
+                length: encoded.len(),
+                payload_length: part.len(),
+            });
+            crc.update(part);
+            waveform.extend_from_slice(&encoded);
+        }
+
+        let manifest = SequenceManifest {
+            parts: manifest_parts,
+            checksum: crc.finish(),
+        };
+        Ok((waveform, manifest))
+    }
+
+    /// Decode raw audio data to text using a provided buffer
+    ///
+    /// The payload must be valid UTF-8. Use [`GGWave::decode_binary`] for data
+    /// that is not text, or [`GGWave::decode_lossy`] to get text with invalid
+    /// sequences replaced.
+    ///
+    /// `waveform` is read in the input sample format of this instance, which
+    /// can differ from the output format produced by [`GGWave::encode`]. Use
+    /// [`GGWave::encode_as_format`] to produce audio this instance can decode.
+    ///
+    /// # Arguments
+    ///
+    /// * `waveform` - The raw audio data to decode
+    /// * `buffer` - Buffer to store the decoded payload
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the decoded text as a string slice,
+    /// `Error::NoMessage` if the audio does not contain a complete message,
+    /// `Error::Utf8Error` if the payload is not valid UTF-8,
+    /// `Error::BufferTooSmall` with the payload length if it does not fit in
+    /// `buffer`, or `Error::InvalidParameter` if `waveform` does not hold a
+    /// whole number of input samples. A buffer of
+    /// [`constants::MAX_DATA_SIZE`] bytes fits any payload, and
+    /// [`GGWave::decode_to_string`] allocates one itself.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let waveform = ggwave.encode("Hello, World!", protocols::AUDIBLE_NORMAL, 50)
+    ///     .expect("Failed to encode text");
+    ///
+    /// let mut buffer = vec![0u8; 1024];
+    /// let decoded = ggwave.decode(&waveform, &mut buffer)
+    ///     .expect("Failed to decode waveform");
+    ///
+    /// assert_eq!(decoded, "Hello, World!");
+    /// ```
+    pub fn decode<'a>(&self, waveform: &[u8], buffer: &'a mut [u8]) -> Result<&'a str> {
+        let payload = self.decode_payload(waveform, buffer)?;
+        core::str::from_utf8(payload).map_err(Error::Utf8Error)
+    }
+
+    /// Decode a waveform and copy the payload into `buffer`
+    ///
+    /// The C library discards a payload that does not fit the buffer it is
+    /// given without reporting its length, so the payload is decoded into a
+    /// buffer that fits any payload first. This way a short `buffer` gives
+    /// `Error::BufferTooSmall` with the length that was needed.
+    fn decode_payload<'a>(&self, waveform: &[u8], buffer: &'a mut [u8]) -> Result<&'a [u8]> {
+        self.check_input_alignment(waveform)?;
+        let mut scratch = [0u8; constants::MAX_DATA_SIZE];
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+        let result = {
+            let _global = global_read();
+            unsafe {
+                ggwave_ndecode(
+                    self.instance,
+                    waveform.as_ptr() as *const c_void,
+                    waveform.len() as i32,
+                    scratch.as_mut_ptr() as *mut c_void,
+                    scratch.len() as i32,
+                )
+            }
+        };
+        #[cfg(feature = "metrics")]
+        self.record_decode(waveform, result, started);
+
+        match result {
+            0 => Err(Error::NoMessage),
+            _ if result < 0 => Err(Error::DecodeFailed(result)),
+            _ => self.copy_payload(&mut scratch[..result as usize], buffer),
+        }
+    }
+
+    #[cfg(feature = "metrics")]
+    /// Count a call into the C decoder that started at `started`
+    fn record_decode(&self, audio: &[u8], result: i32, started: std::time::Instant) {
+        let samples =
+            convert::sample_size(self.params.sampleFormatInp).map_or(0, |size| audio.len() / size);
+        let mut metrics = self.metrics.get();
+        metrics.record(samples, result, started.elapsed());
+        self.metrics.set(metrics);
+    }
+
+    #[cfg(feature = "metrics")]
+    /// Get the decode counters of this instance
+    ///
+    /// Every decoding method ends in calls into the C decoder, and each call
+    /// is counted with its duration and the number of samples it was given.
+    /// The counters start at zero when the instance is created, including by
+    /// [`GGWave::try_clone`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::GGWave;
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// assert_eq!(ggwave.metrics().decode_calls, 0);
+    /// ```
+    pub fn metrics(&self) -> DecodeMetrics {
+        self.metrics.get()
+    }
+
+    #[cfg(feature = "metrics")]
+    /// Set the decode counters of this instance back to zero
+    pub fn reset_metrics(&self) {
+        self.metrics.set(DecodeMetrics::default());
+    }
+
+    /// Undo the transport encoding of a received payload and copy it into `buffer`
+    fn copy_payload<'a>(&self, received: &mut [u8], buffer: &'a mut [u8]) -> Result<&'a [u8]> {
+        let length = self.transport.decode_in_place(received)?;
+        if length > buffer.len() {
+            return Err(Error::BufferTooSmall {
+                required: length,
+                provided: buffer.len(),
+            });
+        }
+
+        buffer[..length].copy_from_slice(&received[..length]);
+        Ok(&buffer[..length])
+    }
+
+    /// Decode raw audio data to text, replacing invalid UTF-8
+    ///
+    /// Invalid sequences are replaced with `U+FFFD` like
+    /// [`String::from_utf8_lossy`] does, so this never fails because of the
+    /// payload's content. Use [`GGWave::decode_binary`] to get the exact bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `waveform` - The raw audio data to decode
+    /// * `buffer` - Buffer to store the decoded payload
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the decoded text, borrowed from `buffer` when the
+    /// payload is valid UTF-8, or `Error::NoMessage` if the audio does not
+    /// contain a complete message
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let waveform = ggwave.encode("Hello, World!", protocols::AUDIBLE_FAST, 50)
+    ///     .expect("Failed to encode text");
+    ///
+    /// let mut buffer = vec![0u8; 1024];
+    /// let decoded = ggwave.decode_lossy(&waveform, &mut buffer)
+    ///     .expect("Failed to decode waveform");
+    /// assert_eq!(decoded, "Hello, World!");
+    /// ```
+    pub fn decode_lossy<'a>(&self, waveform: &[u8], buffer: &'a mut [u8]) -> Result<Cow<'a, str>> {
+        let payload = self.decode_binary(waveform, buffer)?;
+        Ok(String::from_utf8_lossy(payload))
+    }
+
+    /// Decode `f32` samples without converting them to bytes first
+    ///
+    /// The samples are passed to the decoder in place, so the instance's input
+    /// sample format must be [`sample_formats::F32`].
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - Normalized samples, e.g. from a cpal capture callback
+    /// * `buffer` - Buffer to store the decoded payload
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the decoded text as a string slice, or
+    /// `Error::InvalidSampleFormat` if the input format is not `F32`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::GGWave;
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let captured = vec![0.0f32; 1024];
+    ///
+    /// let mut buffer = vec![0u8; 1024];
+    /// let decoded = ggwave.decode_f32(&captured, &mut buffer);
+    /// ```
+    pub fn decode_f32<'a>(&self, samples: &[f32], buffer: &'a mut [u8]) -> Result<&'a str> {
+        if self.params.sampleFormatInp != sample_formats::F32 {
+            return Err(Error::InvalidSampleFormat);
+        }
+
+        // The decoder reads samples in native byte order
+        let waveform = unsafe {
+            core::slice::from_raw_parts(
+                samples.as_ptr() as *const u8,
+                core::mem::size_of_val(samples),
+            )
+        };
+        self.decode(waveform, buffer)
+    }
+
+    #[cfg(feature = "std")]
+    /// Decode `f32` samples and report details about the received audio
+    ///
+    /// Like [`GGWave::decode_detailed`] for samples already in the instance's
+    /// input format, which must be [`sample_formats::F32`]. Besides the
+    /// protocol and confidence, [`DecodeResult::clipped`] tells live capture
+    /// users when their input gain is too high.
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - Normalized samples, e.g. from a cpal capture callback
+    /// * `buffer` - Buffer to store the decoded payload
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the decoded message and its details, or `None` if
+    /// no message was found, or `Error::InvalidSampleFormat` if the input
+    /// format is not `F32`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::GGWave;
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let captured = vec![0.0f32; 1024];
+    ///
+    /// let mut buffer = vec![0u8; 1024];
+    /// if let Ok(Some(result)) = ggwave.decode_f32_detailed(&captured, &mut buffer) {
+    ///     if result.clipped {
+    ///         println!("Input is clipping, lower the microphone gain");
+    ///     }
+    /// }
+    /// ```
+    pub fn decode_f32_detailed(
+        &self,
+        samples: &[f32],
+        buffer: &mut [u8],
+    ) -> Result<Option<DecodeResult>> {
+        if self.params.sampleFormatInp != sample_formats::F32 {
+            return Err(Error::InvalidSampleFormat);
+        }
+
+        let waveform = unsafe {
+            core::slice::from_raw_parts(
+                samples.as_ptr() as *const u8,
+                core::mem::size_of_val(samples),
+            )
+        };
+        self.decode_detailed(waveform, buffer)
+    }
+
+    /// Decode `i16` samples without converting them to bytes first
+    ///
+    /// The samples are passed to the decoder in place, so the instance's input
+    /// sample format must be [`sample_formats::I16`].
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - Signed 16-bit samples
+    /// * `buffer` - Buffer to store the decoded payload
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the decoded text as a string slice, or
+    /// `Error::InvalidSampleFormat` if the input format is not `I16`
+    pub fn decode_i16<'a>(&self, samples: &[i16], buffer: &'a mut [u8]) -> Result<&'a str> {
+        if self.params.sampleFormatInp != sample_formats::I16 {
+            return Err(Error::InvalidSampleFormat);
+        }
+
+        let waveform = unsafe {
+            core::slice::from_raw_parts(
+                samples.as_ptr() as *const u8,
+                core::mem::size_of_val(samples),
+            )
+        };
+        self.decode(waveform, buffer)
+    }
+
+    /// Decode raw audio data to text with heap allocation
+    ///
+    /// # Arguments
+    ///
+    /// * `waveform` - The raw audio data to decode
+    /// * `max_payload_size` - The maximum size of the decoded payload
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the decoded text
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let waveform = ggwave.encode("Hello, World!", protocols::AUDIBLE_NORMAL, 50)
+    ///     .expect("Failed to encode text");
+    ///
+    /// let decoded = ggwave.decode_to_string(&waveform, 1024)
+    ///     .expect("Failed to decode waveform");
+    ///
+    /// assert_eq!(decoded, "Hello, World!");
+    /// ```
+    pub fn decode_to_string(&self, waveform: &[u8], max_payload_size: usize) -> Result<String> {
+        let mut buffer = vec![0u8; max_payload_size];
+        let decoded = self.decode(waveform, &mut buffer)?;
+        Ok(decoded.to_string())
+    }
+
+    /// Decode raw audio data to text using an owned buffer that is handed back
+    ///
+    /// Like [`GGWave::decode`], but the result does not borrow the buffer, so
+    /// it can be sent through a channel or held across an `.await` while the
+    /// buffer is reused for the next call. This is the synchronous
+    /// counterpart of `AsyncGGWave::decode_into`.
+    ///
+    /// # Arguments
+    ///
+    /// * `waveform` - The raw audio data to decode
+    /// * `buffer` - The payload buffer; its length is the maximum payload size
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the decoded text, or `None` if the audio does not
+    /// contain a complete message, and the buffer
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let mut buffer = vec![0u8; 1024];
+    ///
+    /// for text in ["Hello", "World"] {
+    ///     let waveform = ggwave.encode(text, protocols::AUDIBLE_FAST, 50)
+    ///         .expect("Failed to encode text");
+    ///
+    ///     let (decoded, returned) = ggwave.decode_owned(&waveform, buffer)
+    ///         .expect("Failed to decode waveform");
+    ///     assert_eq!(decoded.as_deref(), Some(text));
+    ///     buffer = returned;
+    /// }
+    /// ```
+    pub fn decode_owned(
+        &self,
+        waveform: &[u8],
+        mut buffer: Vec<u8>,
+    ) -> Result<(Option<String>, Vec<u8>)> {
+        let text = match self.decode(waveform, &mut buffer) {
+            Ok(text) => Some(text.to_string()),
+            Err(Error::NoMessage) => None,
+            Err(e) => return Err(e),
+        };
+        Ok((text, buffer))
+    }
+
+    #[cfg(feature = "std")]
+    /// Decode raw audio data that contains noise or silence around the message
+    ///
+    /// Captures from a microphone rarely start exactly at the sound marker. This
+    /// locates the transmission by its energy above the noise floor, trims the
+    /// surrounding garbage (keeping one frame of margin on each side) and appends
+    /// trailing silence so the decoder can finish after the end marker.
+    ///
+    /// If no transmission stands out from the background, the waveform is
+    /// decoded as-is.
+    ///
+    /// # Arguments
+    ///
+    /// * `waveform` - The raw audio data to decode, in the input sample format
+    /// * `buffer` - Buffer to store the decoded payload
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the decoded text as a string slice
+    pub fn decode_robust<'a>(&self, waveform: &[u8], buffer: &'a mut [u8]) -> Result<&'a str> {
+        let format = self.params.sampleFormatInp;
+        let frame_len = self.params.samplesPerFrame.max(1) as usize;
+        let samples = convert::bytes_to_f32(waveform, format)?;
+
+        let Some(message) = signal::trim_silence(&samples, frame_len) else {
+            return self.decode(waveform, buffer);
+        };
+
+        let start = message.start.saturating_sub(frame_len);
+        let end = (message.end + frame_len).min(samples.len());
+        let tail = constants::DEFAULT_MARKER_FRAMES * frame_len;
+
+        let mut trimmed = Vec::with_capacity(end - start + tail);
+        trimmed.extend_from_slice(&samples[start..end]);
+        trimmed.resize(trimmed.len() + tail, 0.0);
+
+        let waveform = convert::f32_to_bytes(&trimmed, format)?;
+        self.decode(&waveform, buffer)
+    }
+
+    /// Decode samples captured at a different rate than the instance's input
+    ///
+    /// The samples are converted from `input_rate` to the configured
+    /// `sampleRateInp` with a [`Resampler`], then to the input sample format,
+    /// and decoded. Real-time callers that decode chunk by chunk should keep
+    /// their own [`Resampler`] instead, so no samples are lost between chunks.
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - Normalized samples at `input_rate`
+    /// * `input_rate` - The sample rate the samples were captured at in Hz
+    /// * `buffer` - Buffer to store the decoded payload
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the decoded text as a string slice
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::GGWave;
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// // Samples from a microphone that only supports 44.1 kHz
+    /// let captured = vec![0.0f32; 44100];
+    ///
+    /// let mut buffer = vec![0u8; 1024];
+    /// let decoded = ggwave.decode_resampled(&captured, 44100.0, &mut buffer);
+    /// ```
+    pub fn decode_resampled<'a>(
+        &self,
+        samples: &[f32],
+        input_rate: f32,
+        buffer: &'a mut [u8],
+    ) -> Result<&'a str> {
+        let mut resampler = Resampler::new(input_rate, self.params.sampleRateInp)?;
+        let mut resampled = Vec::new();
+        resampler.process(samples, &mut resampled);
+
+        let waveform = convert::f32_to_bytes(&resampled, self.params.sampleFormatInp)?;
+        self.decode(&waveform, buffer)
+    }
+
+    /// Decode interleaved multi-channel samples
+    ///
+    /// Audio APIs often deliver interleaved stereo even when mono was
+    /// requested. The channels are averaged with [`downmix_to_mono`] and the
+    /// result is decoded in the instance's input sample format.
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - Normalized interleaved samples
+    /// * `channels` - The number of interleaved channels
+    /// * `buffer` - Buffer to store the decoded payload
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the decoded text as a string slice
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::GGWave;
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// // Stereo samples from the capture callback
+    /// let captured = vec![0.0f32; 2 * 48000];
+    ///
+    /// let mut buffer = vec![0u8; 1024];
+    /// let decoded = ggwave.decode_interleaved(&captured, 2, &mut buffer);
+    /// ```
+    pub fn decode_interleaved<'a>(
+        &self,
+        samples: &[f32],
+        channels: u16,
+        buffer: &'a mut [u8],
+    ) -> Result<&'a str> {
+        if channels == 0 {
+            return Err(Error::InvalidParameter("Channel count must be at least 1"));
+        }
+
+        let mono = downmix_to_mono(samples, channels);
+        let waveform = convert::f32_to_bytes(&mono, self.params.sampleFormatInp)?;
+        self.decode(&waveform, buffer)
+    }
+
+    #[cfg(feature = "std")]
+    /// Decode raw audio data and report which protocol carried the message
+    ///
+    /// The C API does not report the protocol of a decoded message, so it is
+    /// worked out from the number of frames the reception took, which depends
+    /// on the speed of the protocol, and among protocols of the same speed from
+    /// which band holds the tones. The spectrum analysis makes this slower than
+    /// [`GGWave::decode`], so it is meant for diagnostics rather than hot paths.
+    ///
+    /// # Arguments
+    ///
+    /// * `waveform` - The raw audio data to decode
+    /// * `buffer` - Buffer to store the decoded payload
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the decoded message and its details, or `None` if
+    /// no message was found
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let waveform = ggwave.encode("Hello, World!", protocols::AUDIBLE_FAST, 50)
+    ///     .expect("Failed to encode text");
+    ///
+    /// let mut buffer = vec![0u8; 1024];
+    /// let result = ggwave.decode_detailed(&waveform, &mut buffer)
+    ///     .expect("Failed to decode waveform")
+    ///     .expect("No message found");
+    ///
+    /// assert_eq!(result.text, "Hello, World!");
+    /// assert_eq!(result.protocol, protocols::AUDIBLE_FAST);
+    /// ```
+    pub fn decode_detailed(
+        &self,
+        waveform: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<Option<DecodeResult>> {
+        let text = match self.decode(waveform, buffer) {
+            Ok(text) => text.to_string(),
+            Err(Error::NoMessage) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let samples = convert::bytes_to_f32(waveform, self.params.sampleFormatInp)?;
+        let protocol = self.identify_protocol(&samples, text.len())?;
+        let frame_len = self.params.samplesPerFrame.max(1) as usize;
+
+        Ok(Some(DecodeResult {
+            length: text.len(),
+            received_volume: self.received_volume(&samples),
+            confidence: self.confidence(&samples, protocol),
+            clipped: signal::is_clipped(&samples),
+            offset: signal::trim_silence(&samples, frame_len).map_or(0, |m| m.start),
+            text,
+            protocol,
+        }))
+    }
+
+    #[cfg(feature = "std")]
+    /// Decode the messages in a stream of samples lazily
+    ///
+    /// The samples are cut into frames and decoded as the returned iterator is
+    /// advanced, which suits a decoded audio file or the receiving end of a
+    /// channel. This is the pull-style counterpart of [`StreamDecoder`]. Call
+    /// [`DecodeIter::require_checksum`] on the iterator to only receive
+    /// messages sent with [`GGWave::encode_checked`].
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - Normalized samples at the instance's input sample rate
+    ///
+    /// # Returns
+    ///
+    /// An iterator over the decoded messages
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::GGWave;
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let samples = vec![0.0f32; 48000];
+    ///
+    /// for message in ggwave.decode_iter(samples) {
+    ///     let message = message.expect("Failed to decode");
+    ///     println!("Received: {}", message.text);
+    /// }
+    /// ```
+    pub fn decode_iter<I>(&self, samples: I) -> DecodeIter<'_, I::IntoIter>
+    where
+        I: IntoIterator<Item = f32>,
+    {
+        DecodeIter::new(self, samples.into_iter())
+    }
+
+    #[cfg(feature = "std")]
+    /// Decode every message in a long recording
+    ///
+    /// The waveform is fed to the decoder one frame at a time and each message
+    /// is collected as soon as it completes, with the position where its
+    /// transmission starts. Consecutive detections of the same text with no new
+    /// transmission between them are reported once. The protocol of each
+    /// message is found as by [`GGWave::decode_detailed`].
+    ///
+    /// # Arguments
+    ///
+    /// * `waveform` - The raw audio data to decode, in the input sample format
+    /// * `max_payload` - The maximum size of a decoded payload
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the decoded messages, in order
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let mut recording = Vec::new();
+    /// for text in ["one", "two"] {
+    ///     recording.extend(ggwave.encode(text, protocols::AUDIBLE_FAST, 50).unwrap());
+    /// }
+    ///
+    /// let messages = ggwave.decode_all(&recording, 1024).expect("Failed to decode");
+    /// for message in messages {
+    ///     println!("{} at sample {}", message.text, message.offset);
+    /// }
+    /// ```
+    pub fn decode_all(&self, waveform: &[u8], max_payload: usize) -> Result<Vec<DecodeResult>> {
+        self.check_input_alignment(waveform)?;
+        let format = self.params.sampleFormatInp;
+        let frame_len = self.params.samplesPerFrame.max(1) as usize;
+        let frame_bytes = frame_len * convert::sample_size(format)?;
+        // Analysed alongside the raw frames, which are decoded untouched
+        let samples = convert::bytes_to_f32(waveform, format)?;
+
+        let mut buffer = [0u8; constants::MAX_DATA_SIZE];
+        let mut results: Vec<DecodeResult> = Vec::new();
+        // Start of the audio following the previous message
+        let mut segment_start = 0;
+
+        for (index, frame) in waveform.chunks(frame_bytes).enumerate() {
+            let text = match self.process_audio_chunk(frame, &mut buffer) {
+                Ok(Some(text)) if text.len() <= max_payload => text.to_string(),
+                Ok(_) | Err(Error::DecodeFailed(_)) | Err(Error::Utf8Error(_)) => continue,
+                Err(e) => return Err(e),
+            };
+
+            let segment_end = ((index + 1) * frame_len).min(samples.len());
+            let segment = &samples[segment_start..segment_end];
+            let loud = signal::trim_silence(segment, frame_len).is_some();
+            let repeated = results.last().is_some_and(|last| last.text == text);
+
+            if loud || !repeated {
+                let reception = self.reception(segment);
+                let message = &segment[reception.clone()];
+                let protocol = self.identify_protocol(message, text.len())?;
+                results.push(DecodeResult {
+                    protocol,
+                    received_volume: self.received_volume(message),
+                    confidence: self.confidence(message, protocol),
+                    clipped: signal::is_clipped(message),
+                    offset: segment_start + reception.start,
+                    length: text.len(),
+                    text,
+                });
+            }
+            segment_start = segment_end;
+        }
+
+        Ok(results)
+    }
+
+    #[cfg(feature = "std")]
+    /// Find which rx protocol carried the message this instance just decoded
+    ///
+    /// ggwave records the length of each reception, which for a payload of a
+    /// given length depends only on the frames and bytes per transmission of
+    /// the protocol. The candidates whose expected length is closest to the
+    /// recorded one are told apart by how cleanly their band carries tones in
+    /// `samples`, which separates protocols that only differ in frequency.
+    /// Custom protocols, whose layout is not recorded, are only reported when
+    /// they are the single rx protocol.
+    fn identify_protocol(&self, samples: &[f32], length: usize) -> Result<ProtocolId> {
+        let mut candidates = self.rx_protocols.iter();
+        if let (Some(only), None) = (candidates.next(), candidates.next()) {
+            return Ok(only);
+        }
+
+        let fixed = self.params.payloadLength > 0;
+        let (marker_frames, data_offset) = if fixed {
+            (0, 0)
+        } else {
+            (
+                constants::DEFAULT_MARKER_FRAMES,
+                constants::DEFAULT_ENCODED_DATA_OFFSET,
+            )
+        };
+        let encoded_length = data_offset + length + protocols::ecc_bytes(length);
+        // The recorded length covers the data and one of the two markers
+        let recorded = self.rx_duration_frames().max(0) as usize;
+        let distance = |protocol_id: ProtocolId| {
+            let (frames_per_tx, bytes_per_tx, _) = protocols::layout(protocol_id)?;
+            let expected = marker_frames + encoded_length.div_ceil(bytes_per_tx) * frames_per_tx;
+            Some(expected.abs_diff(recorded))
+        };
+
+        let closest = self
+            .rx_protocols
+            .iter()
+            .filter_map(distance)
+            .min()
+            .ok_or(Error::DecodeFailed(-1))?;
+
+        self.rx_protocols
+            .iter()
+            .filter(|&id| distance(id).is_some_and(|d| d <= closest + PROTOCOL_FRAME_TOLERANCE))
+            .map(|id| (id, self.confidence(samples, id)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(id, _)| id)
+            .ok_or(Error::DecodeFailed(-1))
+    }
+
+    #[cfg(feature = "std")]
+    /// Range of a capture that carries the message this instance just decoded
+    ///
+    /// `samples` must end at the frame the message was decoded on. The reception
+    /// that ggwave records starts a few frames into the start marker, which
+    /// places the start of the transmission to within a couple of frames before
+    /// the frame energy pins it down. Captures without a recorded reception,
+    /// such as fixed-length payloads, fall back to their loud part.
+    fn reception(&self, samples: &[f32]) -> Range<usize> {
+        let frame_len = self.params.samplesPerFrame.max(1) as usize;
+        let recorded = self.rx_duration_frames().max(0) as usize;
+        if recorded == 0 {
+            return signal::trim_silence(samples, frame_len).unwrap_or(0..samples.len());
+        }
+
+        let estimate = samples
+            .len()
+            .saturating_sub((recorded + RX_START_LAG_FRAMES) * frame_len);
+        signal::onset(samples, frame_len, estimate)..samples.len()
+    }
+
+    #[cfg(feature = "std")]
+    /// RMS level of the transmission in a capture, ignoring the silence around it
+    fn received_volume(&self, samples: &[f32]) -> Option<f32> {
+        let frame_len = self.params.samplesPerFrame.max(1) as usize;
+        let message = signal::trim_silence(samples, frame_len).unwrap_or(0..samples.len());
+        (!message.is_empty()).then(|| signal::rms(&samples[message]))
+    }
+
+    #[cfg(feature = "std")]
+    /// Estimate how cleanly a protocol's tones stand out in a capture
+    ///
+    /// The analysis frames start where the transmission rises above the noise,
+    /// which lines them up with the tones up to the frame grid of the capture.
+    fn confidence(&self, samples: &[f32], protocol: ProtocolId) -> f32 {
+        let Some(band) = self.protocol_band(protocol) else {
+            return 0.0;
+        };
+
+        let frame_len = self.params.samplesPerFrame.max(1) as usize;
+        let start = signal::trim_silence(samples, frame_len).map_or(0, |m| m.start);
+        let bin_width = self.params.sampleRate / frame_len as f32;
+        signal::tone_confidence(
+            &samples[start..],
+            band,
+            bin_width,
+            self.params.sampleRateInp,
+        )
+    }
+
+    #[cfg(feature = "std")]
+    /// Decode a WAV file to text
+    ///
+    /// The WAV file must be mono and recorded at the instance's input sample rate.
+    /// Its samples are converted to the input sample format the instance expects
+    /// before decoding.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the WAV file to decode
+    /// * `max_payload_size` - The maximum size of the decoded payload
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the decoded text
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// ggwave.encode_to_wav_file("Hello, World!", protocols::AUDIBLE_NORMAL, 50, "hello.wav")
+    ///     .expect("Failed to encode and save WAV file");
+    ///
+    /// let decoded = ggwave.decode_wav_file("hello.wav", 1024)
+    ///     .expect("Failed to decode WAV file");
+    ///
+    /// assert_eq!(decoded, "Hello, World!");
+    /// ```
+    pub fn decode_wav_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+        max_payload_size: usize,
+    ) -> Result<String> {
+        let reader = WavReader::open(path)?;
+        self.decode_wav_reader(reader, max_payload_size)
+    }
+
+    #[cfg(feature = "std")]
+    /// Decode every message in a WAV file while reading it
+    ///
+    /// Unlike [`GGWave::decode_wav_file`], the file is never loaded whole:
+    /// samples are read and handed to the decoder one frame at a time, as by
+    /// [`GGWave::decode_iter`], so memory use does not grow with the length of
+    /// the recording. The requirements on the WAV data are the same.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the WAV file to decode
+    /// * `on_message` - Function to call with each decoded message, in order
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating whether the whole file could be read
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ggwave_rs::GGWave;
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// ggwave
+    ///     .decode_wav_streaming("recording.wav", |message| {
+    ///         println!("{} at sample {}", message.text, message.offset);
+    ///     })
+    ///     .expect("Failed to decode WAV file");
+    /// ```
+    pub fn decode_wav_streaming<P, F>(&self, path: P, mut on_message: F) -> Result<()>
+    where
+        P: AsRef<Path>,
+        F: FnMut(DecodeResult),
+    {
+        let mut reader = WavReader::open(path)?;
+        self.check_wav_spec(&reader.spec())?;
+
+        // The decoder takes plain samples, so a read error ends the stream and
+        // is reported once decoding has stopped
+        let mut read_error = None;
+        let samples = convert::wav_samples(&mut reader)?
+            .map_while(|sample| sample.map_err(|e| read_error = Some(e)).ok());
+
+        for message in self.decode_iter(samples) {
+            on_message(message?);
+        }
+
+        read_error.map_or(Ok(()), Err)
+    }
+
+    #[cfg(feature = "std")]
+    /// Decode in-memory WAV data to text
+    ///
+    /// See [`GGWave::decode_wav_file`] for the requirements on the WAV data.
+    ///
+    /// # Arguments
+    ///
+    /// * `wav` - The WAV data to decode
+    /// * `max_payload_size` - The maximum size of the decoded payload
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the decoded text
+    pub fn decode_wav_bytes(&self, wav: &[u8], max_payload_size: usize) -> Result<String> {
+        let reader = WavReader::new(Cursor::new(wav))?;
+        self.decode_wav_reader(reader, max_payload_size)
+    }
+
+    #[cfg(feature = "std")]
+    /// Decode length-prefixed frames of raw samples received over TCP
+    ///
+    /// Each frame is a little-endian `u32` byte length followed by that many
+    /// bytes of samples in `format`. Network frames need not line up with the
+    /// decoder's frames: the samples are converted to the instance's input
+    /// sample format and fed to the decoder in whole frames of
+    /// `samplesPerFrame`, and `callback` is invoked for every decoded message.
+    /// This lets a thin client capture audio on one machine while a central
+    /// service decodes it.
+    ///
+    /// The function returns when the peer closes the connection between two
+    /// frames, or with the first error returned by `callback`. Messages that
+    /// fail to decode are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - The connection to read frames from
+    /// * `format` - The sample format of the received frames
+    /// * `callback` - Function to call with each decoded message
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success, or an error if the connection failed, a
+    /// frame was truncated or a frame exceeds 1 MiB
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::net::TcpListener;
+    /// use ggwave_rs::{GGWave, sample_formats};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let listener = TcpListener::bind("0.0.0.0:9000").expect("Failed to bind");
+    ///
+    /// for stream in listener.incoming() {
+    ///     let stream = stream.expect("Failed to accept connection");
+    ///     ggwave
+    ///         .decode_from_tcp(stream, sample_formats::I16, |message| {
+    ///             println!("Received: {}", message);
+    ///             Ok(())
+    ///         })
+    ///         .expect("Failed to decode stream");
+    /// }
+    /// ```
+    pub fn decode_from_tcp<F>(
+        &self,
+        stream: TcpStream,
+        format: SampleFormat,
+        callback: F,
+    ) -> Result<()>
+    where
+        F: FnMut(String) -> Result<()>,
+    {
+        self.decode_framed(BufReader::new(stream), format, callback)
+    }
+
+    #[cfg(feature = "std")]
+    /// Decode length-prefixed sample frames from a reader
+    fn decode_framed<R: Read, F>(
+        &self,
+        mut reader: R,
+        format: SampleFormat,
+        mut callback: F,
+    ) -> Result<()>
+    where
+        F: FnMut(String) -> Result<()>,
+    {
+        let mut decode_buffer = vec![0u8; constants::MAX_DATA_SIZE];
+        let mut pending = Vec::new();
+        let mut header = [0u8; 4];
+
+        loop {
+            match reader.read_exact(&mut header) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(()),
+                Err(e) => return Err(e.into()),
+            }
+
+            let mut frame = vec![0u8; stream_frame_len(u32::from_le_bytes(header))?];
+            reader.read_exact(&mut frame)?;
+
+            for message in
+                self.decode_stream_frame(&frame, format, &mut pending, &mut decode_buffer)?
+            {
+                callback(message)?;
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    /// Decode one frame of a length-prefixed sample stream
+    ///
+    /// Shared by the sync and async `decode_from_tcp`. The frame is converted
+    /// from `format` to the input sample format and appended to `pending`,
+    /// whose whole decoder frames are then decoded as by
+    /// [`GGWave::process_audio_chunk_all`]. The samples of an incomplete
+    /// decoder frame stay in `pending` for the next network frame.
+    pub(crate) fn decode_stream_frame(
+        &self,
+        frame: &[u8],
+        format: SampleFormat,
+        pending: &mut Vec<u8>,
+        decode_buffer: &mut [u8],
+    ) -> Result<Vec<String>> {
+        let input_format = self.params.sampleFormatInp;
+        pending.extend(convert::convert_samples(frame, format, input_format)?);
+
+        let frame_bytes =
+            self.params.samplesPerFrame.max(1) as usize * convert::sample_size(input_format)?;
+        let whole = pending.len() - pending.len() % frame_bytes;
+        let messages = self.process_audio_chunk_all(&pending[..whole], decode_buffer)?;
+        pending.drain(..whole);
+        Ok(messages)
+    }
+
+    #[cfg(feature = "std")]
+    /// Validate a WAV stream against the instance parameters and decode it
+    fn decode_wav_reader<R: Read>(
+        &self,
+        mut reader: WavReader<R>,
+        max_payload_size: usize,
+    ) -> Result<String> {
+        self.check_wav_spec(&reader.spec())?;
+        let samples = convert::read_wav_samples(&mut reader)?;
+        let waveform = convert::f32_to_bytes(&samples, self.params.sampleFormatInp)?;
+        self.decode_to_string(&waveform, max_payload_size)
+    }
+
+    #[cfg(feature = "std")]
+    /// Check that WAV data is mono at the input sample rate
+    fn check_wav_spec(&self, spec: &WavSpec) -> Result<()> {
+        if spec.channels != 1 {
+            return Err(Error::InvalidParameter("WAV data must be mono"));
+        }
+
+        if spec.sample_rate != self.params.sampleRateInp as u32 {
+            return Err(Error::InvalidParameter(
+                "WAV sample rate does not match the instance input sample rate",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Get the current output sample format
+    ///
+    /// # Returns
+    ///
+    /// The output sample format this instance was created with
+    pub fn get_output_sample_format(&self) -> SampleFormat {
+        self.params.sampleFormatOut
+    }
+
+    #[cfg(feature = "std")]
+    /// Convert raw audio data to WAV format in memory
+    ///
+    /// `raw_data` is read in the output sample format of this instance. 8-bit
+    /// formats become 8-bit WAV files, which are always unsigned, so `I8`
+    /// samples are offset by 128. 16-bit formats become signed 16-bit files, so
+    /// `U16` samples are offset by -32768, and `F32` samples are scaled to
+    /// signed 16-bit.
+    ///
+    /// # Arguments
+    ///
+    /// * `raw_data` - The raw audio data to convert
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a `Vec<u8>` with the WAV data,
+    /// `Error::InvalidSampleFormat` if the output format is unknown, or
+    /// `Error::InvalidParameter` if `raw_data` ends in a partial sample
+    pub fn raw_to_wav(&self, raw_data: &[u8]) -> Result<Vec<u8>> {
+        convert::write_wav(
+            raw_data,
+            self.params.sampleFormatOut,
+            self.params.sampleRateOut,
+        )
+    }
+
+    #[cfg(feature = "std")]
+    /// Encode text and convert to WAV format
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to encode
+    /// * `protocol_id` - The protocol to use for encoding
+    /// * `volume` - The volume of the encoded audio (0-100)
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a `Vec<u8>` with the WAV data
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    /// use std::fs;
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let wav_data = ggwave.encode_to_wav("Hello, World!", protocols::AUDIBLE_NORMAL, 50)
+    ///     .expect("Failed to encode text to WAV");
+    ///
+    /// fs::write("hello.wav", wav_data).expect("Failed to write WAV file");
+    /// ```
+    pub fn encode_to_wav(
+        &self,
+        text: &str,
+        protocol_id: ProtocolId,
+        volume: i32,
+    ) -> Result<Vec<u8>> {
+        let raw_data = self.encode(text, protocol_id, volume)?;
+        self.raw_to_wav(&raw_data)
+    }
+
+    #[cfg(feature = "std")]
+    /// Encode text and convert to WAV format at a validated volume
+    ///
+    /// Like [`GGWave::encode_to_wav`], but the volume is a [`Volume`].
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to encode
+    /// * `protocol_id` - The protocol to use for encoding
+    /// * `volume` - The volume of the encoded audio
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a `Vec<u8>` with the WAV data
+    pub fn encode_to_wav_v(
+        &self,
+        text: &str,
+        protocol_id: ProtocolId,
+        volume: impl Into<Volume>,
+    ) -> Result<Vec<u8>> {
+        self.encode_to_wav(text, protocol_id, volume.into().into())
+    }
+
+    #[cfg(feature = "std")]
+    /// Save raw audio data to a WAV file
+    ///
+    /// # Arguments
+    ///
+    /// * `raw_data` - The raw audio data to save
+    /// * `path` - The path to save the WAV file to
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure
+    pub fn save_raw_to_wav<P: AsRef<Path>>(&self, raw_data: &[u8], path: P) -> Result<()> {
+        let wav_data = self.raw_to_wav(raw_data)?;
+        std::fs::write(path, wav_data)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    /// Encode text and save directly to a WAV file
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to encode
+    /// * `protocol_id` - The protocol to use for encoding
+    /// * `volume` - The volume of the encoded audio (0-100)
+    /// * `path` - The path to save the WAV file to
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// ggwave.encode_to_wav_file("Hello, World!", protocols::AUDIBLE_NORMAL, 50, "hello.wav")
+    ///     .expect("Failed to encode and save WAV file");
+    /// ```
+    pub fn encode_to_wav_file<P: AsRef<Path>>(
+        &self,
+        text: &str,
+        protocol_id: ProtocolId,
+        volume: i32,
+        path: P,
+    ) -> Result<()> {
+        let raw_data = self.encode(text, protocol_id, volume)?;
+        self.save_raw_to_wav(&raw_data, path)
+    }
+
+    /// Encode text and convert to FLAC format
+    ///
+    /// FLAC is lossless, so the result can be decoded again after converting it
+    /// back to raw samples. Samples are stored as 16-bit integers at the output
+    /// sample rate.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to encode
+    /// * `protocol_id` - The protocol to use for encoding
+    /// * `volume` - The volume of the encoded audio (0-100)
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a `Vec<u8>` with the FLAC data
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    /// use std::fs;
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let flac_data = ggwave.encode_to_flac("Hello, World!", protocols::AUDIBLE_NORMAL, 50)
+    ///     .expect("Failed to encode text to FLAC");
+    ///
+    /// fs::write("hello.flac", flac_data).expect("Failed to write FLAC file");
+    /// ```
+    #[cfg(feature = "flac")]
+    pub fn encode_to_flac(
+        &self,
+        text: &str,
+        protocol_id: ProtocolId,
+        volume: i32,
+    ) -> Result<Vec<u8>> {
+        let raw_data = self.encode(text, protocol_id, volume)?;
+        let samples = convert::bytes_to_f32(&raw_data, self.params.sampleFormatOut)?;
+        export::to_flac(&samples, self.params.sampleRateOut as u32)
+    }
+
+    /// Encode text and save directly to a FLAC file
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to encode
+    /// * `protocol_id` - The protocol to use for encoding
+    /// * `volume` - The volume of the encoded audio (0-100)
+    /// * `path` - The path to save the FLAC file to
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure
+    #[cfg(feature = "flac")]
+    pub fn encode_to_flac_file<P: AsRef<Path>>(
+        &self,
+        text: &str,
+        protocol_id: ProtocolId,
+        volume: i32,
+        path: P,
+    ) -> Result<()> {
+        let flac_data = self.encode_to_flac(text, protocol_id, volume)?;
+        std::fs::write(path, flac_data)?;
+        Ok(())
+    }
+
+    /// Decode FLAC data to text
+    ///
+    /// The FLAC stream must be mono and recorded at the input sample rate of
+    /// this instance, as produced by [`GGWave::encode_to_flac`] on an instance
+    /// with the same sample rate. FLAC is lossless, so the tones arrive intact.
+    ///
+    /// # Arguments
+    ///
+    /// * `flac` - The FLAC data to decode
+    /// * `max_payload_size` - The maximum size of the decoded payload
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the decoded text
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let flac_data = ggwave.encode_to_flac("Hello, World!", protocols::AUDIBLE_NORMAL, 50)
+    ///     .expect("Failed to encode text to FLAC");
+    ///
+    /// let decoded = ggwave.decode_flac(&flac_data, 1024)
+    ///     .expect("Failed to decode FLAC data");
+    /// assert_eq!(decoded, "Hello, World!");
+    /// ```
+    #[cfg(feature = "flac")]
+    pub fn decode_flac(&self, flac: &[u8], max_payload_size: usize) -> Result<String> {
+        let (samples, sample_rate) = export::from_flac(flac)?;
+        self.decode_compressed(&samples, sample_rate, max_payload_size)
+    }
+
+    /// Encode text and convert to Ogg Vorbis format
+    ///
+    /// **Vorbis is lossy.** Its psychoacoustic model can smear or drop the
+    /// tones ggwave relies on, particularly for ultrasound and fast protocols,
+    /// so the result may no longer decode. Use it for compact archival or
+    /// playback only, and prefer FLAC or WAV when the audio must be decoded
+    /// again.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to encode
+    /// * `protocol_id` - The protocol to use for encoding
+    /// * `volume` - The volume of the encoded audio (0-100)
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a `Vec<u8>` with the Ogg Vorbis data
+    #[cfg(feature = "ogg")]
+    pub fn encode_to_ogg(
+        &self,
+        text: &str,
+        protocol_id: ProtocolId,
+        volume: i32,
+    ) -> Result<Vec<u8>> {
+        let raw_data = self.encode(text, protocol_id, volume)?;
+        let samples = convert::bytes_to_f32(&raw_data, self.params.sampleFormatOut)?;
+        export::to_ogg(&samples, self.params.sampleRateOut as u32)
+    }
+
+    /// Encode text and save directly to an Ogg Vorbis file
+    ///
+    /// See [`GGWave::encode_to_ogg`] for the caveats of lossy compression.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to encode
+    /// * `protocol_id` - The protocol to use for encoding
+    /// * `volume` - The volume of the encoded audio (0-100)
+    /// * `path` - The path to save the Ogg file to
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure
+    #[cfg(feature = "ogg")]
+    pub fn encode_to_ogg_file<P: AsRef<Path>>(
+        &self,
+        text: &str,
+        protocol_id: ProtocolId,
+        volume: i32,
+        path: P,
+    ) -> Result<()> {
+        let ogg_data = self.encode_to_ogg(text, protocol_id, volume)?;
+        std::fs::write(path, ogg_data)?;
+        Ok(())
+    }
+
+    /// Decode Ogg Vorbis data to text
+    ///
+    /// The stream must be mono and recorded at the input sample rate of this
+    /// instance. Whether the message survived compression depends on the
+    /// protocol: audible protocols encoded at a high bitrate usually still
+    /// decode, while Vorbis discards most of the ultrasound band, so
+    /// ultrasound transmissions generally do not. Expect
+    /// [`Error::NoMessage`] when the tones were lost.
+    ///
+    /// # Arguments
+    ///
+    /// * `ogg` - The Ogg Vorbis data to decode
+    /// * `max_payload_size` - The maximum size of the decoded payload
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the decoded text
+    #[cfg(feature = "ogg")]
+    pub fn decode_ogg(&self, ogg: &[u8], max_payload_size: usize) -> Result<String> {
+        let (samples, sample_rate) = export::from_ogg(ogg)?;
+        self.decode_compressed(&samples, sample_rate, max_payload_size)
+    }
+
+    /// Check the sample rate of decompressed audio and decode it
+    #[cfg(any(feature = "flac", feature = "ogg"))]
+    fn decode_compressed(
+        &self,
+        samples: &[f32],
+        sample_rate: u32,
+        max_payload_size: usize,
+    ) -> Result<String> {
+        if sample_rate != self.params.sampleRateInp as u32 {
+            return Err(Error::InvalidParameter(
+                "Audio sample rate does not match the instance input sample rate",
+            ));
+        }
+
+        let waveform = convert::f32_to_bytes(samples, self.params.sampleFormatInp)?;
+        self.decode_to_string(&waveform, max_payload_size)
+    }
+
+    /// Toggle reception of a specific protocol
+    ///
+    /// The C library keeps one process-wide protocol table that instances copy
+    /// when they are created, so this affects instances created afterwards on
+    /// any thread but not this one. Use [`GGWaveBuilder::rx_protocols`] to pick
+    /// the protocols a single instance receives.
+    ///
+    /// # Arguments
+    ///
+    /// * `protocol_id` - The protocol to toggle
+    /// * `enabled` - Whether to enable or disable the protocol
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// // Disable reception of ultrasound protocols
+    /// ggwave.toggle_rx_protocol(protocols::ULTRASOUND_NORMAL, false);
+    /// ggwave.toggle_rx_protocol(protocols::ULTRASOUND_FAST, false);
+    /// ggwave.toggle_rx_protocol(protocols::ULTRASOUND_FASTEST, false);
+    /// ```
+    pub fn toggle_rx_protocol(&self, protocol_id: ProtocolId, enabled: bool) {
+        let _global = global_write();
+        let mut mask = protocol_mask::global_rx();
+        mask.set(protocol_id, enabled);
+        unsafe {
+            protocol_mask::apply_rx(mask);
+        }
+    }
+
+    /// Toggle transmission of a specific protocol
+    ///
+    /// Like [`GGWave::toggle_rx_protocol`], this only affects instances created
+    /// afterwards. Use [`GGWaveBuilder::tx_protocols`] to pick the protocols a
+    /// single instance can send.
+    ///
+    /// # Arguments
+    ///
+    /// * `protocol_id` - The protocol to toggle
+    /// * `enabled` - Whether to enable or disable the protocol
+    pub fn toggle_tx_protocol(&self, protocol_id: ProtocolId, enabled: bool) {
+        let _global = global_write();
+        let mut mask = protocol_mask::global_tx();
+        mask.set(protocol_id, enabled);
+        unsafe {
+            protocol_mask::apply_tx(mask);
+        }
+    }
+
+    /// Get the protocols currently enabled for reception
+    ///
+    /// This reports the process-wide table changed by
+    /// [`GGWave::toggle_rx_protocol`] and [`GGWave::enable_all_rx_protocols`],
+    /// which instances created afterwards will use. Use
+    /// [`GGWave::rx_protocols`] for the protocols of this instance.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// ggwave.toggle_rx_protocol(protocols::DT_FAST, false);
+    /// assert!(!ggwave.enabled_rx_protocols().contains(&protocols::DT_FAST));
+    /// ```
+    pub fn enabled_rx_protocols(&self) -> Vec<ProtocolId> {
+        let _global = global_read();
+        protocol_mask::global_rx().iter().collect()
+    }
+
+    /// Get the protocols currently enabled for transmission
+    ///
+    /// This reports the process-wide table changed by
+    /// [`GGWave::toggle_tx_protocol`], which instances created afterwards will
+    /// use. Use [`GGWave::tx_protocols`] for the protocols of this instance.
+    pub fn enabled_tx_protocols(&self) -> Vec<ProtocolId> {
+        let _global = global_read();
+        protocol_mask::global_tx().iter().collect()
+    }
+
+    /// Set the starting frequency for a reception protocol
+    ///
+    /// Start frequencies live in the process-wide protocol table that
+    /// instances copy when they are created, and masks cannot override them, so
+    /// the new frequency only applies to instances created afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `protocol_id` - The protocol to modify
+    /// * `freq_start` - The first frequency bin of the protocol
+    pub fn set_rx_protocol_freq_start(&self, protocol_id: ProtocolId, freq_start: i32) {
+        let _global = global_write();
+        unsafe {
+            ggwave_rxProtocolSetFreqStart(protocol_id, freq_start);
+        }
+    }
+
+    /// Set the starting frequency for a transmission protocol
+    ///
+    /// As with [`GGWave::set_rx_protocol_freq_start`], only instances created
+    /// afterwards send on the new frequency, and receivers must be created with
+    /// the same start to hear them.
+    ///
+    /// # Arguments
+    ///
+    /// * `protocol_id` - The protocol to modify
+    /// * `freq_start` - The first frequency bin of the protocol
+    pub fn set_tx_protocol_freq_start(&self, protocol_id: ProtocolId, freq_start: i32) {
+        let _global = global_write();
+        unsafe {
+            ggwave_txProtocolSetFreqStart(protocol_id, freq_start);
+        }
+    }
+
+    /// Define one of the custom protocols and enable it for rx and tx
+    ///
+    /// The protocol is registered in the process-wide table that instances copy
+    /// when they are created, so both the sender and the receiver must be
+    /// created afterwards. Instances that should not use it can leave it out of
+    /// their builder's `rx_protocols` and `tx_protocols`.
+    ///
+    /// # Arguments
+    ///
+    /// * `protocol_id` - One of `protocols::CUSTOM_0` to `protocols::CUSTOM_9`
+    /// * `protocol` - The tone layout of the protocol
+    ///
+    /// # Returns
+    ///
+    /// `Error::InvalidParameter` if the id is not a custom protocol or the
+    /// tones do not fit below the Nyquist frequency of this instance
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{CustomProtocol, GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// ggwave
+    ///     .register_custom_protocol(
+    ///         protocols::CUSTOM_0,
+    ///         CustomProtocol::new().freq_start(64).frames_per_tx(6),
+    ///     )
+    ///     .expect("Failed to register protocol");
+    ///
+    /// // Instances created from now on can use CUSTOM_0
+    /// let sender = GGWave::new().expect("Failed to initialize GGWave");
+    /// let waveform = sender
+    ///     .encode("Hello", protocols::CUSTOM_0, 50)
+    ///     .expect("Failed to encode text");
+    /// ```
+    pub fn register_custom_protocol(
+        &self,
+        protocol_id: ProtocolId,
+        protocol: CustomProtocol,
+    ) -> Result<()> {
+        protocol.validate(protocol_id, &self.params)?;
+
+        let _global = global_write();
+        unsafe {
+            ggwave_rs_setProtocol(
+                protocol_id,
+                protocol.raw_freq_start(),
+                protocol.raw_frames_per_tx(),
+                protocol.raw_bytes_per_tx(),
+                protocol.extra(),
+            );
+            protocol_mask::apply_rx(protocol_mask::global_rx().with(protocol_id));
+            protocol_mask::apply_tx(protocol_mask::global_tx().with(protocol_id));
+        }
+
+        Ok(())
+    }
+
+    /// Get the duration in frames of the current or last reception
+    ///
+    /// ggwave sets this when it detects a start marker, so it is 0 on an
+    /// instance that has not heard one yet. Use
+    /// [`GGWave::max_rx_duration_frames`] to size buffers.
+    ///
+    /// # Returns
+    ///
+    /// The duration in frames
+    pub fn rx_duration_frames(&self) -> i32 {
+        let _global = global_read();
+        unsafe { ggwave_rxDurationFrames(self.instance) }
+    }
+
+    /// Longest reception, in frames, that this instance can go through
+    ///
+    /// This is the length ggwave allows for a message once it hears a start
+    /// marker: both markers plus the data of a `MAX_LENGTH_VARIABLE` byte
+    /// payload with its error correction, sent with the slowest enabled rx
+    /// protocol. Unlike [`GGWave::rx_duration_frames`] it does not depend on
+    /// what the instance has heard, so it can size capture buffers up front.
+    /// Custom protocols, whose layout is not recorded, count as slow as the
+    /// slowest built-in protocol.
+    ///
+    /// # Returns
+    ///
+    /// The number of frames, or 0 for instances that cannot receive
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::GGWave;
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let frames = ggwave.max_rx_duration_frames();
+    /// let capture = Vec::<f32>::with_capacity(frames * ggwave.parameters().samplesPerFrame as usize);
+    /// ```
+    pub fn max_rx_duration_frames(&self) -> usize {
+        if self.params.operatingMode & operating_modes::RX == 0 {
+            return 0;
+        }
+
+        // Mirrors the receive duration ggwave sets when it detects a start
+        // marker; multi-tone protocols do not lengthen it
+        let (frames_per_tx, bytes_per_tx) = self
+            .rx_protocols
+            .iter()
+            .map(|id| protocols::layout(id).unwrap_or((9, 1, 1)))
+            .fold((0, usize::MAX), |(frames, bytes), (f, b, extra)| {
+                let frames = if extra == 1 { frames.max(f) } else { frames };
+                (frames, bytes.min(b))
+            });
+        if bytes_per_tx == usize::MAX {
+            return 0;
+        }
+
+        let max_length = constants::MAX_LENGTH_VARIABLE;
+        let ecc_length = protocols::ecc_bytes(max_length);
+        let marker_frames = if self.params.payloadLength > 0 {
+            0
+        } else {
+            constants::DEFAULT_MARKER_FRAMES
+        };
+
+        2 * marker_frames + frames_per_tx * ((max_length + ecc_length) / bytes_per_tx + 1)
+    }
+
+    /// Get the duration in samples of the current or last reception
+    ///
+    /// Like [`GGWave::rx_duration_frames`], this is 0 until the instance has
+    /// heard a start marker.
+    ///
+    /// # Returns
+    ///
+    /// The duration in samples, [`GGWave::rx_duration_frames`] times the
+    /// samples per frame
+    pub fn rx_duration_samples(&self) -> i32 {
+        self.rx_duration_frames() * self.params.samplesPerFrame
+    }
+
+    /// Get the duration in seconds of the current or last reception
+    ///
+    /// # Returns
+    ///
+    /// The duration of [`GGWave::rx_duration_samples`] at the input sample rate
+    pub fn rx_duration_secs(&self) -> f32 {
+        self.rx_duration_samples() as f32 / self.params.sampleRateInp
+    }
+
+    /// Discard any message the decoder is part way through receiving
+    ///
+    /// Call this between recording sessions that reuse one instance, so the
+    /// end of one session cannot combine with the start of the next. The C API
+    /// has no reset, so this feeds the decoder enough silence to abandon a
+    /// partial message of any length, which takes about as long as decoding
+    /// [`GGWave::rx_duration_samples`] samples. Does nothing for instances that
+    /// cannot receive or have not heard a start marker yet.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating whether the silence could be fed to the decoder
+    pub fn reset_rx_state(&self) -> Result<()> {
+        let rx_frames = self.rx_duration_frames();
+        if rx_frames <= 0 {
+            return Ok(());
+        }
+
+        let frame_len = self.params.samplesPerFrame.max(1) as usize;
+        let silence = convert::f32_to_bytes(&vec![0.0; frame_len], self.params.sampleFormatInp)?;
+        let mut buffer = vec![0u8; constants::MAX_DATA_SIZE];
+        let frames = rx_frames as usize
+            + 2 * constants::DEFAULT_MARKER_FRAMES
+            + constants::DEFAULT_ENCODED_DATA_OFFSET;
+
+        for _ in 0..frames {
+            match self.process_audio_chunk(&silence, &mut buffer) {
+                Ok(_) | Err(Error::DecodeFailed(_)) | Err(Error::Utf8Error(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Set debug mode and optionally redirect logs to a file
+    ///
+    /// The log output of the C library is process-wide, so this affects every
+    /// instance. A log file opened by an earlier call, or the stream of
+    /// [`GGWave::set_log_callback`], is closed once it is replaced.
+    ///
+    /// # Arguments
+    ///
+    /// * `debug_file` - Optional path to a log file, or None to disable logging
+    ///
+    /// # Safety
+    ///
+    /// This function is marked safe but internally uses unsafe operations to interact
+    /// with C file handling. The file path must be valid and accessible; if the
+    /// file cannot be opened, or the path contains a NUL byte, logging is left
+    /// unchanged.
+    pub fn set_debug_mode(&self, debug_file: Option<&str>) {
+        let file = match debug_file {
+            Some(path) => {
+                let Ok(c_str) = alloc::ffi::CString::new(path) else {
+                    return;
+                };
+
+                // Try to open the file in C
+                let file_ptr = unsafe { libc::fopen(c_str.as_ptr(), c"w".as_ptr()) };
+                if file_ptr.is_null() {
+                    return;
+                }
+                file_ptr
+            }
+            // Disable logging
+            None => ptr::null_mut(),
+        };
+
+        let _global = global_write();
+        unsafe { replace_log_file(file) };
+    }
+
+    #[cfg(feature = "std")]
+    /// Route the log output of the C library to a closure
+    ///
+    /// The closure is called with each line the library logs, without the
+    /// line break, which makes it easy to forward diagnostics to `log` or
+    /// `tracing`. Like [`GGWave::set_debug_mode`], this is process-wide and
+    /// replaces any log file or earlier callback.
+    ///
+    /// The closure runs on the thread that made the call being logged, while
+    /// that call holds the lock on the global state, so it must not call back
+    /// into ggwave. A panic in the closure aborts the process.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - Function to call with each log line
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success, or `Error::InvalidParameter` on platforms
+    /// whose C library cannot create custom streams (only glibc, macOS and the
+    /// BSDs can)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::GGWave;
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// ggwave
+    ///     .set_log_callback(|line| eprintln!("ggwave: {}", line))
+    ///     .expect("Failed to set log callback");
+    /// ```
+    pub fn set_log_callback(&self, callback: impl Fn(&str) + Send + 'static) -> Result<()> {
+        let _global = global_write();
+        let stream = unsafe { ggwave_rs_openLogStream(log_trampoline) };
+        if stream.is_null() {
+            return Err(Error::InvalidParameter(
+                "Log callbacks are not supported on this platform",
+            ));
+        }
+
+        // Only replace the callback once the stream it serves exists, so a
+        // failed call leaves the current callback in place
+        *LOG_CALLBACK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(Box::new(callback));
+        unsafe { replace_log_file(stream) };
+        Ok(())
+    }
+
+    /// Enables all reception protocols
+    ///
+    /// This is a convenience method to enable all available protocols for reception.
+    pub fn enable_all_rx_protocols(&self) {
+        for protocol_id in 0..protocols::COUNT {
+            self.toggle_rx_protocol(protocol_id, true);
+        }
+    }
+
+    /// Decode raw audio data to binary data
+    ///
+    /// This is the variant of decode to use when the data being transmitted is
+    /// not UTF-8 text, e.g. data sent with [`GGWave::encode_checked`].
+    ///
+    /// `waveform` is read in the input sample format of this instance, as by
+    /// [`GGWave::decode`].
+    ///
+    /// # Arguments
+    ///
+    /// * `waveform` - The raw audio data to decode
+    /// * `buffer` - Buffer to store the decoded payload
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a slice of the decoded binary data,
+    /// `Error::NoMessage` if the audio does not contain a complete message,
+    /// `Error::BufferTooSmall` with the payload length if it does not fit in
+    /// `buffer`, or `Error::InvalidParameter` if `waveform` does not hold a
+    /// whole number of input samples
+    pub fn decode_binary<'a>(&self, waveform: &[u8], buffer: &'a mut [u8]) -> Result<&'a [u8]> {
+        self.decode_payload(waveform, buffer)
+    }
+
+    /// Decode raw audio data to an owned vector of binary data
+    ///
+    /// The binary counterpart of [`GGWave::decode_to_string`]: the payload is
+    /// returned in a vector of exactly its length, which can be passed up the
+    /// call stack without keeping a buffer alive.
+    ///
+    /// # Arguments
+    ///
+    /// * `waveform` - The raw audio data to decode
+    /// * `max_payload_size` - The maximum size of the decoded payload
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the decoded binary data, or `Error::NoMessage` if
+    /// the audio does not contain a complete message
+    pub fn decode_binary_owned(&self, waveform: &[u8], max_payload_size: usize) -> Result<Vec<u8>> {
+        let mut buffer = vec![0u8; max_payload_size];
+        let length = self.decode_binary(waveform, &mut buffer)?.len();
+        buffer.truncate(length);
+        buffer.shrink_to_fit();
+        Ok(buffer)
+    }
+
+    /// Memory-efficient continuous audio decoder
+    ///
+    /// This method is designed for real-time continuous audio processing where
+    /// audio is being processed in chunks, such as from a microphone input.
+    /// Chunks are read in the input sample format of this instance, as by
+    /// [`GGWave::decode`], so each must end on a sample boundary.
+    ///
+    /// # Arguments
+    ///
+    /// * `audio_chunk` - New chunk of audio data to process
+    /// * `decode_buffer` - Buffer to store decoded payload if found
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing an Option with the decoded string if something was
+    /// found. A chunk that completes no message gives `Ok(None)`, not an error.
+    /// `Error::BufferTooSmall` is returned if the payload does not fit in
+    /// `decode_buffer`, and `Error::InvalidParameter` if the chunk does not
+    /// hold a whole number of input samples.
+    pub fn process_audio_chunk<'a>(
+        &self,
+        audio_chunk: &[u8],
+        decode_buffer: &'a mut [u8],
+    ) -> Result<Option<&'a str>> {
+        match self.process_audio_chunk_binary(audio_chunk, decode_buffer)? {
+            Some(payload) => core::str::from_utf8(payload)
+                .map(Some)
+                .map_err(Error::Utf8Error),
+            None => Ok(None),
+        }
+    }
+
+    /// Binary counterpart of [`GGWave::process_audio_chunk`]
+    pub(crate) fn process_audio_chunk_binary<'a>(
+        &self,
+        audio_chunk: &[u8],
+        decode_buffer: &'a mut [u8],
+    ) -> Result<Option<&'a [u8]>> {
+        self.check_input_alignment(audio_chunk)?;
+        // `ggwave_decode` does not take the buffer size, so decode into a
+        // buffer that fits any payload
+        let mut scratch = [0u8; constants::MAX_DATA_SIZE];
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+        let result = {
+            let _global = global_read();
+            unsafe {
+                ggwave_decode(
+                    self.instance,
+                    audio_chunk.as_ptr() as *const c_void,
+                    audio_chunk.len() as i32,
+                    scratch.as_mut_ptr() as *mut c_void,
+                )
+            }
+        };
+        #[cfg(feature = "metrics")]
+        self.record_decode(audio_chunk, result, started);
+
+        if result == 0 {
+            Ok(None) // No data decoded, but no error
+        } else if result < 0 {
+            Err(Error::DecodeFailed(result))
+        } else {
+            // Something was decoded
+            self.copy_payload(&mut scratch[..result as usize], decode_buffer)
+                .map(Some)
+        }
+    }
+
+    /// Process a chunk of audio and collect every message completed in it
+    ///
+    /// [`GGWave::process_audio_chunk`] only reports the last message completed
+    /// in a chunk, so a long chunk holding several transmissions loses all but
+    /// one. This feeds the chunk to the decoder one frame at a time and collects
+    /// each message as soon as it completes. Repeated messages within the chunk
+    /// are reported once, and frames that fail to decode are skipped.
+    ///
+    /// An instance receives one transmission at a time. To receive simultaneous
+    /// transmissions on different bands, use one instance per band, built with
+    /// disjoint [`GGWaveBuilder::rx_protocols`] masks.
+    ///
+    /// # Arguments
+    ///
+    /// * `audio_chunk` - New chunk of audio data to process
+    /// * `decode_buffer` - Buffer used to decode each payload
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the distinct messages decoded from the chunk, in order
+    pub fn process_audio_chunk_all(
+        &self,
+        audio_chunk: &[u8],
+        decode_buffer: &mut [u8],
+    ) -> Result<Vec<String>> {
+        self.check_input_alignment(audio_chunk)?;
+        let frame_bytes = self.params.samplesPerFrame.max(1) as usize
+            * convert::sample_size(self.params.sampleFormatInp)?;
+        let mut messages: Vec<String> = Vec::new();
+
+        for frame in audio_chunk.chunks(frame_bytes) {
+            match self.process_audio_chunk(frame, decode_buffer) {
+                Ok(Some(message)) if !message.is_empty() => {
+                    if !messages.iter().any(|m| m == message) {
+                        messages.push(message.to_string());
+                    }
+                }
+                Ok(_) | Err(Error::DecodeFailed(_)) | Err(Error::Utf8Error(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(messages)
+    }
+
+    /// Check that `audio` holds a whole number of samples in the input format
+    ///
+    /// The C library silently drops a trailing partial sample, which hides
+    /// audio passed in the wrong format.
+    fn check_input_alignment(&self, audio: &[u8]) -> Result<()> {
+        if !audio
+            .len()
+            .is_multiple_of(convert::sample_size(self.params.sampleFormatInp)?)
+        {
+            return Err(Error::InvalidParameter(
+                "Audio must hold whole samples in the input sample format",
+            ));
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "audio")]
+    /// Wait for a message on the default input device
+    ///
+    /// Opens the default capture device at the input sample rate, feeds it to
+    /// the decoder frame by frame like [`StreamDecoder`] and returns the first
+    /// message decoded. This blocks the current thread, which suits scripts and
+    /// tests; for continuous reception use [`audio::listen`].
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long to listen before giving up
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the decoded message, or `None` if no message
+    /// arrived before the timeout
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ggwave_rs::GGWaveBuilder;
+    /// use std::time::Duration;
+    ///
+    /// let ggwave = GGWaveBuilder::rx_preset().build().expect("Failed to initialize GGWave");
+    /// match ggwave.listen_blocking(Duration::from_secs(10)) {
+    ///     Ok(Some(message)) => println!("Received: {}", message),
+    ///     Ok(None) => println!("Nothing received"),
+    ///     Err(e) => eprintln!("Audio capture failed: {}", e),
+    /// }
+    /// ```
+    pub fn listen_blocking(&self, timeout: std::time::Duration) -> Result<Option<String>> {
+        audio::receive(self, timeout)
+    }
+
+    #[cfg(feature = "audio")]
+    /// Check that the default speaker and microphone can carry a message
+    ///
+    /// Encodes a random message with `AUDIBLE_FAST`, plays it on the default
+    /// output device while recording the default input device, and decodes the
+    /// recording. Both streams are closed before returning, and playback gives
+    /// up shortly after the message should have finished.
+    ///
+    /// The microphone must be able to hear the speakers, so this fails with
+    /// headphones or a muted output. The instance must be able to both transmit
+    /// and receive.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing whether the message was decoded from the
+    /// recording, or an error if a device could not be opened
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ggwave_rs::GGWave;
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// match ggwave.loopback_self_test() {
+    ///     Ok(true) => println!("Audio setup works"),
+    ///     Ok(false) => println!("The microphone did not pick up the speakers"),
+    ///     Err(e) => eprintln!("Audio devices unavailable: {}", e),
+    /// }
+    /// ```
+    pub fn loopback_self_test(&self) -> Result<bool> {
+        audio::loopback(self)
+    }
+
+    #[cfg(feature = "audio")]
+    /// Transmit a message until an acknowledgement is heard, raising the
+    /// volume after each unanswered try
+    ///
+    /// Plays the message on the default output device, then listens on the
+    /// default input device for up to [`audio::ACK_TIMEOUT`] for a message
+    /// accepted by `ack`. Each retransmission is [`audio::ACK_VOLUME_STEP`]
+    /// louder, capped at 100. The instance must be able to both transmit and
+    /// receive.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to transmit
+    /// * `protocol_id` - The protocol to use for encoding
+    /// * `start_volume` - The volume of the first try (0-100)
+    /// * `max_retries` - How many times to retransmit after the first try
+    /// * `ack` - Returns whether a decoded message acknowledges the transmission
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing whether an acknowledgement was heard, or an
+    /// error if a device could not be opened
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ggwave_rs::{GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let acked = ggwave
+    ///     .transmit_reliable("ping", protocols::AUDIBLE_FAST, 30, 3, |reply| reply == "pong")
+    ///     .expect("Audio devices unavailable");
+    /// println!("Acknowledged: {}", acked);
+    /// ```
+    pub fn transmit_reliable<F>(
+        &self,
+        text: &str,
+        protocol_id: ProtocolId,
+        start_volume: i32,
+        max_retries: u32,
+        ack: F,
+    ) -> Result<bool>
+    where
+        F: FnMut(&str) -> bool,
+    {
+        audio::transmit_reliable(self, text, protocol_id, start_volume, max_retries, ack)
+    }
+
+    #[cfg(feature = "std")]
+    /// Assess how well a playback/capture chain carries ggwave tones
+    ///
+    /// A stepped sine sweep covering the audible and ultrasound range is passed
+    /// to `playback_and_capture`, which must play it and return what was
+    /// recorded. The captured levels give the frequency response of the chain,
+    /// the usable frequency range, the rolloff above it and the built-in
+    /// protocols whose tones fit in that range.
+    ///
+    /// With the `audio` feature, `audio::play_and_capture` runs the sweep on
+    /// the default devices.
+    ///
+    /// # Arguments
+    ///
+    /// * `playback_and_capture` - Plays normalized mono samples at the output
+    ///   sample rate and returns the capture as normalized mono samples at the
+    ///   input sample rate
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the device report, or the error returned by
+    /// `playback_and_capture`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    ///
+    /// // A perfect loopback supports every built-in protocol
+    /// let report = ggwave.assess_device(|sweep| Ok(sweep.to_vec()))
+    ///     .expect("Failed to assess device");
+    /// assert!(report.supports(protocols::ULTRASOUND_NORMAL));
+    /// ```
+    pub fn assess_device<F>(&self, playback_and_capture: F) -> Result<DeviceReport>
+    where
+        F: FnOnce(&[f32]) -> Result<Vec<f32>>,
+    {
+        let sweep = diagnostics::sweep(&self.params);
+        let captured = playback_and_capture(&sweep)?;
+        Ok(diagnostics::analyze(&self.params, &captured))
+    }
+
+    /// Compute the spectrum of the last frame of captured audio
+    ///
+    /// The frame is the last `samplesPerFrame` samples, analyzed at the input
+    /// sample rate, which is what the decoder sees when it processes that
+    /// frame. Useful to show users whether their speaker and microphone carry
+    /// the tones of a protocol, see [`GGWave::protocol_band`].
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - Normalized samples at the input sample rate
+    ///
+    /// # Returns
+    ///
+    /// The spectrum of the last frame, or `None` if there is no whole frame
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let waveform = ggwave
+    ///     .encode_normalized("Hello, World!", protocols::AUDIBLE_FAST, 1.0)
+    ///     .expect("Failed to encode text");
+    ///
+    /// let spectrum = ggwave.frame_spectrum(&waveform[..16 * 1024]).unwrap();
+    /// if let Some((frequency, magnitude)) = spectrum.peak() {
+    ///     println!("Strongest tone: {frequency} Hz ({magnitude:.2})");
+    /// }
+    /// ```
+    #[cfg(feature = "analysis")]
+    pub fn frame_spectrum(&self, samples: &[f32]) -> Option<analysis::Spectrum> {
+        let frame_len = self.params.samplesPerFrame.max(1) as usize;
+        let frame = samples.get(samples.len().checked_sub(frame_len)?..)?;
+        Some(analysis::Spectrum::compute(
+            frame,
+            self.params.sampleRateInp,
+        ))
+    }
+
+    /// Frequency band in Hz a protocol occupies with this instance's parameters
+    ///
+    /// # Arguments
+    ///
+    /// * `protocol_id` - The protocol to look up
+    ///
+    /// # Returns
+    ///
+    /// The range from the lowest to the highest tone frequency, or `None` for
+    /// custom protocols, whose layout is not known
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let band = ggwave.protocol_band(protocols::AUDIBLE_FAST).unwrap();
+    /// println!("AUDIBLE_FAST uses {:.0}-{:.0} Hz", band.start, band.end);
+    /// ```
+    pub fn protocol_band(&self, protocol_id: ProtocolId) -> Option<Range<f32>> {
+        protocols::band(protocol_id, &self.params)
+    }
+
+    /// Check whether a protocol's tones are all above the range of human hearing
+    ///
+    /// The frequencies of the "ultrasound" protocols scale with the processing
+    /// sample rate; with the default 48 kHz and 1024 samples per frame they
+    /// start at 15 kHz, which many people can hear. This returns `true` only if
+    /// the lowest tone is above [`constants::INAUDIBLE_FREQUENCY`] (18 kHz).
+    ///
+    /// # Arguments
+    ///
+    /// * `protocol_id` - The protocol to check
+    ///
+    /// # Returns
+    ///
+    /// `true` if the protocol is inaudible with this configuration, `false`
+    /// otherwise and for custom protocols
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// if !ggwave.is_effectively_inaudible(protocols::ULTRASOUND_FAST) {
+    ///     println!("Ultrasound tones will be audible at this sample rate");
+    /// }
+    /// ```
+    pub fn is_effectively_inaudible(&self, protocol_id: ProtocolId) -> bool {
+        self.protocol_band(protocol_id)
+            .is_some_and(|band| band.start > constants::INAUDIBLE_FREQUENCY)
+    }
+
+    /// Estimate the duration of the encoded audio in seconds
+    ///
+    /// The duration is computed from the number of samples the encoder would
+    /// produce for a text of `text_length` bytes at the instance's output
+    /// sample rate, so it matches the length of the waveform returned by
+    /// [`GGWave::encode`].
+    ///
+    /// # Arguments
+    ///
+    /// * `protocol_id` - The protocol used for encoding
+    /// * `text_length` - The length of the text in bytes
+    ///
+    /// # Returns
+    ///
+    /// Duration in seconds, or 0.0 if such a text cannot be encoded
+    pub fn estimate_duration(&self, protocol_id: ProtocolId, text_length: usize) -> f32 {
+        // The waveform length only depends on the payload length, not its content
+        let placeholder = "0".repeat(text_length);
+
+        match self.encoded_sample_count(&placeholder, protocol_id, constants::DEFAULT_VOLUME) {
+            Ok(samples) => samples as f32 / self.params.sampleRateOut,
+            Err(_) => 0.0,
+        }
+    }
+}
+
+impl Default for GGWave {
+    fn default() -> Self {
         Self::new().expect("Failed to initialize GGWave with default parameters")
     }
-}
+}
+
+/// Creates an independent instance, see [`GGWave::try_clone`]
+///
+/// # Panics
+///
+/// Panics if all [`MAX_INSTANCES`] instance slots are in use. Use
+/// `try_clone` to handle that case.
+impl Clone for GGWave {
+    fn clone(&self) -> Self {
+        self.try_clone()
+            .expect("Failed to clone GGWave: no free instance slot")
+    }
+}
+
+impl Drop for GGWave {
+    fn drop(&mut self) {
+        let _global = global_write();
+        unsafe {
+            ggwave_free(self.instance);
+        }
+        LIVE_INSTANCES.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Protocol constants module for easier import
+///
+/// This module provides constants for all the available transmission protocols.
+pub mod protocols {
+    use super::*;
+
+    /// Standard audible protocol with normal speed
+    pub const AUDIBLE_NORMAL: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_AUDIBLE_NORMAL;
+    /// Fast audible protocol
+    pub const AUDIBLE_FAST: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_AUDIBLE_FAST;
+    /// Fastest audible protocol
+    pub const AUDIBLE_FASTEST: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_AUDIBLE_FASTEST;
+
+    /// Standard ultrasound protocol with normal speed
+    pub const ULTRASOUND_NORMAL: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_ULTRASOUND_NORMAL;
+    /// Fast ultrasound protocol
+    pub const ULTRASOUND_FAST: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_ULTRASOUND_FAST;
+    /// Fastest ultrasound protocol
+    pub const ULTRASOUND_FASTEST: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_ULTRASOUND_FASTEST;
+
+    /// DT protocol with normal speed
+    pub const DT_NORMAL: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_DT_NORMAL;
+    /// Fast DT protocol
+    pub const DT_FAST: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_DT_FAST;
+    /// Fastest DT protocol
+    pub const DT_FASTEST: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_DT_FASTEST;
+
+    /// MT protocol with normal speed
+    pub const MT_NORMAL: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_MT_NORMAL;
+    /// Fast MT protocol
+    pub const MT_FAST: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_MT_FAST;
+    /// Fastest MT protocol
+    pub const MT_FASTEST: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_MT_FASTEST;
+
+    /// Custom protocol 0
+    pub const CUSTOM_0: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_CUSTOM_0;
+    /// Custom protocol 1
+    pub const CUSTOM_1: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_CUSTOM_1;
+    /// Custom protocol 2
+    pub const CUSTOM_2: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_CUSTOM_2;
+    /// Custom protocol 3
+    pub const CUSTOM_3: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_CUSTOM_3;
+    /// Custom protocol 4
+    pub const CUSTOM_4: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_CUSTOM_4;
+    /// Custom protocol 5
+    pub const CUSTOM_5: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_CUSTOM_5;
+    /// Custom protocol 6
+    pub const CUSTOM_6: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_CUSTOM_6;
+    /// Custom protocol 7
+    pub const CUSTOM_7: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_CUSTOM_7;
+    /// Custom protocol 8
+    pub const CUSTOM_8: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_CUSTOM_8;
+    /// Custom protocol 9
+    pub const CUSTOM_9: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_CUSTOM_9;
+    /// Total number of protocols
+    pub const COUNT: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_COUNT;
+
+    /// Every protocol id, in ascending order
+    const ALL: [ProtocolId; COUNT as usize] = [
+        AUDIBLE_NORMAL,
+        AUDIBLE_FAST,
+        AUDIBLE_FASTEST,
+        ULTRASOUND_NORMAL,
+        ULTRASOUND_FAST,
+        ULTRASOUND_FASTEST,
+        DT_NORMAL,
+        DT_FAST,
+        DT_FASTEST,
+        MT_NORMAL,
+        MT_FAST,
+        MT_FASTEST,
+        CUSTOM_0,
+        CUSTOM_1,
+        CUSTOM_2,
+        CUSTOM_3,
+        CUSTOM_4,
+        CUSTOM_5,
+        CUSTOM_6,
+        CUSTOM_7,
+        CUSTOM_8,
+        CUSTOM_9,
+    ];
+
+    /// Names of the protocols, indexed by protocol id
+    const NAMES: [&str; COUNT as usize] = [
+        "audible_normal",
+        "audible_fast",
+        "audible_fastest",
+        "ultrasound_normal",
+        "ultrasound_fast",
+        "ultrasound_fastest",
+        "dt_normal",
+        "dt_fast",
+        "dt_fastest",
+        "mt_normal",
+        "mt_fast",
+        "mt_fastest",
+        "custom_0",
+        "custom_1",
+        "custom_2",
+        "custom_3",
+        "custom_4",
+        "custom_5",
+        "custom_6",
+        "custom_7",
+        "custom_8",
+        "custom_9",
+    ];
+
+    /// Every protocol id, including the custom ones, in ascending order
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::protocols;
+    ///
+    /// for (i, &id) in protocols::all().iter().enumerate() {
+    ///     println!("{}. {}", i + 1, protocols::name(id));
+    /// }
+    /// ```
+    pub fn all() -> &'static [ProtocolId] {
+        &ALL
+    }
+
+    /// Name of a protocol, e.g. `"audible_fast"`
+    ///
+    /// Returns `"unknown"` for ids that are not protocols.
+    pub fn name(protocol_id: ProtocolId) -> &'static str {
+        NAMES
+            .get(protocol_id as usize)
+            .copied()
+            .unwrap_or("unknown")
+    }
+
+    /// Look up a protocol by name
+    ///
+    /// Matching ignores case and treats `-` like `_`, so `"AUDIBLE_FAST"`,
+    /// `"audible_fast"` and `"audible-fast"` all give [`AUDIBLE_FAST`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::protocols;
+    ///
+    /// assert_eq!(protocols::from_name("Audible-Fast"), Some(protocols::AUDIBLE_FAST));
+    /// assert_eq!(protocols::from_name("loud"), None);
+    /// ```
+    pub fn from_name(name: &str) -> Option<ProtocolId> {
+        let matches = |candidate: &str| {
+            candidate.len() == name.len()
+                && candidate
+                    .bytes()
+                    .zip(name.bytes())
+                    .all(|(c, n)| c == n.to_ascii_lowercase() || (c == b'_' && n == b'-'))
+        };
+
+        NAMES
+            .iter()
+            .position(|candidate| matches(candidate))
+            .map(|id| id as ProtocolId)
+    }
+
+    /// Volume that usually carries a protocol reliably, from 0 to 100
+    ///
+    /// This is a heuristic, not a measurement: the right volume depends on the
+    /// speaker, the microphone and the distance between them. Faster protocols
+    /// hold each tone for fewer frames and get a little more volume, and
+    /// ultrasound protocols get more still, since most speakers and
+    /// microphones are much less sensitive above 15 kHz. Custom protocols get
+    /// [`constants::DEFAULT_VOLUME`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let protocol = protocols::ULTRASOUND_FAST;
+    /// let waveform = ggwave
+    ///     .encode("Hello, World!", protocol, protocols::recommended_volume(protocol))
+    ///     .expect("Failed to encode text");
+    /// ```
+    pub const fn recommended_volume(protocol_id: ProtocolId) -> i32 {
+        match protocol_id {
+            AUDIBLE_NORMAL | DT_NORMAL | MT_NORMAL => 40,
+            AUDIBLE_FAST | DT_FAST | MT_FAST => 50,
+            AUDIBLE_FASTEST | DT_FASTEST | MT_FASTEST => 60,
+            ULTRASOUND_NORMAL => 70,
+            ULTRASOUND_FAST => 80,
+            ULTRASOUND_FASTEST => 90,
+            _ => constants::DEFAULT_VOLUME,
+        }
+    }
+
+    /// Frequency band in Hz a protocol occupies with the default parameters
+    ///
+    /// The band scales with the processing sample rate and samples per frame,
+    /// so this assumes ggwave's defaults of 48 kHz and 1024 samples per frame.
+    /// Use [`GGWave::protocol_band`] for the band of a configured instance.
+    ///
+    /// # Returns
+    ///
+    /// The range from the lowest to the highest tone frequency, or `None` for
+    /// custom protocols, whose layout is not known
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::protocols;
+    ///
+    /// let band = protocols::frequency_range(protocols::ULTRASOUND_FAST).unwrap();
+    /// println!("ULTRASOUND_FAST uses {:.0}-{:.0} Hz", band.start, band.end);
+    /// assert!(band.start >= 15000.0);
+    /// ```
+    pub fn frequency_range(protocol_id: ProtocolId) -> Option<Range<f32>> {
+        band(protocol_id, &GGWave::default_parameters())
+    }
+
+    /// Frequency band in Hz occupied by a built-in protocol
+    ///
+    /// ggwave places tones on FFT bins of `sampleRate / samplesPerFrame` Hz,
+    /// starting at a protocol-specific bin. Returns `None` for custom protocols,
+    /// whose layout is not known.
+    pub(crate) fn band(protocol_id: ProtocolId, params: &Parameters) -> Option<Range<f32>> {
+        let bins = bins(protocol_id)?;
+        let hz_per_bin = params.sampleRate / params.samplesPerFrame.max(1) as f32;
+        Some(bins.start as f32 * hz_per_bin..bins.end as f32 * hz_per_bin)
+    }
+
+    /// Frames per transmission, bytes per transmission and tones per bit of a
+    /// built-in protocol, as defined by ggwave
+    ///
+    /// Returns `None` for custom protocols.
+    pub(crate) const fn layout(protocol_id: ProtocolId) -> Option<(usize, usize, usize)> {
+        let frames_per_tx = match protocol_id {
+            AUDIBLE_NORMAL | ULTRASOUND_NORMAL | DT_NORMAL | MT_NORMAL => 9,
+            AUDIBLE_FAST | ULTRASOUND_FAST | DT_FAST | MT_FAST => 6,
+            AUDIBLE_FASTEST | ULTRASOUND_FASTEST | DT_FASTEST | MT_FASTEST => 3,
+            _ => return None,
+        };
+        let (bytes_per_tx, extra) = match protocol_id {
+            AUDIBLE_NORMAL..=ULTRASOUND_FASTEST => (3, 1),
+            DT_NORMAL..=DT_FASTEST => (1, 1),
+            _ => (1, 2),
+        };
+        Some((frames_per_tx, bytes_per_tx, extra))
+    }
+
+    /// Number of error correction bytes ggwave adds to a payload
+    pub(crate) const fn ecc_bytes(length: usize) -> usize {
+        if length < 4 {
+            2
+        } else if 2 * (length / 5) > 4 {
+            2 * (length / 5)
+        } else {
+            4
+        }
+    }
+
+    /// FFT bins occupied by the tones of a built-in protocol
+    ///
+    /// Returns `None` for custom protocols.
+    pub(crate) fn bins(protocol_id: ProtocolId) -> Option<Range<i32>> {
+        let (freq_start, bins) = match protocol_id {
+            AUDIBLE_NORMAL..=AUDIBLE_FASTEST => (40, 96),
+            ULTRASOUND_NORMAL..=ULTRASOUND_FASTEST => (320, 96),
+            DT_NORMAL..=MT_FASTEST => (24, 64),
+            _ => return None,
+        };
+        Some(freq_start..freq_start + bins)
+    }
+}
+
+/// Sample format constants
+///
+/// This module provides constants for all the available sample formats.
+pub mod sample_formats {
+    use super::*;
+
+    /// Undefined sample format
+    pub const UNDEFINED: SampleFormat = ggwave_SampleFormat_GGWAVE_SAMPLE_FORMAT_UNDEFINED;
+    /// Unsigned 8-bit sample format
+    pub const U8: SampleFormat = ggwave_SampleFormat_GGWAVE_SAMPLE_FORMAT_U8;
+    /// Signed 8-bit sample format
+    pub const I8: SampleFormat = ggwave_SampleFormat_GGWAVE_SAMPLE_FORMAT_I8;
+    /// Unsigned 16-bit sample format
+    pub const U16: SampleFormat = ggwave_SampleFormat_GGWAVE_SAMPLE_FORMAT_U16;
+    /// Signed 16-bit sample format
+    pub const I16: SampleFormat = ggwave_SampleFormat_GGWAVE_SAMPLE_FORMAT_I16;
+    /// 32-bit float sample format
+    pub const F32: SampleFormat = ggwave_SampleFormat_GGWAVE_SAMPLE_FORMAT_F32;
+
+    /// Names of the defined sample formats
+    pub(crate) const NAMES: [(SampleFormat, &str); 5] = [
+        (U8, "u8"),
+        (I8, "i8"),
+        (U16, "u16"),
+        (I16, "i16"),
+        (F32, "f32"),
+    ];
+
+    /// Size in bytes of one sample in a format
+    ///
+    /// Returns 0 for [`UNDEFINED`] and unknown values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::{GGWave, protocols, sample_formats};
+    ///
+    /// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+    /// let waveform = ggwave
+    ///     .encode("Hello, World!", protocols::AUDIBLE_FAST, 50)
+    ///     .expect("Failed to encode text");
+    ///
+    /// let size = sample_formats::bytes_per_sample(ggwave.parameters().sampleFormatOut);
+    /// println!("{} samples", waveform.len() / size);
+    /// ```
+    pub const fn bytes_per_sample(format: SampleFormat) -> usize {
+        match format {
+            U8 | I8 => 1,
+            U16 | I16 => 2,
+            F32 => 4,
+            _ => 0,
+        }
+    }
+
+    /// Name of a sample format, e.g. `"f32"`
+    ///
+    /// Returns `"undefined"` for [`UNDEFINED`] and unknown values.
+    pub fn name(format: SampleFormat) -> &'static str {
+        NAMES
+            .iter()
+            .find(|(candidate, _)| *candidate == format)
+            .map_or("undefined", |(_, name)| name)
+    }
+}
+
+/// Operating mode constants
+///
+/// This module provides constants for all the available operating modes.
+///
+/// The mode is a bitfield. `RX`, `TX` and `RX_AND_TX` choose the direction;
+/// `TX_ONLY_TONES` and `USE_DSS` are modifiers that are combined with a
+/// direction and mean nothing on their own.
+pub mod operating_modes {
+    use super::*;
+
+    /// Reception mode
+    pub const RX: i32 = GGWAVE_OPERATING_MODE_RX as i32;
+    /// Transmission mode
+    pub const TX: i32 = GGWAVE_OPERATING_MODE_TX as i32;
+    /// Reception and transmission mode
+    pub const RX_AND_TX: i32 = GGWAVE_OPERATING_MODE_RX_AND_TX as i32;
+    /// Transmission of tones only
+    pub const TX_ONLY_TONES: i32 = GGWAVE_OPERATING_MODE_TX_ONLY_TONES as i32;
+    /// Use DSS (Direct Sequence Spread)
+    ///
+    /// The spreading sequence is fixed, so DSS output is as reproducible as
+    /// plain output.
+    pub const USE_DSS: i32 = GGWAVE_OPERATING_MODE_USE_DSS as i32;
+
+    /// Names of the flags set in an operating mode, e.g. `"rx | tx"`
+    ///
+    /// Returns `"none"` if no known flag is set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::operating_modes;
+    ///
+    /// assert_eq!(operating_modes::describe(operating_modes::RX_AND_TX), "rx | tx");
+    /// assert_eq!(operating_modes::describe(operating_modes::TX | operating_modes::USE_DSS), "tx | use_dss");
+    /// ```
+    pub fn describe(mode: i32) -> String {
+        let flags = [
+            (RX, "rx"),
+            (TX, "tx"),
+            (TX_ONLY_TONES, "tx_only_tones"),
+            (USE_DSS, "use_dss"),
+        ];
+
+        let names: Vec<&str> = flags
+            .iter()
+            .filter(|(flag, _)| mode & flag != 0)
+            .map(|(_, name)| *name)
+            .collect();
+
+        if names.is_empty() {
+            "none".to_string()
+        } else {
+            names.join(" | ")
+        }
+    }
+
+    /// What an instance does, the typed form of the operating mode bits
+    ///
+    /// Converts into the `i32` stored in [`Parameters::operatingMode`], so it
+    /// can be passed wherever one of the constants above is accepted.
+    /// Modifiers that combine with any mode, such as DSS, are kept apart in
+    /// [`Flags`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ggwave_rs::operating_modes::{self, Flags, OperatingMode};
+    ///
+    /// assert_eq!(i32::from(OperatingMode::Rx), operating_modes::RX);
+    ///
+    /// let mode = OperatingMode::Tx | Flags::USE_DSS;
+    /// assert_eq!(OperatingMode::try_from(mode).unwrap(), OperatingMode::Tx);
+    /// assert_eq!(Flags::from(mode), Flags::USE_DSS);
+    /// ```
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum OperatingMode {
+        /// Receive only, see [`RX`]
+        Rx,
+        /// Transmit only, see [`TX`]
+        Tx,
+        /// Receive and transmit, see [`RX_AND_TX`]
+        RxAndTx,
+        /// Transmit the tones of a message only, see [`TX_ONLY_TONES`]
+        ///
+        /// This is [`TX`] together with [`TX_ONLY_TONES`], since ggwave does
+        /// not enable transmission for the tone flag alone.
+        TxOnlyTones,
+    }
+
+    impl From<OperatingMode> for i32 {
+        fn from(mode: OperatingMode) -> Self {
+            match mode {
+                OperatingMode::Rx => RX,
+                OperatingMode::Tx => TX,
+                OperatingMode::RxAndTx => RX_AND_TX,
+                OperatingMode::TxOnlyTones => TX | TX_ONLY_TONES,
+            }
+        }
+    }
+
+    impl TryFrom<i32> for OperatingMode {
+        type Error = Error;
+
+        /// Read the mode from operating mode bits, ignoring [`Flags`]
+        ///
+        /// Returns `Error::InvalidParameter` for combinations with no variant,
+        /// such as no direction at all.
+        fn try_from(mode: i32) -> Result<Self> {
+            match mode & !Flags::ALL.0 {
+                RX => Ok(OperatingMode::Rx),
+                TX => Ok(OperatingMode::Tx),
+                RX_AND_TX => Ok(OperatingMode::RxAndTx),
+                m if m == TX | TX_ONLY_TONES => Ok(OperatingMode::TxOnlyTones),
+                _ => Err(Error::InvalidParameter("Unknown operating mode")),
+            }
+        }
+    }
+
+    impl core::ops::BitOr<Flags> for OperatingMode {
+        type Output = i32;
+
+        fn bitor(self, flags: Flags) -> i32 {
+            i32::from(self) | flags.0
+        }
+    }
+
+    /// Modifier flags that combine with any [`OperatingMode`]
+    ///
+    /// Converting an operating mode `i32` into `Flags` keeps only the
+    /// modifier bits.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+    pub struct Flags(i32);
+
+    impl Flags {
+        /// No modifier
+        pub const NONE: Flags = Flags(0);
+        /// Direct Sequence Spread, see [`USE_DSS`]
+        pub const USE_DSS: Flags = Flags(USE_DSS);
+        /// Every modifier this type knows about
+        pub const ALL: Flags = Flags(USE_DSS);
+
+        /// Check whether every flag in `other` is set
+        pub const fn contains(self, other: Flags) -> bool {
+            self.0 & other.0 == other.0
+        }
+    }
+
+    impl core::ops::BitOr for Flags {
+        type Output = Flags;
+
+        fn bitor(self, other: Flags) -> Flags {
+            Flags(self.0 | other.0)
+        }
+    }
+
+    impl From<Flags> for i32 {
+        fn from(flags: Flags) -> Self {
+            flags.0
+        }
+    }
+
+    impl From<i32> for Flags {
+        fn from(mode: i32) -> Self {
+            Flags(mode & Flags::ALL.0)
+        }
+    }
+}
+
+/// Filter type constants
+///
+/// This module provides constants for all the available filter types.
+pub mod filters {
+    use super::*;
+
+    /// Hann window filter
+    pub const HANN: Filter = ggwave_Filter_GGWAVE_FILTER_HANN;
+    /// Hamming window filter
+    pub const HAMMING: Filter = ggwave_Filter_GGWAVE_FILTER_HAMMING;
+    /// First order high-pass filter
+    pub const FIRST_ORDER_HIGH_PASS: Filter = ggwave_Filter_GGWAVE_FILTER_FIRST_ORDER_HIGH_PASS;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Mutex, MutexGuard};
+
+    use super::*;
+
+    /// Serialize tests that create instances so they stay below `GGWAVE_MAX_INSTANCES`
+    pub(crate) fn serial() -> MutexGuard<'static, ()> {
+        static LOCK: Mutex<()> = Mutex::new(());
+        LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    #[test]
+    fn test_initialization() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        drop(ggwave);
+    }
+
+    #[test]
+    fn test_live_instance_count() {
+        let _serial = serial();
+        const { assert!(MAX_INSTANCES >= 1) };
+        let before = GGWave::live_instance_count();
+
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let clone = ggwave.try_clone().expect("Failed to clone GGWave");
+        assert_eq!(GGWave::live_instance_count(), before + 2);
+
+        drop(ggwave);
+        assert_eq!(GGWave::live_instance_count(), before + 1);
+        drop(clone);
+        assert_eq!(GGWave::live_instance_count(), before);
+    }
+
+    #[test]
+    fn test_instance_limit_reached() {
+        let _serial = serial();
+        let mut instances: Vec<GGWave> = (GGWave::live_instance_count()..MAX_INSTANCES)
+            .map(|_| GGWave::new().expect("Failed to initialize GGWave"))
+            .collect();
+        assert_eq!(GGWave::live_instance_count(), MAX_INSTANCES);
+
+        let is_limit = |result: Result<GGWave>| {
+            matches!(
+                result,
+                Err(Error::InstanceLimitReached { max: MAX_INSTANCES })
+            )
+        };
+        assert!(is_limit(GGWave::new()));
+        assert!(is_limit(GGWave::builder().build()));
+        if let Some(ggwave) = instances.first() {
+            assert!(is_limit(ggwave.try_clone()));
+        }
+        // Bad parameters are still reported as such
+        assert!(matches!(
+            GGWave::builder().samples_per_frame(0).build(),
+            Err(Error::InvalidParameter(_))
+        ));
+        assert_eq!(GGWave::live_instance_count(), MAX_INSTANCES);
+
+        // Dropping an instance frees its slot
+        instances.pop();
+        assert!(GGWave::new().is_ok());
+    }
+
+    #[test]
+    fn test_instance_validity() {
+        let _serial = serial();
+        unsafe {
+            assert!(ffi::helpers::is_valid_instance(0));
+            assert!(ffi::helpers::is_valid_instance(
+                GGWAVE_MAX_INSTANCES as i32 + 1
+            ));
+            assert!(!ffi::helpers::is_valid_instance(-1));
+        }
+
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        assert!(ggwave.raw_instance() >= 0);
+    }
+
+    #[test]
+    fn test_try_from_raw_instance() {
+        let _serial = serial();
+        assert!(matches!(
+            unsafe { GGWave::try_from_raw_instance(-1) },
+            Err(Error::InvalidParameter(_))
+        ));
+
+        let instance = unsafe { ffi::helpers::init_default() };
+        let ggwave = unsafe { GGWave::try_from_raw_instance(instance) }
+            .expect("Failed to wrap a valid instance");
+        assert_eq!(ggwave.raw_instance(), instance);
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let text = "Hello, GGWave!";
+
+        let waveform = ggwave
+            .encode(text, protocols::AUDIBLE_NORMAL, 50)
+            .expect("Failed to encode text");
+
+        let mut buffer = vec![0u8; 1024];
+        let decoded = ggwave
+            .decode(&waveform, &mut buffer)
+            .expect("Failed to decode waveform");
+
+        assert_eq!(decoded, text);
+    }
+
+    /// Runs with and without the `std` feature, so `cargo test
+    /// --no-default-features` checks that the `alloc`-only API keeps working
+    #[test]
+    fn test_alloc_only_api() {
+        let _serial = serial();
+        let ggwave = GGWave::builder()
+            .input_sample_format(sample_formats::F32)
+            .output_sample_format(sample_formats::F32)
+            .build()
+            .expect("Failed to initialize GGWave");
+        let mut buffer = vec![0u8; 1024];
+
+        let waveform = ggwave
+            .encode("No std", protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text");
+        assert_eq!(ggwave.decode(&waveform, &mut buffer).unwrap(), "No std");
+
+        let waveform = ggwave
+            .encode_checked(&[0xFF, 0x00], protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode data");
+        assert_eq!(
+            ggwave.decode_checked(&waveform, &mut buffer).unwrap(),
+            [0xFF, 0x00]
+        );
+
+        ggwave.toggle_tx_protocol(protocols::DT_FAST, false);
+        ggwave.toggle_tx_protocol(protocols::DT_FAST, true);
+        assert_eq!(protocol_mask::global_tx(), ProtocolMask::BUILT_IN);
+    }
+
+    #[test]
+    fn test_describe_parameters() {
+        let mut params = Parameters::receiver_default();
+        params.payloadLength = 16;
+        params.operatingMode = operating_modes::RX;
+
+        let description = describe_parameters(&params);
+        assert!(description.contains("sample_rate:            48000 Hz"));
+        assert!(description.contains("input_sample_format:    f32"));
+        assert!(description.contains("output_sample_format:   i16"));
+        assert!(description.contains("payload_length:         16 bytes"));
+        assert!(description.contains("operating_mode:         rx"));
+        assert_eq!(description.lines().count(), 9);
+
+        assert_eq!(sample_formats::name(sample_formats::UNDEFINED), "undefined");
+        assert_eq!(operating_modes::describe(0), "none");
+    }
+
+    #[test]
+    fn test_operating_mode_round_trip() {
+        use operating_modes::{Flags, OperatingMode};
+
+        let modes = [
+            OperatingMode::Rx,
+            OperatingMode::Tx,
+            OperatingMode::RxAndTx,
+            OperatingMode::TxOnlyTones,
+        ];
+        for mode in modes {
+            let bits = i32::from(mode);
+            assert_eq!(OperatingMode::try_from(bits).unwrap(), mode);
+            assert_eq!(Flags::from(bits), Flags::NONE);
+
+            let with_dss = mode | Flags::USE_DSS;
+            assert_eq!(OperatingMode::try_from(with_dss).unwrap(), mode);
+            assert!(Flags::from(with_dss).contains(Flags::USE_DSS));
+        }
+
+        assert_eq!(
+            i32::from(OperatingMode::RxAndTx),
+            operating_modes::RX_AND_TX
+        );
+        assert_eq!(i32::from(Flags::USE_DSS), operating_modes::USE_DSS);
+        assert!(OperatingMode::try_from(0).is_err());
+
+        let params = GGWaveBuilder::new()
+            .operating_mode(OperatingMode::Tx | Flags::USE_DSS)
+            .into_parameters();
+        assert_eq!(
+            params.operatingMode,
+            operating_modes::TX | operating_modes::USE_DSS
+        );
+    }
+
+    #[test]
+    fn test_bytes_per_sample() {
+        let sizes = [
+            (sample_formats::UNDEFINED, 0),
+            (sample_formats::U8, 1),
+            (sample_formats::I8, 1),
+            (sample_formats::U16, 2),
+            (sample_formats::I16, 2),
+            (sample_formats::F32, 4),
+            (sample_formats::F32 + 1, 0),
+        ];
+        for (format, size) in sizes {
+            assert_eq!(
+                sample_formats::bytes_per_sample(format),
+                size,
+                "{}",
+                sample_formats::name(format)
+            );
+            assert_eq!(
+                convert::sample_size(format).ok(),
+                (size > 0).then_some(size)
+            );
+        }
+    }
+
+    #[test]
+    fn test_builder() {
+        let _serial = serial();
+        let ggwave = GGWave::builder()
+            .sample_rate(48000.0)
+            .output_sample_format(sample_formats::F32)
+            .build()
+            .expect("Failed to initialize GGWave with builder");
+
+        let format = ggwave.get_output_sample_format();
+        assert_eq!(format, sample_formats::F32);
+    }
+
+    #[test]
+    fn test_clone() {
+        let _serial = serial();
+        let original = GGWave::builder()
+            .tx_protocols(ProtocolMask::NONE.with(protocols::AUDIBLE_FAST))
+            .build()
+            .expect("Failed to initialize GGWave");
+        let clone = original.clone();
+        assert_ne!(clone.raw_instance(), original.raw_instance());
+        assert_eq!(clone.tx_protocols(), original.tx_protocols());
+
+        let mut buffer = vec![0u8; 1024];
+        let from_original = original
+            .encode("From the original", protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode with the original");
+        let from_clone = clone
+            .encode("From the clone", protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode with the clone");
+        assert_eq!(
+            clone.decode(&from_original, &mut buffer).unwrap(),
+            "From the original"
+        );
+        assert_eq!(
+            original.decode(&from_clone, &mut buffer).unwrap(),
+            "From the clone"
+        );
+
+        // Dropping one leaves the other usable
+        drop(original);
+        assert!(
+            clone
+                .encode("Still here", protocols::AUDIBLE_FAST, 50)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_fixed_length_has_no_markers() {
+        let _serial = serial();
+        let text = "Short";
+        let variable = GGWave::new().expect("Failed to initialize GGWave");
+        let fixed = GGWave::builder()
+            .fixed_payload_length(text.len() as i32)
+            .build()
+            .expect("Failed to initialize fixed-length GGWave");
+
+        let long = variable
+            .encode(text, protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode variable-length");
+        let short = fixed
+            .encode(text, protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode fixed-length");
+
+        let frame_bytes = fixed.parameters().samplesPerFrame as usize
+            * convert::sample_size(fixed.parameters().sampleFormatOut).unwrap();
+        let markers = 2 * constants::DEFAULT_MARKER_FRAMES * frame_bytes;
+        assert!(short.len() + markers <= long.len());
+
+        let mut buffer = vec![0u8; 1024];
+        assert_eq!(fixed.decode(&short, &mut buffer).unwrap(), text);
+    }
+
+    #[test]
+    fn test_builder_mode_flags() {
+        let _serial = serial();
+        let ggwave = GGWave::builder()
+            .operating_mode(operating_modes::RX_AND_TX)
+            .use_dss(true)
+            .build()
+            .expect("Failed to initialize GGWave");
+        let mode = ggwave.parameters().operatingMode;
+        assert_eq!(mode, operating_modes::RX_AND_TX | operating_modes::USE_DSS);
+        drop(ggwave);
+
+        let params = GGWave::builder()
+            .operating_mode(operating_modes::TX)
+            .tx_only_tones(true)
+            .use_dss(true)
+            .use_dss(false)
+            .params;
+        assert_eq!(
+            params.operatingMode,
+            operating_modes::TX | operating_modes::TX_ONLY_TONES
+        );
+
+        let params = GGWave::builder()
+            .with_mode_flags(operating_modes::Flags::USE_DSS)
+            .base_mode(operating_modes::OperatingMode::Tx)
+            .params;
+        assert_eq!(
+            params.operatingMode,
+            operating_modes::TX | operating_modes::USE_DSS
+        );
+    }
+
+    #[test]
+    fn test_dss_is_deterministic() {
+        let _serial = serial();
+        let text = "Spread";
+        let plain = GGWave::new()
+            .expect("Failed to initialize GGWave")
+            .encode(text, protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text");
+
+        let ggwave = GGWave::builder()
+            .use_dss(true)
+            .build()
+            .expect("Failed to initialize GGWave");
+        let first = ggwave
+            .encode(text, protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text");
+        let second = ggwave
+            .encode(text, protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text");
+        assert_eq!(first, second);
+        assert_ne!(first, plain);
+
+        // A separate instance spreads with the same sequence
+        let other = ggwave.try_clone().expect("Failed to clone GGWave");
+        assert_eq!(
+            other.encode(text, protocols::AUDIBLE_FAST, 50).unwrap(),
+            first
+        );
+
+        let mut buffer = vec![0u8; 1024];
+        assert_eq!(other.decode(&first, &mut buffer).unwrap(), text);
+    }
+
+    #[test]
+    fn test_rx_preset() {
+        let _serial = serial();
+        let ggwave = GGWaveBuilder::rx_preset()
+            .build()
+            .expect("Failed to initialize GGWave with rx preset");
+
+        let params = ggwave.parameters();
+        assert_eq!(params.sampleRateInp, 48000.0);
+        assert_eq!(params.samplesPerFrame, 1024);
+        assert_eq!(params.soundMarkerThreshold, 0.5);
+        assert_eq!(params.sampleFormatInp, sample_formats::F32);
+        assert_eq!(params.sampleFormatOut, sample_formats::I16);
+        assert_eq!(params.operatingMode, operating_modes::RX_AND_TX);
+
+        // Output is I16 but captures are F32, as with a real microphone
+        let text = "Preset";
+        let waveform = ggwave
+            .encode_as_format(text, protocols::AUDIBLE_FAST, 50, sample_formats::F32)
+            .expect("Failed to encode text");
+        let mut buffer = vec![0u8; 1024];
+        assert_eq!(ggwave.decode(&waveform, &mut buffer).unwrap(), text);
+    }
+
+    #[test]
+    fn test_decode_uses_input_format() {
+        let _serial = serial();
+        let ggwave = GGWave::builder()
+            .input_sample_format(sample_formats::F32)
+            .output_sample_format(sample_formats::I16)
+            .build()
+            .expect("Failed to initialize GGWave");
+        let text = "Input format";
+        let mut buffer = vec![0u8; 1024];
+
+        // Output audio has to be converted to the input format first
+        let waveform = ggwave
+            .encode(text, protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text");
+        let captured =
+            convert::convert_samples(&waveform, sample_formats::I16, sample_formats::F32).unwrap();
+        assert_eq!(ggwave.decode(&captured, &mut buffer).unwrap(), text);
+        assert_eq!(
+            ggwave
+                .process_audio_chunk_all(&captured, &mut buffer)
+                .unwrap(),
+            [text]
+        );
+
+        // Misaligned for F32 input, though whole I16 samples
+        let misaligned = &waveform[..waveform.len() / 4 * 4 - 2];
+        assert!(matches!(
+            ggwave.decode(misaligned, &mut buffer),
+            Err(Error::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            ggwave.decode_binary(misaligned, &mut buffer),
+            Err(Error::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            ggwave.process_audio_chunk(&captured[..6], &mut buffer),
+            Err(Error::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            ggwave.process_audio_chunk_all(&captured[..6], &mut buffer),
+            Err(Error::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn test_encode_argument_validation() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let text = "Volume";
+
+        for volume in [-50, 101] {
+            assert!(matches!(
+                ggwave.encode(text, protocols::AUDIBLE_FAST, volume),
+                Err(Error::InvalidParameter(_))
+            ));
+            assert!(matches!(
+                ggwave.calculate_encode_buffer_size(text, protocols::AUDIBLE_FAST, volume),
+                Err(Error::InvalidParameter(_))
+            ));
+            assert!(matches!(
+                ggwave.encode_into_buffer(text, protocols::AUDIBLE_FAST, volume, &mut [0u8; 16]),
+                Err(Error::InvalidParameter(_))
+            ));
+        }
+
+        assert!(ggwave.encode(text, protocols::AUDIBLE_FAST, 0).is_ok());
+        assert!(matches!(
+            ggwave.encode("", protocols::AUDIBLE_FAST, 50),
+            Err(Error::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_audio_config() {
+        let _serial = serial();
+        assert!(matches!(
+            GGWave::from_audio_config(48000.0, 2, sample_formats::F32, operating_modes::RX),
+            Err(Error::InvalidParameter(_))
+        ));
+
+        let ggwave =
+            GGWave::from_audio_config(44100.0, 1, sample_formats::I16, operating_modes::RX_AND_TX)
+                .expect("Failed to initialize GGWave from audio config");
+        let params = ggwave.parameters();
+        assert_eq!(params.sampleRateInp, 44100.0);
+        assert_eq!(params.sampleRateOut, 44100.0);
+        assert_eq!(params.sampleFormatInp, sample_formats::I16);
+        assert_eq!(params.sampleFormatOut, sample_formats::I16);
+
+        // Output in the stream's format decodes without conversion
+        let text = "Matched";
+        let waveform = ggwave
+            .encode(text, protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text");
+        let mut buffer = vec![0u8; 1024];
+        assert_eq!(ggwave.decode(&waveform, &mut buffer).unwrap(), text);
+    }
+
+    #[test]
+    fn test_invalid_frame_size() {
+        let _serial = serial();
+        let mut params = GGWave::default_parameters();
+        params.sampleRate = 8000.0;
+        params.sampleRateInp = 8000.0;
+        params.sampleRateOut = 8000.0;
+        params.samplesPerFrame = 1;
+
+        match GGWave::new_with_params(params) {
+            Err(Error::InvalidParameter(message)) => {
+                assert!(message.contains("Samples per frame"), "{}", message)
+            }
+            other => panic!("Expected InvalidParameter, got {:?}", other.err()),
+        }
+
+        params.samplesPerFrame = 2048;
+        assert!(matches!(
+            GGWave::new_with_params(params),
+            Err(Error::InvalidParameter(
+                "Samples per frame must be between 1 and 1024"
+            ))
+        ));
+
+        // Ultrasound tones need 832 samples per frame, audible ones 272
+        params.samplesPerFrame = 512;
+        let ultrasound = GGWave::builder()
+            .samples_per_frame(512)
+            .rx_protocols(ProtocolMask::NONE.with(protocols::ULTRASOUND_FAST))
+            .tx_protocols(ProtocolMask::NONE.with(protocols::ULTRASOUND_FAST))
+            .build();
+        assert!(matches!(ultrasound, Err(Error::InvalidParameter(_))));
+        assert!(GGWave::new_with_params(params).is_ok());
+
+        params.sampleRate = 200000.0;
+        assert!(matches!(
+            GGWave::new_with_params(params),
+            Err(Error::InvalidParameter(
+                "Sample rates must be between 1000 and 96000 Hz"
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_builder_sample_rates() {
+        let _serial = serial();
+        assert!(matches!(
+            GGWave::builder().output_sample_rate(0.0).build(),
+            Err(Error::InvalidParameter(_))
+        ));
+
+        let text = "Rates";
+        let native = GGWave::builder()
+            .sample_rate(48000.0)
+            .build()
+            .expect("Failed to initialize GGWave");
+        let resampled = GGWave::builder()
+            .sample_rate(48000.0)
+            .output_sample_rate(44100.0)
+            .build()
+            .expect("Failed to initialize GGWave");
+
+        assert_eq!(resampled.parameters().sampleRate, 48000.0);
+        assert_eq!(resampled.parameters().sampleRateOut, 44100.0);
+
+        // Only the output rate changed, so the transmission lasts as long
+        let native_duration = native.estimate_duration(protocols::AUDIBLE_FAST, text.len());
+        let resampled_duration = resampled.estimate_duration(protocols::AUDIBLE_FAST, text.len());
+        let frame_duration = 1024.0 / 44100.0;
+        assert!((native_duration - resampled_duration).abs() < frame_duration);
+    }
+
+    #[test]
+    fn test_encode_at_rate() {
+        let _serial = serial();
+        let text = "Per-call rate";
+        let ggwave = GGWave::builder()
+            .sample_rate(48000.0)
+            .build()
+            .expect("Failed to initialize GGWave");
+        let live = GGWave::live_instance_count();
+
+        let native = ggwave
+            .encode_at_rate(text, protocols::AUDIBLE_FAST, 50, 48000.0)
+            .expect("Failed to encode at 48000 Hz");
+        let cd = ggwave
+            .encode_at_rate(text, protocols::AUDIBLE_FAST, 50, 44100.0)
+            .expect("Failed to encode at 44100 Hz");
+        assert_eq!(GGWave::live_instance_count(), live);
+        assert_eq!(ggwave.parameters().sampleRateOut, 48000.0);
+        assert_eq!(
+            native,
+            ggwave
+                .encode_full(text, protocols::AUDIBLE_FAST, 50)
+                .unwrap()
+        );
+
+        // Same duration, so the sample counts follow the rates
+        assert_eq!(native.sample_rate, 48000.0);
+        assert_eq!(cd.sample_rate, 44100.0);
+        assert_eq!(cd.format, native.format);
+        let expected = native.sample_count as f32 * 44100.0 / 48000.0;
+        assert!(
+            (cd.sample_count as f32 - expected).abs() < 1024.0,
+            "{} samples, expected about {}",
+            cd.sample_count,
+            expected
+        );
+
+        let receiver = GGWave::builder()
+            .sample_rate(48000.0)
+            .input_sample_rate(44100.0)
+            .input_sample_format(cd.format)
+            .build()
+            .expect("Failed to initialize GGWave");
+        let mut buffer = vec![0u8; 1024];
+        assert_eq!(receiver.decode(&cd.bytes, &mut buffer).unwrap(), text);
+
+        assert!(matches!(
+            ggwave.encode_at_rate(text, protocols::AUDIBLE_FAST, 50, 0.0),
+            Err(Error::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn test_builder_into_parameters() {
+        let _serial = serial();
+        let builder = GGWave::builder()
+            .sample_rate(44100.0)
+            .samples_per_frame(512)
+            .sound_marker_threshold(4.5)
+            .operating_mode(operating_modes::RX);
+        assert_eq!(builder.parameters().sampleRateOut, 44100.0);
+        assert_eq!(builder.parameters().samplesPerFrame, 512);
+
+        let params = builder.into_parameters();
+        let first = GGWave::new_with_params(params).expect("Failed to initialize GGWave");
+        let second = GGWave::new_with_params(params).expect("Failed to initialize GGWave");
+
+        for ggwave in [&first, &second] {
+            let stored = ggwave.parameters();
+            assert_eq!(stored.sampleRate, 44100.0);
+            assert_eq!(stored.sampleRateInp, 44100.0);
+            assert_eq!(stored.samplesPerFrame, 512);
+            assert_eq!(stored.soundMarkerThreshold, 4.5);
+            assert_eq!(stored.operatingMode, operating_modes::RX);
+        }
+        assert_eq!(
+            describe_parameters(first.parameters()),
+            describe_parameters(second.parameters())
+        );
+    }
+
+    #[test]
+    fn test_max_payload_len() {
+        let _serial = serial();
+        let variable = GGWave::new().expect("Failed to initialize GGWave");
+        assert_eq!(variable.max_payload_len(), constants::MAX_LENGTH_VARIABLE);
+        assert!(variable.can_encode(&"a".repeat(constants::MAX_LENGTH_VARIABLE)));
+        assert!(!variable.can_encode(&"a".repeat(constants::MAX_LENGTH_VARIABLE + 1)));
+        assert!(!variable.can_encode(""));
+
+        let fixed = GGWave::new_with_fixed_payload(8, operating_modes::RX_AND_TX)
+            .expect("Failed to initialize fixed-length GGWave");
+        assert_eq!(fixed.max_payload_len(), 8);
+        assert!(fixed.can_encode("12345678"));
+        assert!(matches!(
+            fixed.encode("123456789", protocols::AUDIBLE_FAST, 50),
+            Err(Error::TextTooLong { length: 9, max: 8 })
+        ));
+    }
+
+    #[test]
+    fn test_fixed_payload_too_long() {
+        let _serial = serial();
+        let fixed = GGWave::builder()
+            .fixed_payload_length(32)
+            .build()
+            .expect("Failed to initialize fixed-length GGWave");
+        let text = "a".repeat(40);
+
+        assert!(matches!(
+            fixed.calculate_encode_buffer_size(&text, protocols::AUDIBLE_FAST, 50),
+            Err(Error::TextTooLong {
+                length: 40,
+                max: 32
+            })
+        ));
+        let mut buffer = vec![0u8; 1 << 20];
+        assert!(matches!(
+            fixed.encode_into_buffer(&text, protocols::AUDIBLE_FAST, 50, &mut buffer),
+            Err(Error::TextTooLong {
+                length: 40,
+                max: 32
+            })
+        ));
+        assert!(
+            fixed
+                .calculate_encode_buffer_size(&text[..32], protocols::AUDIBLE_FAST, 50)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_encode_into_buffer() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let text = "Test buffer encode";
+
+        let size = ggwave
+            .calculate_encode_buffer_size(text, protocols::AUDIBLE_NORMAL, 50)
+            .expect("Failed to calculate buffer size");
+
+        let mut buffer = vec![0u8; size];
+        let written = ggwave
+            .encode_into_buffer(text, protocols::AUDIBLE_NORMAL, 50, &mut buffer)
+            .expect("Failed to encode text into buffer");
+
+        assert!(written > 0);
+        assert!(written <= buffer.len());
+    }
+
+    #[test]
+    fn test_decode_binary() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let data = [1u8, 2, 3, 4, 5];
+
+        // First encode the binary data as a string
+        let encoded = ggwave
+            .encode(
+                &String::from_utf8_lossy(&data),
+                protocols::AUDIBLE_FASTEST,
+                50,
+            )
+            .expect("Failed to encode binary data");
+
+        // Then decode back to binary
+        let mut buffer = vec![0u8; 1024];
+        let decoded = ggwave
+            .decode_binary(&encoded, &mut buffer)
+            .expect("Failed to decode binary data");
+
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn test_decode_buffer_too_small() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let text = "Does not fit";
+        let waveform = ggwave
+            .encode(text, protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text");
+
+        let mut small = [0u8; 2];
+        assert!(matches!(
+            ggwave.decode(&waveform, &mut small),
+            Err(Error::BufferTooSmall {
+                required: 12,
+                provided: 2
+            })
+        ));
+        assert!(matches!(
+            ggwave.decode_binary(&waveform, &mut small),
+            Err(Error::BufferTooSmall {
+                required: 12,
+                provided: 2
+            })
+        ));
+
+        // An exact fit is enough
+        let mut exact = vec![0u8; text.len()];
+        assert_eq!(ggwave.decode(&waveform, &mut exact).unwrap(), text);
+    }
+
+    #[test]
+    fn test_decode_owned() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let mut buffer = vec![0u8; 1024];
+        let address = buffer.as_ptr();
+
+        for text in ["First", "Second", "Third"] {
+            let waveform = ggwave
+                .encode(text, protocols::AUDIBLE_FAST, 50)
+                .expect("Failed to encode text");
+            let (decoded, returned) = ggwave
+                .decode_owned(&waveform, buffer)
+                .expect("Failed to decode waveform");
+            assert_eq!(decoded.as_deref(), Some(text));
+
+            // The same allocation comes back every time
+            assert_eq!(returned.as_ptr(), address);
+            assert_eq!(returned.len(), 1024);
+            buffer = returned;
+        }
+
+        let silence = vec![0u8; 4096];
+        let (decoded, returned) = ggwave.decode_owned(&silence, buffer).unwrap();
+        assert_eq!(decoded, None);
+        assert_eq!(returned.as_ptr(), address);
+    }
+
+    #[test]
+    fn test_decode_binary_owned() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        // Not valid UTF-8
+        let data = [0xffu8, 0x00, 0x80, 0x42, 0xfe];
+
+        let encoded = ggwave
+            .encode_payload(&data, protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode binary data");
+        let decoded = ggwave
+            .decode_binary_owned(&encoded, 1024)
+            .expect("Failed to decode binary data");
+
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn test_encode_as_format() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        assert_eq!(ggwave.parameters().sampleFormatOut, sample_formats::F32);
+        let text = "Format override";
+
+        let native = ggwave
+            .encode(text, protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text");
+        let converted = ggwave
+            .encode_as_format(text, protocols::AUDIBLE_FAST, 50, sample_formats::I16)
+            .expect("Failed to encode text as I16");
+
+        assert_eq!(converted.len(), native.len() / 2);
+
+        let first = f32::from_ne_bytes(native[..4].try_into().unwrap());
+        let first_i16 = i16::from_ne_bytes(converted[..2].try_into().unwrap());
+        assert_eq!(first_i16, (first.clamp(-1.0, 1.0) * 32767.0) as i16);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_raw_to_wav_formats() {
+        let _serial = serial();
+        let cases: [(SampleFormat, Vec<u8>, u16, [i32; 3]); 4] = [
+            (sample_formats::U8, vec![0, 128, 255], 8, [-128, 0, 127]),
+            (sample_formats::I8, vec![0x80, 0, 0x7f], 8, [-128, 0, 127]),
+            (
+                sample_formats::U16,
+                [0u16, 0x8000, 0xffff]
+                    .iter()
+                    .flat_map(|s| s.to_ne_bytes())
+                    .collect(),
+                16,
+                [-32768, 0, 32767],
+            ),
+            (
+                sample_formats::I16,
+                [i16::MIN, 0, i16::MAX]
+                    .iter()
+                    .flat_map(|s| s.to_ne_bytes())
+                    .collect(),
+                16,
+                [-32768, 0, 32767],
+            ),
+        ];
+
+        for (format, raw, bits, expected) in cases {
+            let ggwave = GGWave::builder()
+                .output_sample_rate(44100.0)
+                .output_sample_format(format)
+                .build()
+                .expect("Failed to initialize GGWave");
+
+            let wav = ggwave.raw_to_wav(&raw).expect("Failed to convert to WAV");
+            let mut reader = WavReader::new(Cursor::new(wav)).expect("Failed to reopen WAV");
+            let spec = reader.spec();
+            assert_eq!(spec.bits_per_sample, bits, "format {}", format);
+            assert_eq!(spec.sample_rate, 44100);
+            assert_eq!(spec.sample_format, hound::SampleFormat::Int);
+
+            let samples: Vec<i32> = reader.samples::<i32>().map(|s| s.unwrap()).collect();
+            assert_eq!(samples, expected, "format {}", format);
+        }
+    }
+
+    #[test]
+    fn test_misaligned_waveform() {
+        let _serial = serial();
+        let ggwave = GGWave::builder()
+            .output_sample_format(sample_formats::F32)
+            .build()
+            .expect("Failed to initialize GGWave");
+        let waveform = ggwave
+            .encode("Aligned", protocols::AUDIBLE_FAST, 50)
+            .unwrap();
+        // Cut the last sample in half
+        let misaligned = &waveform[..waveform.len() - 2];
+
+        let is_misaligned = |result: Result<_>| {
+            matches!(
+                result,
+                Err(Error::InvalidParameter(
+                    "waveform length not aligned to sample size"
+                ))
+            )
+        };
+        #[cfg(feature = "std")]
+        assert!(is_misaligned(ggwave.raw_to_wav(misaligned).map(drop)));
+        assert!(is_misaligned(
+            convert::bytes_to_f32(misaligned, sample_formats::F32).map(drop)
+        ));
+        assert!(is_misaligned(
+            convert::convert_samples(misaligned, sample_formats::F32, sample_formats::I16)
+                .map(drop)
+        ));
+        assert!(is_misaligned(
+            convert::convert_samples(misaligned, sample_formats::F32, sample_formats::F32)
+                .map(drop)
+        ));
+
+        let audio = EncodedAudio {
+            bytes: misaligned.to_vec(),
+            ..ggwave
+                .encode_full("Aligned", protocols::AUDIBLE_FAST, 50)
+                .unwrap()
+        };
+        assert!(is_misaligned(audio.as_f32().map(drop)));
+        assert!(is_misaligned(audio.as_i16().map(drop)));
+        assert!(is_misaligned(audio.chunks_f32(1024).map(drop)));
+
+        // Whole samples still convert
+        #[cfg(feature = "std")]
+        assert!(ggwave.raw_to_wav(&waveform).is_ok());
+        assert_eq!(
+            convert::bytes_to_f32(&waveform, sample_formats::F32)
+                .unwrap()
+                .len(),
+            waveform.len() / 4
+        );
+    }
+
+    #[test]
+    fn test_rx_duration() {
+        let _serial = serial();
+        let ggwave = GGWaveBuilder::rx_preset()
+            .build()
+            .expect("Failed to initialize GGWave");
+
+        // Nothing heard yet
+        assert_eq!(ggwave.rx_duration_frames(), 0);
+        assert_eq!(ggwave.rx_duration_samples(), 0);
+
+        let max_frames = ggwave.max_rx_duration_frames();
+        assert!(max_frames > 0);
+
+        let waveform = ggwave
+            .encode("Duration", protocols::AUDIBLE_FAST, 50)
+            .unwrap();
+        let input = convert::convert_samples(
+            &waveform,
+            ggwave.parameters().sampleFormatOut,
+            ggwave.parameters().sampleFormatInp,
+        )
+        .unwrap();
+        let mut buffer = vec![0u8; 1024];
+        ggwave.process_audio_chunk_all(&input, &mut buffer).unwrap();
+
+        let frames = ggwave.rx_duration_frames();
+        let samples = ggwave.rx_duration_samples();
+        assert!(frames > 0);
+        assert!(frames as usize <= max_frames);
+        assert_eq!(samples, frames * 1024);
+        assert_eq!(ggwave.rx_duration_secs(), samples as f32 / 48000.0);
+
+        let transmitter = GGWaveBuilder::new()
+            .operating_mode(operating_modes::TX)
+            .build()
+            .unwrap();
+        assert_eq!(transmitter.max_rx_duration_frames(), 0);
+    }
+
+    #[test]
+    fn test_reset_rx_state() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let mut buffer = vec![0u8; 1024];
+        let sample_size = sample_formats::bytes_per_sample(ggwave.parameters().sampleFormatInp);
+        let frame_bytes = ggwave.parameters().samplesPerFrame as usize * sample_size;
+        let silence = vec![0u8; ggwave.max_rx_duration_frames() * frame_bytes];
+
+        let first = ggwave
+            .encode("Session A", protocols::AUDIBLE_FAST, 50)
+            .unwrap();
+        let second = ggwave
+            .encode("Session B", protocols::AUDIBLE_FAST, 50)
+            .unwrap();
+        assert_eq!(
+            ggwave.process_audio_chunk_all(&first, &mut buffer).unwrap(),
+            ["Session A"]
+        );
+
+        // A session that ends part way through a message
+        let cut = second.len() / 2 / frame_bytes * frame_bytes;
+        ggwave
+            .process_audio_chunk_all(&second[..cut], &mut buffer)
+            .unwrap();
+        ggwave.reset_rx_state().expect("Failed to reset decoder");
+
+        assert!(
+            ggwave
+                .process_audio_chunk_all(&silence, &mut buffer)
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            ggwave
+                .process_audio_chunk_all(&second, &mut buffer)
+                .unwrap(),
+            ["Session B"]
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_debug_mode_closes_log_files() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let path = dir.path().join("ggwave.log");
+        let path = path.to_str().expect("Temp path is not UTF-8");
+
+        // More files than the usual limit of 1024 open descriptors
+        for _ in 0..2048 {
+            ggwave.set_debug_mode(Some(path));
+        }
+        ggwave.set_debug_mode(Some("nul\0byte.log"));
+        ggwave.set_debug_mode(None);
+
+        std::fs::File::open(path).expect("Ran out of file descriptors");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_log_callback() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let lines = std::sync::Arc::new(Mutex::new(Vec::new()));
+
+        let sink = lines.clone();
+        ggwave
+            .set_log_callback(move |line| sink.lock().unwrap().push(line.to_string()))
+            .expect("Failed to set log callback");
+
+        // The safe API rejects the volume before the C library would log it
+        let payload = b"Log";
+        let result = unsafe {
+            ggwave_encode(
+                ggwave.raw_instance(),
+                payload.as_ptr() as *const c_void,
+                payload.len() as i32,
+                protocols::AUDIBLE_FAST,
+                1000,
+                ptr::null_mut(),
+                1,
+            )
+        };
+        ggwave.set_debug_mode(None);
+
+        assert!(result < 0);
+        let lines = lines.lock().unwrap();
+        assert!(!lines.is_empty());
+        assert!(lines.iter().all(|line| !line.contains('\n')));
+    }
+
+    #[test]
+    fn test_encode_with_volume() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let text = "Volume 30";
+        let volume = Volume::new(30).expect("Volume must be 0-100");
+
+        let waveform = ggwave
+            .encode_v(text, protocols::AUDIBLE_FAST, volume)
+            .expect("Failed to encode text");
+        assert_eq!(
+            waveform,
+            ggwave.encode(text, protocols::AUDIBLE_FAST, 30).unwrap()
+        );
+
+        let mut buffer = vec![0u8; waveform.len()];
+        let written = ggwave
+            .encode_into_buffer_v(
+                text,
+                protocols::AUDIBLE_FAST,
+                Volume::clamped(30),
+                &mut buffer,
+            )
+            .expect("Failed to encode text into buffer");
+        assert_eq!(&buffer[..written], &waveform[..]);
+
+        let mut decode_buffer = vec![0u8; 1024];
+        assert_eq!(ggwave.decode(&waveform, &mut decode_buffer).unwrap(), text);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_decode_wav_file() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let text = "Hello, WAV!";
+
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let path = dir.path().join("message.wav");
+
+        ggwave
+            .encode_to_wav_file(text, protocols::AUDIBLE_FAST, 50, &path)
+            .expect("Failed to encode text to WAV file");
+
+        let decoded = ggwave
+            .decode_wav_file(&path, 1024)
+            .expect("Failed to decode WAV file");
+        assert_eq!(decoded, text);
+
+        let wav = std::fs::read(&path).expect("Failed to read WAV file");
+        let decoded = ggwave
+            .decode_wav_bytes(&wav, 1024)
+            .expect("Failed to decode WAV bytes");
+        assert_eq!(decoded, text);
+    }
+
+    #[test]
+    fn test_convert_samples_boundaries() {
+        fn bytes<const N: usize, T: Copy>(samples: [T; N], to_ne: fn(T) -> Vec<u8>) -> Vec<u8> {
+            samples.into_iter().flat_map(to_ne).collect()
+        }
+        let f32s = |s: [f32; 3]| bytes(s, |v| v.to_ne_bytes().to_vec());
+        let convert = |raw: &[u8], from, to| {
+            convert::convert_samples(raw, from, to).expect("Failed to convert")
+        };
+
+        // Integer formats to f32 divide by 128 or 32768
+        let cases: [(SampleFormat, Vec<u8>, [f32; 3]); 4] = [
+            (
+                sample_formats::U8,
+                vec![0, 128, 255],
+                [-1.0, 0.0, 127.0 / 128.0],
+            ),
+            (
+                sample_formats::I8,
+                vec![0x80, 0, 0x7f],
+                [-1.0, 0.0, 127.0 / 128.0],
+            ),
+            (
+                sample_formats::U16,
+                bytes([0u16, 0x8000, 0xffff], |v| v.to_ne_bytes().to_vec()),
+                [-1.0, 0.0, 32767.0 / 32768.0],
+            ),
+            (
+                sample_formats::I16,
+                bytes([i16::MIN, 0, i16::MAX], |v| v.to_ne_bytes().to_vec()),
+                [-1.0, 0.0, 32767.0 / 32768.0],
+            ),
+        ];
+        for (format, raw, expected) in &cases {
+            assert_eq!(
+                convert(raw, *format, sample_formats::F32),
+                f32s(*expected),
+                "{} to f32",
+                sample_formats::name(*format)
+            );
+        }
+
+        // f32 to integer formats clamps and multiplies by 127 or 32767
+        let out_of_range = f32s([-2.0, 0.0, 2.0]);
+        let full_scale = f32s([-1.0, 0.0, 1.0]);
+        let cases: [(SampleFormat, Vec<u8>); 4] = [
+            (sample_formats::U8, vec![1, 128, 255]),
+            (sample_formats::I8, vec![0x81, 0, 0x7f]),
+            (
+                sample_formats::U16,
+                bytes([1u16, 0x8000, 0xffff], |v| v.to_ne_bytes().to_vec()),
+            ),
+            (
+                sample_formats::I16,
+                bytes([-32767i16, 0, 32767], |v| v.to_ne_bytes().to_vec()),
+            ),
+        ];
+        for (format, expected) in &cases {
+            for input in [&full_scale, &out_of_range] {
+                assert_eq!(
+                    &convert(input, sample_formats::F32, *format),
+                    expected,
+                    "f32 to {}",
+                    sample_formats::name(*format)
+                );
+            }
+        }
+
+        // Half scale truncates toward zero
+        assert_eq!(
+            convert(
+                &f32s([-0.5, 0.25, 0.5]),
+                sample_formats::F32,
+                sample_formats::I16
+            ),
+            bytes([-16383i16, 8191, 16383], |v| v.to_ne_bytes().to_vec())
+        );
+        // The same format is copied as-is, even out of range
+        assert_eq!(
+            convert(&out_of_range, sample_formats::F32, sample_formats::F32),
+            out_of_range
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_decode_wav_in_other_format() {
+        let _serial = serial();
+        let text = "Converted WAV";
+
+        // A 16-bit capture decoded by an f32 instance
+        let ggwave = GGWave::builder()
+            .input_sample_format(sample_formats::F32)
+            .output_sample_format(sample_formats::I16)
+            .build()
+            .expect("Failed to initialize GGWave");
+        let wav = ggwave
+            .encode_to_wav(text, protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text to WAV");
+        assert_eq!(
+            WavReader::new(Cursor::new(&wav))
+                .unwrap()
+                .spec()
+                .bits_per_sample,
+            16
+        );
+        assert_eq!(ggwave.decode_wav_bytes(&wav, 1024).unwrap(), text);
+
+        // A float capture decoded by an i16 instance
+        let ggwave = GGWave::builder()
+            .input_sample_format(sample_formats::I16)
+            .output_sample_format(sample_formats::F32)
+            .build()
+            .expect("Failed to initialize GGWave");
+        let waveform = ggwave
+            .encode(text, protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text");
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: ggwave.parameters().sampleRateInp as u32,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let mut wav = Vec::new();
+        let mut writer = hound::WavWriter::new(Cursor::new(&mut wav), spec).unwrap();
+        for sample in convert::bytes_to_f32(&waveform, sample_formats::F32).unwrap() {
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+        assert_eq!(ggwave.decode_wav_bytes(&wav, 1024).unwrap(), text);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_decode_robust_with_padded_noise() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let params = *ggwave.parameters();
+        let text = "Noisy channel";
+
+        let waveform = ggwave
+            .encode(text, protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text");
+        let message = convert::bytes_to_f32(&waveform, params.sampleFormatOut).unwrap();
+
+        // Deterministic low-level noise around the message
+        let mut seed = 0x1234_5678u32;
+        let mut noise = |len: usize| -> Vec<f32> {
+            (0..len)
+                .map(|_| {
+                    seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                    ((seed >> 8) as f32 / (1u32 << 24) as f32 - 0.5) * 0.01
+                })
+                .collect()
+        };
+
+        let rate = params.sampleRateInp as usize;
+        let mut padded = noise(rate + 123);
+        padded.extend_from_slice(&message);
+        padded.extend(noise(rate / 2));
+
+        let input = convert::f32_to_bytes(&padded, params.sampleFormatInp).unwrap();
+        let mut buffer = vec![0u8; 1024];
+        let decoded = ggwave
+            .decode_robust(&input, &mut buffer)
+            .expect("Failed to decode padded waveform");
+
+        assert_eq!(decoded, text);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_assess_device() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let rate = ggwave.parameters().sampleRateInp;
+
+        let flat = ggwave
+            .assess_device(|sweep| Ok(sweep.to_vec()))
+            .expect("Failed to assess loopback");
+        assert!(flat.usable_range.is_some());
+        assert_eq!(flat.rolloff_db_per_octave, None);
+        assert_eq!(
+            flat.supported_protocols,
+            (0..protocols::CUSTOM_0).collect::<Vec<_>>()
+        );
+
+        // Simulate a speaker that rolls off above 8 kHz with four one-pole lowpass stages
+        let alpha = 1.0 - (-2.0 * std::f32::consts::PI * 8000.0 / rate).exp();
+        let muffled = ggwave
+            .assess_device(|sweep| {
+                let mut state = [0.0f32; 4];
+                Ok(sweep
+                    .iter()
+                    .map(|&x| {
+                        state.iter_mut().fold(x, |input, y| {
+                            *y += alpha * (input - *y);
+                            *y
+                        })
+                    })
+                    .collect())
+            })
+            .expect("Failed to assess lowpass device");
+
+        assert!(muffled.supports(protocols::AUDIBLE_NORMAL));
+        assert!(!muffled.supports(protocols::ULTRASOUND_NORMAL));
+        assert!(muffled.rolloff_db_per_octave.is_some_and(|r| r > 0.0));
+    }
+
+    #[test]
+    fn test_send_to_thread() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+
+        let handle = std::thread::spawn(move || {
+            ggwave
+                .encode("Hello from a thread", protocols::AUDIBLE_FAST, 50)
+                .map(|waveform| waveform.len())
+        });
+
+        let len = handle
+            .join()
+            .expect("Encoding thread panicked")
+            .expect("Failed to encode text");
+        assert!(len > 0);
+    }
+
+    #[test]
+    fn test_max_output_samples() {
+        let _serial = serial();
+        let text = "Capped output";
+
+        let unlimited = GGWave::builder()
+            .build()
+            .expect("Failed to initialize GGWave");
+        let size = unlimited
+            .calculate_encode_buffer_size(text, protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to calculate buffer size");
+        let samples = size / convert::sample_size(unlimited.parameters().sampleFormatOut).unwrap();
+        drop(unlimited);
+
+        let capped = GGWave::builder()
+            .max_output_samples(samples - 1)
+            .build()
+            .expect("Failed to initialize GGWave");
+        assert!(matches!(
+            capped.encode(text, protocols::AUDIBLE_FAST, 50),
+            Err(Error::OutputTooLarge { max, .. }) if max == samples - 1
+        ));
+        drop(capped);
+
+        let exact = GGWave::builder()
+            .max_output_samples(samples)
+            .build()
+            .expect("Failed to initialize GGWave");
+        assert!(exact.encode(text, protocols::AUDIBLE_FAST, 50).is_ok());
+    }
+
+    #[test]
+    fn test_protocol_mask_isolation() {
+        let _serial = serial();
+        let text = "Per-instance masks";
+        let audible_only = ProtocolMask::NONE.with(protocols::AUDIBLE_FAST);
+
+        let restricted = GGWave::builder()
+            .rx_protocols(audible_only)
+            .tx_protocols(audible_only)
+            .build()
+            .expect("Failed to initialize restricted GGWave");
+        let full = GGWave::builder()
+            .rx_protocols(ProtocolMask::BUILT_IN)
+            .tx_protocols(ProtocolMask::BUILT_IN)
+            .build()
+            .expect("Failed to initialize full GGWave");
+
+        assert_eq!(restricted.tx_protocols(), audible_only);
+        assert_eq!(full.tx_protocols(), ProtocolMask::BUILT_IN);
+
+        // Creating the second instance did not widen the first one
+        assert!(
+            restricted
+                .encode(text, protocols::AUDIBLE_FASTEST, 50)
+                .is_err()
+        );
+
+        // And the restricted masks did not leak into the second one
+        let waveform = full
+            .encode(text, protocols::AUDIBLE_FASTEST, 50)
+            .expect("Failed to encode fastest");
+        let mut buffer = vec![0u8; 1024];
+        assert_eq!(full.decode(&waveform, &mut buffer).unwrap(), text);
+        assert_ne!(restricted.decode(&waveform, &mut buffer).ok(), Some(text));
+
+        // Instances without explicit masks use the process-wide defaults
+        assert_eq!(protocol_mask::global_tx(), ProtocolMask::BUILT_IN);
+    }
+
+    #[test]
+    fn test_concurrent_protocol_toggles() {
+        let _serial = serial();
+        let text = "Concurrent";
+        let toggler = GGWave::new().expect("Failed to initialize toggler");
+        let encoder = GGWave::new().expect("Failed to initialize encoder");
+        let reference = encoder
+            .encode(text, protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode reference");
+
+        std::thread::scope(|scope| {
+            scope.spawn(move || {
+                for i in 0..200 {
+                    let enabled = i % 2 == 1;
+                    toggler.toggle_rx_protocol(protocols::ULTRASOUND_FASTEST, enabled);
+                    toggler.toggle_tx_protocol(protocols::ULTRASOUND_FASTEST, enabled);
+                }
+            });
+            scope.spawn(move || {
+                for _ in 0..20 {
+                    let waveform = encoder
+                        .encode(text, protocols::AUDIBLE_FAST, 50)
+                        .expect("Failed to encode while toggling");
+                    assert_eq!(waveform, reference);
+                }
+            });
+            scope.spawn(|| {
+                // Instances created meanwhile get their own masks whatever the
+                // global tables hold at the time
+                for _ in 0..20 {
+                    let ggwave = GGWave::builder()
+                        .tx_protocols(ProtocolMask::BUILT_IN)
+                        .build()
+                        .expect("Failed to initialize while toggling");
+                    assert_eq!(ggwave.tx_protocols(), ProtocolMask::BUILT_IN);
+                }
+            });
+        });
+
+        // The toggler finished with the protocols enabled again
+        assert_eq!(protocol_mask::global_rx(), ProtocolMask::BUILT_IN);
+        assert_eq!(protocol_mask::global_tx(), ProtocolMask::BUILT_IN);
+    }
+
+    #[test]
+    fn test_register_custom_protocol() {
+        let _serial = serial();
+        let text = "Custom protocol";
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+
+        let protocol = CustomProtocol::new()
+            .freq_start(64)
+            .frames_per_tx(6)
+            .bytes_per_tx(3);
+        assert!(
+            ggwave
+                .register_custom_protocol(protocols::AUDIBLE_FAST, protocol)
+                .is_err()
+        );
+        ggwave
+            .register_custom_protocol(protocols::CUSTOM_0, protocol)
+            .expect("Failed to register custom protocol");
+
+        let custom_only = ProtocolMask::NONE.with(protocols::CUSTOM_0);
+        let sender = GGWave::builder()
+            .tx_protocols(custom_only)
+            .build()
+            .expect("Failed to initialize sender");
+        let receiver = GGWave::builder()
+            .rx_protocols(custom_only)
+            .build()
+            .expect("Failed to initialize receiver");
+
+        // Leave the global tables as the other tests expect them
+        ggwave.toggle_rx_protocol(protocols::CUSTOM_0, false);
+        ggwave.toggle_tx_protocol(protocols::CUSTOM_0, false);
+
+        let waveform = sender
+            .encode(text, protocols::CUSTOM_0, 50)
+            .expect("Failed to encode with custom protocol");
+        let mut buffer = vec![0u8; 1024];
+        assert_eq!(receiver.decode(&waveform, &mut buffer).unwrap(), text);
+    }
+
+    #[test]
+    fn test_enabled_protocols() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let built_in: Vec<_> = ProtocolMask::BUILT_IN.iter().collect();
+        assert_eq!(ggwave.enabled_rx_protocols(), built_in);
+        assert_eq!(ggwave.enabled_tx_protocols(), built_in);
+
+        ggwave.toggle_rx_protocol(protocols::ULTRASOUND_FAST, false);
+        ggwave.toggle_rx_protocol(protocols::DT_NORMAL, false);
+        ggwave.toggle_tx_protocol(protocols::CUSTOM_1, true);
+
+        let rx = ggwave.enabled_rx_protocols();
+        assert!(!rx.contains(&protocols::ULTRASOUND_FAST));
+        assert!(!rx.contains(&protocols::DT_NORMAL));
+        assert_eq!(rx.len(), built_in.len() - 2);
+        assert!(ggwave.enabled_tx_protocols().contains(&protocols::CUSTOM_1));
+        // Instances created before the toggles keep their protocols
+        assert_eq!(ggwave.rx_protocols(), ProtocolMask::BUILT_IN);
+
+        // Leave the global tables as the other tests expect them
+        ggwave.toggle_tx_protocol(protocols::CUSTOM_1, false);
+        ggwave.toggle_rx_protocol(protocols::ULTRASOUND_FAST, true);
+        ggwave.toggle_rx_protocol(protocols::DT_NORMAL, true);
+        assert_eq!(ggwave.enabled_rx_protocols(), built_in);
+        assert_eq!(ggwave.enabled_tx_protocols(), built_in);
+    }
+
+    #[test]
+    fn test_decode_resampled() {
+        let _serial = serial();
+        let text = "Resampled";
+        // Tones are laid out at 48 kHz and played out at 44.1 kHz
+        let sender = GGWave::builder()
+            .sample_rate(48000.0)
+            .output_sample_rate(44100.0)
+            .output_sample_format(sample_formats::F32)
+            .build()
+            .expect("Failed to initialize 44.1 kHz GGWave");
+        let receiver = GGWave::builder()
+            .sample_rate(48000.0)
+            .input_sample_format(sample_formats::F32)
+            .build()
+            .expect("Failed to initialize 48 kHz GGWave");
+
+        let waveform = sender
+            .encode(text, protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text");
+        let samples = convert::bytes_to_f32(&waveform, sample_formats::F32).unwrap();
+
+        let mut buffer = vec![0u8; 1024];
+        assert_eq!(
+            receiver
+                .decode_resampled(&samples, 44100.0, &mut buffer)
+                .unwrap(),
+            text
+        );
+    }
+
+    #[test]
+    fn test_encode_sequence() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let parts: [&[u8]; 3] = [b"first", b"second", b"third"];
+
+        let (waveform, manifest) = ggwave
+            .encode_sequence(&parts, protocols::AUDIBLE_FASTEST, 50)
+            .expect("Failed to encode sequence");
+        assert_eq!(manifest.len(), parts.len());
+        assert_eq!(manifest.waveform_length(), waveform.len());
+
+        let mut buffer = vec![0u8; 1024];
+        let received: Vec<Vec<u8>> = manifest
+            .parts
+            .iter()
+            .map(|part| {
+                ggwave
+                    .decode_binary(&waveform[part.range()], &mut buffer)
+                    .expect("Failed to decode part")
+                    .to_vec()
+            })
+            .collect();
+
+        assert!(manifest.verify(&received));
+        assert!(!manifest.verify(&received[..2]));
+        assert!(!manifest.verify(&["first", "secobd", "third"]));
+    }
+
+    #[test]
+    fn test_is_effectively_inaudible() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+
+        // 15 kHz at 48 kHz and 1024 samples per frame
+        assert!(!ggwave.is_effectively_inaudible(protocols::ULTRASOUND_FAST));
+        assert!(!ggwave.is_effectively_inaudible(protocols::AUDIBLE_FAST));
+        assert!(!ggwave.is_effectively_inaudible(protocols::CUSTOM_0));
+        drop(ggwave);
+
+        // 30 kHz at 96 kHz
+        let ggwave = GGWave::builder()
+            .sample_rate(96000.0)
+            .samples_per_frame(1024)
+            .build()
+            .expect("Failed to initialize 96 kHz GGWave");
+        assert!(ggwave.is_effectively_inaudible(protocols::ULTRASOUND_FAST));
+        assert!(!ggwave.is_effectively_inaudible(protocols::AUDIBLE_FAST));
+    }
+
+    #[test]
+    fn test_decode_lossy() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let mut buffer = vec![0u8; 1024];
+
+        let waveform = ggwave
+            .encode_payload(&[0xFF, 0xFE], protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode bytes");
+        assert!(matches!(
+            ggwave.decode(&waveform, &mut buffer),
+            Err(Error::Utf8Error(_))
+        ));
+        assert_eq!(
+            ggwave.decode_lossy(&waveform, &mut buffer).unwrap(),
+            "\u{FFFD}\u{FFFD}"
+        );
+        assert_eq!(
+            ggwave.decode_binary(&waveform, &mut buffer).unwrap(),
+            [0xFF, 0xFE]
+        );
+
+        let waveform = ggwave
+            .encode("Valid", protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text");
+        assert!(matches!(
+            ggwave.decode_lossy(&waveform, &mut buffer).unwrap(),
+            Cow::Borrowed("Valid")
+        ));
+    }
+
+    #[test]
+    fn test_encode_checked() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let data = [0u8, 1, 2, 3, 0xFF];
+        let mut buffer = vec![0u8; 1024];
+
+        let waveform = ggwave
+            .encode_checked(&data, protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode data");
+        assert_eq!(ggwave.decode_checked(&waveform, &mut buffer).unwrap(), data);
+
+        // A payload corrupted in a way the error correction cannot detect
+        let mut crc = checksum::Crc32::new();
+        crc.update(&data);
+        let mut corrupted = crc.finish().to_le_bytes().to_vec();
+        corrupted.extend_from_slice(&data);
+        corrupted[CHECKSUM_LEN + 1] ^= 0x10;
+        let waveform = ggwave
+            .encode_payload(&corrupted, protocols::AUDIBLE_FAST, 50)
+            .unwrap();
+        assert!(matches!(
+            ggwave.decode_checked(&waveform, &mut buffer),
+            Err(Error::ChecksumMismatch)
+        ));
+
+        let max = ggwave.max_payload_len() - CHECKSUM_LEN;
+        assert!(
+            ggwave
+                .encode_checked(&vec![0u8; max + 1], protocols::AUDIBLE_FAST, 50)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_send_large() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let data: Vec<u8> = (0..1024).map(|i| (i * 7 % 256) as u8).collect();
+
+        let waveforms = ggwave
+            .send_large(&data, protocols::AUDIBLE_FASTEST, 50)
+            .expect("Failed to encode data");
+        assert_eq!(
+            waveforms.len(),
+            1024usize.div_ceil(ggwave.max_payload_len() - 4)
+        );
+
+        let mut buffer = vec![0u8; 1024];
+        let mut reassembler = ChunkReassembler::new();
+        let mut order: Vec<usize> = (0..waveforms.len()).rev().collect();
+        // A repeated chunk
+        order.insert(1, order[0]);
+
+        let mut received = None;
+        for (n, &index) in order.iter().enumerate() {
+            let chunk = ggwave
+                .decode_binary(&waveforms[index], &mut buffer)
+                .expect("Failed to decode chunk");
+            received = reassembler.push(chunk).expect("Invalid chunk");
+            assert_eq!(received.is_some(), n == order.len() - 1);
+        }
+        assert_eq!(received, Some(data));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_decode_all() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let silence = convert::f32_to_bytes(&[0.0; 4096], sample_formats::F32).unwrap();
+        let messages = [
+            ("first", protocols::AUDIBLE_FAST),
+            ("second", protocols::AUDIBLE_FASTEST),
+            ("third", protocols::AUDIBLE_FAST),
+        ];
+
+        let mut recording = Vec::new();
+        let mut offsets = Vec::new();
+        for (text, protocol) in messages {
+            recording.extend_from_slice(&silence);
+            offsets.push(recording.len() / 4);
+            recording.extend(ggwave.encode(text, protocol, 50).unwrap());
+        }
+        recording.extend_from_slice(&silence);
+
+        let results = ggwave
+            .decode_all(&recording, 1024)
+            .expect("Failed to decode");
+        assert_eq!(results.len(), messages.len());
+        for ((result, (text, protocol)), offset) in results.iter().zip(messages).zip(offsets) {
+            assert_eq!(result.text, text);
+            assert_eq!(result.protocol, protocol);
+            // The offset is found to within a frame
+            assert!(result.offset.abs_diff(offset) <= 1024);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_decode_wav_streaming() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let silence = convert::f32_to_bytes(&[0.0; 4096], sample_formats::F32).unwrap();
+        let texts = ["first", "second", "third"];
+
+        let mut recording = Vec::new();
+        for text in texts {
+            recording.extend_from_slice(&silence);
+            recording.extend(ggwave.encode(text, protocols::AUDIBLE_FAST, 50).unwrap());
+        }
+        recording.extend_from_slice(&silence);
+
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let path = dir.path().join("recording.wav");
+        ggwave
+            .save_raw_to_wav(&recording, &path)
+            .expect("Failed to write WAV file");
+
+        let mut received = Vec::new();
+        ggwave
+            .decode_wav_streaming(&path, |message| received.push(message))
+            .expect("Failed to decode WAV file");
+
+        assert_eq!(received.len(), texts.len());
+        for (message, text) in received.iter().zip(texts) {
+            assert_eq!(message.text, text);
+            assert_eq!(message.protocol, protocols::AUDIBLE_FAST);
+        }
+        assert!(received.windows(2).all(|w| w[0].offset < w[1].offset));
+
+        assert!(
+            ggwave
+                .decode_wav_streaming(dir.path().join("missing.wav"), |_| {})
+                .is_err()
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_no_message() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let silence = convert::f32_to_bytes(&[0.0; 4096], sample_formats::F32).unwrap();
+        let mut buffer = vec![0u8; 1024];
+
+        assert!(matches!(
+            ggwave.decode(&silence, &mut buffer),
+            Err(Error::NoMessage)
+        ));
+        assert!(matches!(
+            ggwave.decode_binary(&silence, &mut buffer),
+            Err(Error::NoMessage)
+        ));
+        assert!(matches!(
+            ggwave.process_audio_chunk(&silence, &mut buffer),
+            Ok(None)
+        ));
+        assert!(matches!(
+            ggwave.decode_detailed(&silence, &mut buffer),
+            Ok(None)
+        ));
+    }
+
+    #[test]
+    fn test_decode_typed_samples() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let text = "Typed";
+
+        let waveform = ggwave
+            .encode_as_format(text, protocols::AUDIBLE_FAST, 50, sample_formats::F32)
+            .expect("Failed to encode text");
+        let samples = convert::bytes_to_f32(&waveform, sample_formats::F32).unwrap();
+
+        let mut buffer = vec![0u8; 1024];
+        assert_eq!(ggwave.decode_f32(&samples, &mut buffer).unwrap(), text);
+        assert!(matches!(
+            ggwave.decode_i16(&[0; 16], &mut buffer),
+            Err(Error::InvalidSampleFormat)
+        ));
+        drop(ggwave);
+
+        let receiver = GGWave::builder()
+            .input_sample_format(sample_formats::I16)
+            .build()
+            .expect("Failed to initialize GGWave");
+        // Encoded by the receiver itself, whose sample rate differs from the
+        // first instance's
+        let waveform = receiver
+            .encode_as_format(text, protocols::AUDIBLE_FAST, 50, sample_formats::I16)
+            .expect("Failed to encode text");
+        let samples: Vec<i16> = waveform
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+            .collect();
+        assert_eq!(receiver.decode_i16(&samples, &mut buffer).unwrap(), text);
+        assert!(matches!(
+            receiver.decode_f32(&[0.0; 16], &mut buffer),
+            Err(Error::InvalidSampleFormat)
+        ));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_decode_clipped_samples() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let text = "Too loud";
+        let mut buffer = vec![0u8; 1024];
+
+        let waveform = ggwave
+            .encode_as_format(text, protocols::AUDIBLE_NORMAL, 50, sample_formats::F32)
+            .expect("Failed to encode text");
+        let samples = convert::bytes_to_f32(&waveform, sample_formats::F32).unwrap();
+
+        let result = ggwave
+            .decode_f32_detailed(&samples, &mut buffer)
+            .expect("Failed to decode samples")
+            .expect("No message found");
+        assert_eq!(result.text, text);
+        assert!(!result.clipped);
+
+        // An input gain far too high for the signal
+        let clipped: Vec<f32> = samples.iter().map(|s| (s * 3.0).clamp(-1.0, 1.0)).collect();
+        let result = ggwave
+            .decode_f32_detailed(&clipped, &mut buffer)
+            .expect("Failed to decode samples")
+            .expect("No message found");
+        assert_eq!(result.text, text);
+        assert!(result.clipped);
+    }
 
-impl Drop for GGWave {
-    fn drop(&mut self) {
-        unsafe {
-            ggwave_free(self.instance);
-        }
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_encode_with_padding() {
+        let _serial = serial();
+        let ggwave = GGWave::builder()
+            .output_sample_rate(48000.0)
+            .output_sample_format(sample_formats::U16)
+            .build()
+            .expect("Failed to initialize GGWave");
+
+        let waveform = ggwave
+            .encode("Padded", protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text");
+        let padded = ggwave
+            .encode_with_padding("Padded", protocols::AUDIBLE_FAST, 50, 100, 50)
+            .expect("Failed to encode text");
+
+        // 100 ms and 50 ms at 48 kHz, 2 bytes per sample
+        let lead = 4800 * 2;
+        let trail = 2400 * 2;
+        assert_eq!(padded.len(), waveform.len() + lead + trail);
+        assert_eq!(&padded[lead..lead + waveform.len()], &waveform[..]);
+        assert_eq!(
+            convert::bytes_to_f32(&padded[..lead], sample_formats::U16).unwrap(),
+            vec![0.0; 4800]
+        );
     }
-}
 
-/// Protocol constants module for easier import
-///
-/// This module provides constants for all the available transmission protocols.
-pub mod protocols {
-    use super::*;
+    #[test]
+    fn test_mix_into() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let original: Vec<f32> = (0..8 * 48000)
+            .map(|i| 0.8 * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / 48000.0).sin())
+            .collect();
 
-    /// Standard audible protocol with normal speed
-    pub const AUDIBLE_NORMAL: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_AUDIBLE_NORMAL;
-    /// Fast audible protocol
-    pub const AUDIBLE_FAST: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_AUDIBLE_FAST;
-    /// Fastest audible protocol
-    pub const AUDIBLE_FASTEST: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_AUDIBLE_FASTEST;
+        let mut track = original.clone();
+        let at = 24000;
+        let mixed = ggwave
+            .mix_into("Mixed", protocols::AUDIBLE_FAST, 50, &mut track, at)
+            .expect("Failed to mix");
 
-    /// Standard ultrasound protocol with normal speed
-    pub const ULTRASOUND_NORMAL: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_ULTRASOUND_NORMAL;
-    /// Fast ultrasound protocol
-    pub const ULTRASOUND_FAST: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_ULTRASOUND_FAST;
-    /// Fastest ultrasound protocol
-    pub const ULTRASOUND_FASTEST: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_ULTRASOUND_FASTEST;
+        assert_eq!(track[..at], original[..at]);
+        assert_eq!(track[at + mixed..], original[at + mixed..]);
+        assert_ne!(track[at..at + mixed], original[at..at + mixed]);
+        assert!(track.iter().all(|s| (-1.0..=1.0).contains(s)));
 
-    /// DT protocol with normal speed
-    pub const DT_NORMAL: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_DT_NORMAL;
-    /// Fast DT protocol
-    pub const DT_FAST: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_DT_FAST;
-    /// Fastest DT protocol
-    pub const DT_FASTEST: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_DT_FASTEST;
+        let mut short = vec![0.0f32; 1000];
+        assert!(matches!(
+            ggwave.mix_into("Mixed", protocols::AUDIBLE_FAST, 50, &mut short, 0),
+            Err(Error::BufferTooSmall { provided: 1000, .. })
+        ));
+    }
 
-    /// MT protocol with normal speed
-    pub const MT_NORMAL: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_MT_NORMAL;
-    /// Fast MT protocol
-    pub const MT_FAST: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_MT_FAST;
-    /// Fastest MT protocol
-    pub const MT_FASTEST: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_MT_FASTEST;
+    #[test]
+    fn test_encode_normalized() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
 
-    /// Custom protocol 0
-    pub const CUSTOM_0: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_CUSTOM_0;
-    /// Custom protocol 1
-    pub const CUSTOM_1: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_CUSTOM_1;
-    /// Custom protocol 2
-    pub const CUSTOM_2: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_CUSTOM_2;
-    /// Custom protocol 3
-    pub const CUSTOM_3: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_CUSTOM_3;
-    /// Custom protocol 4
-    pub const CUSTOM_4: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_CUSTOM_4;
-    /// Custom protocol 5
-    pub const CUSTOM_5: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_CUSTOM_5;
-    /// Custom protocol 6
-    pub const CUSTOM_6: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_CUSTOM_6;
-    /// Custom protocol 7
-    pub const CUSTOM_7: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_CUSTOM_7;
-    /// Custom protocol 8
-    pub const CUSTOM_8: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_CUSTOM_8;
-    /// Custom protocol 9
-    pub const CUSTOM_9: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_CUSTOM_9;
-    /// Total number of protocols
-    pub const COUNT: ProtocolId = ggwave_ProtocolId_GGWAVE_PROTOCOL_COUNT;
-}
+        for (protocol, target) in [(protocols::AUDIBLE_FAST, 0.5), (protocols::DT_FAST, 0.9)] {
+            let samples = ggwave
+                .encode_normalized("Normalized", protocol, target)
+                .expect("Failed to encode text");
+            let peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+            assert!((peak - target).abs() < 1e-4);
+        }
 
-/// Sample format constants
-///
-/// This module provides constants for all the available sample formats.
-pub mod sample_formats {
-    use super::*;
+        assert!(
+            ggwave
+                .encode_normalized("Normalized", protocols::AUDIBLE_FAST, 1.5)
+                .is_err()
+        );
+    }
 
-    /// Undefined sample format
-    pub const UNDEFINED: SampleFormat = ggwave_SampleFormat_GGWAVE_SAMPLE_FORMAT_UNDEFINED;
-    /// Unsigned 8-bit sample format
-    pub const U8: SampleFormat = ggwave_SampleFormat_GGWAVE_SAMPLE_FORMAT_U8;
-    /// Signed 8-bit sample format
-    pub const I8: SampleFormat = ggwave_SampleFormat_GGWAVE_SAMPLE_FORMAT_I8;
-    /// Unsigned 16-bit sample format
-    pub const U16: SampleFormat = ggwave_SampleFormat_GGWAVE_SAMPLE_FORMAT_U16;
-    /// Signed 16-bit sample format
-    pub const I16: SampleFormat = ggwave_SampleFormat_GGWAVE_SAMPLE_FORMAT_I16;
-    /// 32-bit float sample format
-    pub const F32: SampleFormat = ggwave_SampleFormat_GGWAVE_SAMPLE_FORMAT_F32;
-}
+    #[test]
+    fn test_decode_interleaved() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let text = "Stereo";
 
-/// Operating mode constants
-///
-/// This module provides constants for all the available operating modes.
-pub mod operating_modes {
-    use super::*;
+        let waveform = ggwave
+            .encode(text, protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text");
+        let mono = convert::bytes_to_f32(&waveform, sample_formats::F32).unwrap();
 
-    /// Reception mode
-    pub const RX: i32 = GGWAVE_OPERATING_MODE_RX as i32;
-    /// Transmission mode
-    pub const TX: i32 = GGWAVE_OPERATING_MODE_TX as i32;
-    /// Reception and transmission mode
-    pub const RX_AND_TX: i32 = GGWAVE_OPERATING_MODE_RX_AND_TX as i32;
-    /// Transmission of tones only
-    pub const TX_ONLY_TONES: i32 = GGWAVE_OPERATING_MODE_TX_ONLY_TONES as i32;
-    /// Use DSS (Direct Sequence Spread)
-    pub const USE_DSS: i32 = GGWAVE_OPERATING_MODE_USE_DSS as i32;
-}
+        // Same signal on the left, half of it on the right, and a partial frame
+        let mut stereo: Vec<f32> = mono.iter().flat_map(|&s| [s, s * 0.5]).collect();
+        stereo.push(1.0);
+        assert_eq!(downmix_to_mono(&stereo, 2).len(), mono.len());
 
-/// Filter type constants
-///
-/// This module provides constants for all the available filter types.
-pub mod filters {
-    use super::*;
+        let mut buffer = vec![0u8; 1024];
+        assert_eq!(
+            ggwave.decode_interleaved(&stereo, 2, &mut buffer).unwrap(),
+            text
+        );
+        assert!(ggwave.decode_interleaved(&stereo, 0, &mut buffer).is_err());
+    }
 
-    /// Hann window filter
-    pub const HANN: Filter = ggwave_Filter_GGWAVE_FILTER_HANN;
-    /// Hamming window filter
-    pub const HAMMING: Filter = ggwave_Filter_GGWAVE_FILTER_HAMMING;
-    /// First order high-pass filter
-    pub const FIRST_ORDER_HIGH_PASS: Filter = ggwave_Filter_GGWAVE_FILTER_FIRST_ORDER_HIGH_PASS;
-}
+    #[test]
+    fn test_protocol_names() {
+        assert_eq!(protocols::all().len(), protocols::COUNT as usize);
+        for &id in protocols::all() {
+            assert_eq!(protocols::from_name(protocols::name(id)), Some(id));
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        assert_eq!(protocols::name(protocols::DT_FASTEST), "dt_fastest");
+        assert_eq!(protocols::name(protocols::COUNT), "unknown");
+        assert_eq!(
+            protocols::from_name("AUDIBLE_FAST"),
+            Some(protocols::AUDIBLE_FAST)
+        );
+        assert_eq!(
+            protocols::from_name("ultrasound-normal"),
+            Some(protocols::ULTRASOUND_NORMAL)
+        );
+        assert_eq!(protocols::from_name("audible fast"), None);
+        assert_eq!(protocols::from_name(""), None);
+    }
 
     #[test]
-    fn test_initialization() {
-        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
-        drop(ggwave);
+    fn test_recommended_volume() {
+        let speeds = [
+            (protocols::AUDIBLE_NORMAL, protocols::ULTRASOUND_NORMAL),
+            (protocols::AUDIBLE_FAST, protocols::ULTRASOUND_FAST),
+            (protocols::AUDIBLE_FASTEST, protocols::ULTRASOUND_FASTEST),
+        ];
+        for (audible, ultrasound) in speeds {
+            assert!(
+                protocols::recommended_volume(ultrasound) >= protocols::recommended_volume(audible),
+                "{}",
+                protocols::name(ultrasound)
+            );
+        }
+
+        for &id in protocols::all() {
+            let volume = protocols::recommended_volume(id);
+            assert!(
+                (constants::MIN_VOLUME..=constants::MAX_VOLUME).contains(&volume),
+                "{}",
+                protocols::name(id)
+            );
+        }
+        assert_eq!(
+            protocols::recommended_volume(protocols::CUSTOM_0),
+            constants::DEFAULT_VOLUME
+        );
     }
 
     #[test]
-    fn test_encode_decode() {
+    fn test_frequency_range() {
+        for id in protocols::AUDIBLE_NORMAL..=protocols::AUDIBLE_FASTEST {
+            let band = protocols::frequency_range(id).unwrap();
+            assert!(band.start < 5000.0, "{}", protocols::name(id));
+            assert!(band.end < 15000.0, "{}", protocols::name(id));
+        }
+        for id in protocols::ULTRASOUND_NORMAL..=protocols::ULTRASOUND_FASTEST {
+            let band = protocols::frequency_range(id).unwrap();
+            assert!(band.start >= 15000.0, "{}", protocols::name(id));
+            assert!(band.end <= 24000.0, "{}", protocols::name(id));
+        }
+        for id in protocols::DT_NORMAL..=protocols::MT_FASTEST {
+            assert!(protocols::frequency_range(id).unwrap().end < 5000.0);
+        }
+        assert_eq!(protocols::frequency_range(protocols::CUSTOM_0), None);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_decode_from_tcp() {
+        let _serial = serial();
         let ggwave = GGWave::new().expect("Failed to initialize GGWave");
-        let text = "Hello, GGWave!";
+        let text = "Hello over TCP";
 
         let waveform = ggwave
-            .encode(text, protocols::AUDIBLE_NORMAL, 50)
+            .encode_as_format(text, protocols::AUDIBLE_FAST, 50, sample_formats::I16)
             .expect("Failed to encode text");
+        let frame_bytes = ggwave.parameters().samplesPerFrame as usize * 2;
 
-        let mut buffer = vec![0u8; 1024];
-        let decoded = ggwave
-            .decode(&waveform, &mut buffer)
-            .expect("Failed to decode waveform");
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("Failed to bind");
+        let addr = listener.local_addr().unwrap();
 
-        assert_eq!(decoded, text);
+        let client = std::thread::spawn(move || {
+            use std::io::Write;
+
+            let mut stream = TcpStream::connect(addr).expect("Failed to connect");
+            let silence = vec![0u8; frame_bytes * 8];
+            // Network frames that do not line up with the decoder's frames
+            for frame in waveform.chunks(700 * 2).chain(silence.chunks(700 * 2)) {
+                stream
+                    .write_all(&(frame.len() as u32).to_le_bytes())
+                    .unwrap();
+                stream.write_all(frame).unwrap();
+            }
+        });
+
+        let (stream, _) = listener.accept().expect("Failed to accept connection");
+        let mut messages = Vec::new();
+        ggwave
+            .decode_from_tcp(stream, sample_formats::I16, |message| {
+                messages.push(message);
+                Ok(())
+            })
+            .expect("Failed to decode TCP stream");
+        client.join().unwrap();
+
+        assert_eq!(messages, [text]);
     }
 
     #[test]
-    fn test_builder() {
+    fn test_encoded_sample_count() {
+        let _serial = serial();
+        let text = "Count the samples";
+
+        for format in [sample_formats::U8, sample_formats::I16, sample_formats::F32] {
+            let ggwave = GGWave::builder()
+                .output_sample_format(format)
+                .build()
+                .expect("Failed to initialize GGWave");
+
+            let samples = ggwave
+                .encoded_sample_count(text, protocols::AUDIBLE_FAST, 50)
+                .expect("Failed to calculate sample count");
+            let bytes = ggwave
+                .calculate_encode_buffer_size(text, protocols::AUDIBLE_FAST, 50)
+                .expect("Failed to calculate buffer size");
+            assert_eq!(samples * convert::sample_size(format).unwrap(), bytes);
+
+            let waveform = ggwave.encode(text, protocols::AUDIBLE_FAST, 50).unwrap();
+            assert_eq!(waveform.len(), bytes, "{}", sample_formats::name(format));
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_calibrate_marker_threshold() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let rate = ggwave.parameters().sampleRateInp as usize;
+
+        // Deterministic white noise
+        let mut seed = 0x1357_9bdfu32;
+        let noise: Vec<f32> = (0..rate)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                ((seed >> 8) as f32 / (1u32 << 24) as f32 - 0.5) * 0.2
+            })
+            .collect();
+
+        let threshold = ggwave.calibrate_marker_threshold(&noise);
+        assert!((0.5..=10.0).contains(&threshold), "threshold {}", threshold);
+        // Louder white noise has the same spectral shape
+        let louder: Vec<f32> = noise.iter().map(|s| s * 4.0).collect();
+        assert!((ggwave.calibrate_marker_threshold(&louder) - threshold).abs() < 1e-3);
+
+        let silence = vec![0.0f32; rate];
+        assert_eq!(
+            ggwave.calibrate_marker_threshold(&silence),
+            constants::DEFAULT_SOUND_MARKER_THRESHOLD
+        );
+
+        let tuned = GGWave::builder()
+            .auto_marker_threshold(&noise)
+            .build()
+            .expect("Failed to initialize GGWave");
+        assert!((0.5..=10.0).contains(&tuned.parameters().soundMarkerThreshold));
+
+        // Calibrated receivers still decode clean messages
+        let waveform = tuned
+            .encode_as_format(
+                "Calibrated",
+                protocols::AUDIBLE_FAST,
+                50,
+                sample_formats::F32,
+            )
+            .unwrap();
+        let mut buffer = vec![0u8; 1024];
+        assert_eq!(tuned.decode(&waveform, &mut buffer).unwrap(), "Calibrated");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_contains_signal() {
+        let _serial = serial();
         let ggwave = GGWave::builder()
-            .sample_rate(48000.0)
             .output_sample_format(sample_formats::F32)
             .build()
-            .expect("Failed to initialize GGWave with builder");
+            .expect("Failed to initialize GGWave");
+        let rate = ggwave.parameters().sampleRateInp as usize;
 
-        let format = ggwave.get_output_sample_format();
-        assert_eq!(format, sample_formats::F32);
+        assert!(!ggwave.contains_signal(&vec![0.0f32; rate]));
+        assert!(!ggwave.contains_signal(&[]));
+
+        // Deterministic white noise has no marker either
+        let mut seed = 0x2468_ace0u32;
+        let noise: Vec<f32> = (0..rate)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                ((seed >> 8) as f32 / (1u32 << 24) as f32 - 0.5) * 0.2
+            })
+            .collect();
+        assert!(!ggwave.contains_signal(&noise));
+
+        for protocol in [protocols::AUDIBLE_FAST, protocols::ULTRASOUND_FAST] {
+            let waveform = ggwave.encode("Marker", protocol, 50).unwrap();
+            let message = convert::bytes_to_f32(&waveform, sample_formats::F32).unwrap();
+            // Not aligned to frames, and surrounded by silence
+            let mut recording = vec![0.0f32; rate / 3];
+            recording.extend(&message);
+            recording.resize(recording.len() + rate / 3, 0.0);
+            assert!(
+                ggwave.contains_signal(&recording),
+                "{}",
+                protocols::name(protocol)
+            );
+        }
     }
 
     #[test]
-    fn test_encode_into_buffer() {
+    fn test_estimate_duration() {
+        let _serial = serial();
+        let text = "How long is this?";
+
+        for rate in [16000.0, 48000.0] {
+            let ggwave = GGWave::builder()
+                .sample_rate(rate)
+                .build()
+                .expect("Failed to initialize GGWave");
+            let params = *ggwave.parameters();
+
+            let waveform = ggwave
+                .encode(text, protocols::AUDIBLE_FAST, 50)
+                .expect("Failed to encode text");
+            let samples = waveform.len() / convert::sample_size(params.sampleFormatOut).unwrap();
+            let actual = samples as f32 / params.sampleRateOut;
+
+            let estimate = ggwave.estimate_duration(protocols::AUDIBLE_FAST, text.len());
+            assert!(
+                (estimate - actual).abs() < 1e-3,
+                "estimate {} s, actual {} s at {} Hz",
+                estimate,
+                actual,
+                rate
+            );
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_decode_detailed() {
+        let _serial = serial();
         let ggwave = GGWave::new().expect("Failed to initialize GGWave");
-        let text = "Test buffer encode";
+        let text = "Which protocol?";
+        let mut buffer = vec![0u8; 1024];
 
-        let size = ggwave
-            .calculate_encode_buffer_size(text, protocols::AUDIBLE_NORMAL, 50)
-            .expect("Failed to calculate buffer size");
+        for protocol in [protocols::AUDIBLE_FASTEST, protocols::ULTRASOUND_FAST] {
+            let waveform = ggwave
+                .encode(text, protocol, 50)
+                .expect("Failed to encode text");
 
-        let mut buffer = vec![0u8; size];
-        let written = ggwave
-            .encode_into_buffer(text, protocols::AUDIBLE_NORMAL, 50, &mut buffer)
-            .expect("Failed to encode text into buffer");
+            let result = ggwave
+                .decode_detailed(&waveform, &mut buffer)
+                .expect("Failed to decode waveform")
+                .expect("No message found");
 
-        assert!(written > 0);
-        assert!(written <= buffer.len());
+            assert_eq!(result.text, text);
+            assert_eq!(result.protocol, protocol);
+            assert_eq!(result.length, text.len());
+            assert!(result.received_volume.is_some_and(|v| v > 0.0));
+            assert!(result.confidence > 0.8, "confidence {}", result.confidence);
+        }
+
+        let silence = vec![0u8; 4 * 48000];
+        let result = ggwave
+            .decode_detailed(&silence, &mut buffer)
+            .expect("Failed to decode silence");
+        assert_eq!(result, None);
     }
 
+    #[cfg(feature = "std")]
     #[test]
-    fn test_decode_binary() {
+    fn test_decode_confidence() {
+        let _serial = serial();
         let ggwave = GGWave::new().expect("Failed to initialize GGWave");
-        let data = [1u8, 2, 3, 4, 5];
+        let protocol = protocols::AUDIBLE_FAST;
+        let clean = ggwave
+            .encode_normalized("Confidence", protocol, 0.5)
+            .expect("Failed to encode text");
 
-        // First encode the binary data as a string
-        let encoded = ggwave
-            .encode(
-                &String::from_utf8_lossy(&data),
-                protocols::AUDIBLE_FASTEST,
-                50,
-            )
-            .expect("Failed to encode binary data");
+        // Deterministic white noise
+        let mut seed = 0x8765_4321u32;
+        let noise: Vec<f32> = (0..clean.len())
+            .map(|_| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                ((seed >> 8) as f32 / (1u32 << 24) as f32 - 0.5) * 0.4
+            })
+            .collect();
+        let noisy: Vec<f32> = clean.iter().zip(&noise).map(|(s, n)| s + n).collect();
 
-        // Then decode back to binary
+        let clean_confidence = ggwave.confidence(&clean, protocol);
+        let noisy_confidence = ggwave.confidence(&noisy, protocol);
+        let noise_confidence = ggwave.confidence(&noise, protocol);
+
+        assert!(clean_confidence > 0.9, "clean {}", clean_confidence);
+        assert!(
+            noisy_confidence < clean_confidence,
+            "noisy {}",
+            noisy_confidence
+        );
+        assert!(noise_confidence < 0.1, "noise {}", noise_confidence);
+        assert!(noise_confidence < noisy_confidence);
+
+        assert_eq!(ggwave.confidence(&clean, protocols::CUSTOM_0), 0.0);
+        assert_eq!(ggwave.confidence(&[], protocol), 0.0);
+    }
+
+    #[test]
+    fn test_process_audio_chunk_all() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let silence = vec![0u8; ggwave.parameters().samplesPerFrame as usize * 4 * 16];
+
+        let first = ggwave
+            .encode("First", protocols::AUDIBLE_FASTEST, 50)
+            .expect("Failed to encode first message");
+        let second = ggwave
+            .encode("Second", protocols::ULTRASOUND_FASTEST, 50)
+            .expect("Failed to encode second message");
+
+        let chunk = [&first[..], &silence, &second, &silence, &first, &silence].concat();
         let mut buffer = vec![0u8; 1024];
+        let messages = ggwave
+            .process_audio_chunk_all(&chunk, &mut buffer)
+            .expect("Failed to process chunk");
+
+        assert_eq!(messages, ["First", "Second"]);
+    }
+
+    #[cfg(feature = "flac")]
+    #[test]
+    fn test_encode_to_flac() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+
+        let flac = ggwave
+            .encode_to_flac("Hello, FLAC!", protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text to FLAC");
+        let wav = ggwave
+            .encode_to_wav("Hello, FLAC!", protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text to WAV");
+
+        assert_eq!(&flac[..4], b"fLaC");
+        assert!(flac.len() < wav.len());
+
         let decoded = ggwave
-            .decode_binary(&encoded, &mut buffer)
-            .expect("Failed to decode binary data");
+            .decode_flac(&flac, 1024)
+            .expect("Failed to decode FLAC data");
+        assert_eq!(decoded, "Hello, FLAC!");
+    }
 
-        assert_eq!(decoded, data);
+    #[cfg(feature = "ogg")]
+    #[test]
+    fn test_encode_to_ogg() {
+        let _serial = serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+
+        let ogg = ggwave
+            .encode_to_ogg("Hello, Ogg!", protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text to Ogg");
+
+        assert_eq!(&ogg[..4], b"OggS");
+
+        // Lossy compression may remove the tones, but never corrupts the text
+        match ggwave.decode_ogg(&ogg, 1024) {
+            Ok(decoded) => assert_eq!(decoded, "Hello, Ogg!"),
+            Err(Error::NoMessage) => {}
+            Err(e) => panic!("Failed to decode Ogg data: {}", e),
+        }
     }
 }
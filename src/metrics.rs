@@ -0,0 +1,130 @@
+//! Decode counters for performance tuning
+//!
+//! With the `metrics` feature, every `GGWave` counts the calls it makes into
+//! the C decoder and how long they take, which is cheaper and more precise
+//! than timing the calls from the outside. The counters cover every decoding
+//! method, since they all end in one of these calls.
+
+use core::time::Duration;
+
+/// Decode counters of one instance, returned by [`GGWave::metrics`](crate::GGWave::metrics)
+///
+/// # Examples
+///
+/// ```
+/// use ggwave_rs::{GGWave, protocols};
+///
+/// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+/// let waveform = ggwave
+///     .encode("Hello, World!", protocols::AUDIBLE_FAST, 50)
+///     .expect("Failed to encode text");
+/// let mut buffer = vec![0u8; 1024];
+/// ggwave.decode(&waveform, &mut buffer).expect("Failed to decode");
+///
+/// let metrics = ggwave.metrics();
+/// println!(
+///     "{} calls, {} messages, {:?} per call",
+///     metrics.decode_calls,
+///     metrics.messages_decoded,
+///     metrics.average_decode_time()
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecodeMetrics {
+    /// Number of calls into the C decoder
+    pub decode_calls: u64,
+    /// Number of calls that completed a message
+    pub messages_decoded: u64,
+    /// Time spent in the C decoder, in nanoseconds
+    pub total_decode_nanos: u64,
+    /// Number of input samples passed to the C decoder
+    pub samples_processed: u64,
+}
+
+impl DecodeMetrics {
+    /// Time spent in the C decoder
+    pub fn total_decode_time(&self) -> Duration {
+        Duration::from_nanos(self.total_decode_nanos)
+    }
+
+    /// Mean time of a call into the C decoder, or zero before the first call
+    pub fn average_decode_time(&self) -> Duration {
+        match self.decode_calls {
+            0 => Duration::ZERO,
+            calls => Duration::from_nanos(self.total_decode_nanos / calls),
+        }
+    }
+
+    /// Count a call into the C decoder
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - Number of input samples passed to the decoder
+    /// * `result` - The value returned by the decoder, positive for a message
+    /// * `elapsed` - Time spent in the call
+    pub(crate) fn record(&mut self, samples: usize, result: i32, elapsed: Duration) {
+        self.decode_calls += 1;
+        if result > 0 {
+            self.messages_decoded += 1;
+        }
+        self.total_decode_nanos = self
+            .total_decode_nanos
+            .saturating_add(elapsed.as_nanos().try_into().unwrap_or(u64::MAX));
+        self.samples_processed += samples as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Error, GGWave, constants, convert, protocols};
+
+    #[test]
+    fn test_decode_metrics() {
+        let _serial = crate::tests::serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        assert_eq!(ggwave.metrics(), DecodeMetrics::default());
+        assert_eq!(ggwave.metrics().average_decode_time(), Duration::ZERO);
+
+        let waveform = ggwave
+            .encode("Counted", protocols::AUDIBLE_FAST, 50)
+            .unwrap();
+        let samples =
+            waveform.len() / convert::sample_size(ggwave.parameters().sampleFormatInp).unwrap();
+        let mut buffer = vec![0u8; constants::MAX_DATA_SIZE];
+
+        for _ in 0..3 {
+            assert_eq!(ggwave.decode(&waveform, &mut buffer).unwrap(), "Counted");
+        }
+        let metrics = ggwave.metrics();
+        assert_eq!(metrics.decode_calls, 3);
+        assert_eq!(metrics.messages_decoded, 3);
+        assert_eq!(metrics.samples_processed, 3 * samples as u64);
+        assert!(metrics.total_decode_nanos > 0);
+        assert_eq!(
+            metrics.total_decode_time(),
+            Duration::from_nanos(metrics.total_decode_nanos)
+        );
+
+        // Calls that find nothing are counted, but not as messages
+        let silence = vec![0u8; waveform.len()];
+        assert!(matches!(
+            ggwave.decode(&silence, &mut buffer),
+            Err(Error::NoMessage)
+        ));
+        let frame_len = ggwave.parameters().samplesPerFrame as usize * (waveform.len() / samples);
+        let mut streamed = 0;
+        for frame in waveform.chunks(frame_len) {
+            if let Ok(Some(_)) = ggwave.process_audio_chunk(frame, &mut buffer) {
+                streamed += 1;
+            }
+        }
+
+        let advanced = ggwave.metrics();
+        let frames = waveform.len().div_ceil(frame_len) as u64;
+        assert_eq!(advanced.decode_calls, 4 + frames);
+        assert_eq!(advanced.messages_decoded, 3 + streamed);
+        assert_eq!(advanced.samples_processed, 5 * samples as u64);
+        assert!(advanced.total_decode_nanos >= metrics.total_decode_nanos);
+    }
+}
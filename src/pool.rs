@@ -0,0 +1,242 @@
+//! Pool of GGWave instances
+//!
+//! The C library supports at most [`MAX_INSTANCES`] live instances per
+//! process and fails to initialize any beyond that. A [`GGWavePool`] creates
+//! instances on demand up to a fixed capacity, hands them out as
+//! [`PooledGGWave`] guards and takes them back when the guards are dropped, so
+//! callers wait for (or are told about) an exhausted pool instead of hitting an
+//! initialization failure.
+//!
+//! # Examples
+//!
+//! ```
+//! use ggwave_rs::{GGWave, GGWavePool, protocols};
+//!
+//! let pool = GGWavePool::new(GGWave::default_parameters(), 2)
+//!     .expect("Failed to create pool");
+//!
+//! let ggwave = pool.acquire().expect("Failed to acquire instance");
+//! let waveform = ggwave.encode("Hello, pool!", protocols::AUDIBLE_FAST, 50)
+//!     .expect("Failed to encode text");
+//! ```
+
+use std::ops::Deref;
+use std::sync::{Condvar, Mutex, MutexGuard};
+
+use crate::{Error, GGWave, MAX_INSTANCES, Parameters, Result};
+
+/// A fixed-capacity pool of GGWave instances sharing the same parameters
+pub struct GGWavePool {
+    params: Parameters,
+    capacity: usize,
+    state: Mutex<PoolState>,
+    released: Condvar,
+}
+
+/// Mutable pool state guarded by the pool mutex
+struct PoolState {
+    /// Instances that are created and not currently handed out
+    idle: Vec<GGWave>,
+    /// Number of instances created by the pool, idle or in use
+    live: usize,
+}
+
+impl GGWavePool {
+    /// Create a pool that holds up to `capacity` instances
+    ///
+    /// Instances are created lazily on the first `acquire` calls that find no
+    /// idle instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The parameters used for every instance in the pool
+    /// * `capacity` - Maximum number of instances (1 to [`MAX_INSTANCES`])
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the pool, or `InvalidParameter` if the capacity is out of range
+    pub fn new(params: Parameters, capacity: usize) -> Result<Self> {
+        if capacity == 0 || capacity > MAX_INSTANCES {
+            return Err(Error::InvalidParameter(
+                "Pool capacity must be between 1 and MAX_INSTANCES",
+            ));
+        }
+
+        Ok(Self {
+            params,
+            capacity,
+            state: Mutex::new(PoolState {
+                idle: Vec::with_capacity(capacity),
+                live: 0,
+            }),
+            released: Condvar::new(),
+        })
+    }
+
+    /// Create a pool that can hold every instance the process may have
+    ///
+    /// The capacity is [`MAX_INSTANCES`]. Instances created outside the pool
+    /// still use up slots, so `acquire` can then fail to create one.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The parameters used for every instance in the pool
+    pub fn with_max_capacity(params: Parameters) -> Self {
+        Self::new(params, MAX_INSTANCES).expect("MAX_INSTANCES is a valid capacity")
+    }
+
+    /// Maximum number of instances the pool will create
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of instances currently handed out
+    pub fn in_use(&self) -> usize {
+        let state = self.lock();
+        state.live - state.idle.len()
+    }
+
+    /// Acquire an instance, blocking until one is available
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a guard that returns the instance to the pool on drop,
+    /// or `InstanceLimitReached` if a new instance could not be created because
+    /// instances outside the pool use up the slots
+    pub fn acquire(&self) -> Result<PooledGGWave<'_>> {
+        let mut state = self.lock();
+
+        loop {
+            if let Some(ggwave) = self.take(&mut state)? {
+                return Ok(PooledGGWave::new(self, ggwave));
+            }
+
+            state = self
+                .released
+                .wait(state)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+    }
+
+    /// Acquire an instance without blocking
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a guard that returns the instance to the pool on drop,
+    /// or `PoolExhausted` if every instance is in use
+    pub fn try_acquire(&self) -> Result<PooledGGWave<'_>> {
+        let mut state = self.lock();
+
+        match self.take(&mut state)? {
+            Some(ggwave) => Ok(PooledGGWave::new(self, ggwave)),
+            None => Err(Error::PoolExhausted {
+                capacity: self.capacity,
+            }),
+        }
+    }
+
+    /// Take an idle instance or create a new one if the pool is not full
+    fn take(&self, state: &mut PoolState) -> Result<Option<GGWave>> {
+        if let Some(ggwave) = state.idle.pop() {
+            return Ok(Some(ggwave));
+        }
+
+        if state.live >= self.capacity {
+            return Ok(None);
+        }
+
+        let ggwave = GGWave::new_with_params(self.params)?;
+        state.live += 1;
+        Ok(Some(ggwave))
+    }
+
+    /// Return an instance to the pool and wake one waiter
+    fn release(&self, ggwave: GGWave) {
+        self.lock().idle.push(ggwave);
+        self.released.notify_one();
+    }
+
+    /// Lock the pool state, ignoring poisoning since the state stays consistent
+    fn lock(&self) -> MutexGuard<'_, PoolState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// An instance borrowed from a [`GGWavePool`]
+///
+/// Dereferences to [`GGWave`] and returns the instance to the pool when dropped.
+pub struct PooledGGWave<'a> {
+    pool: &'a GGWavePool,
+    ggwave: Option<GGWave>,
+}
+
+impl<'a> PooledGGWave<'a> {
+    fn new(pool: &'a GGWavePool, ggwave: GGWave) -> Self {
+        Self {
+            pool,
+            ggwave: Some(ggwave),
+        }
+    }
+}
+
+impl Deref for PooledGGWave<'_> {
+    type Target = GGWave;
+
+    fn deref(&self) -> &GGWave {
+        self.ggwave
+            .as_ref()
+            .expect("pooled instance is only taken on drop")
+    }
+}
+
+impl Drop for PooledGGWave<'_> {
+    fn drop(&mut self) {
+        if let Some(ggwave) = self.ggwave.take() {
+            self.pool.release(ggwave);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_pool_exhaustion() {
+        let _serial = crate::tests::serial();
+        let pool = GGWavePool::with_max_capacity(GGWave::default_parameters());
+        assert_eq!(pool.capacity(), MAX_INSTANCES);
+
+        let mut guards: Vec<_> = (0..4)
+            .map(|_| pool.acquire().expect("Failed to acquire instance"))
+            .collect();
+        assert_eq!(pool.in_use(), 4);
+
+        assert!(matches!(
+            pool.try_acquire(),
+            Err(Error::PoolExhausted { capacity: 4 })
+        ));
+
+        let pool = &pool;
+        thread::scope(|scope| {
+            let (tx, rx) = mpsc::channel();
+            scope.spawn(move || {
+                let ggwave = pool.acquire().expect("Failed to acquire instance");
+                tx.send(ggwave.parameters().sampleRate).unwrap();
+            });
+
+            // The fifth acquire blocks until an instance is released
+            assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+            guards.pop();
+            assert!(rx.recv_timeout(Duration::from_secs(5)).is_ok());
+        });
+
+        assert_eq!(pool.in_use(), 3);
+    }
+}
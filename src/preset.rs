@@ -0,0 +1,145 @@
+//! Known-good parameter sets for common uses
+//!
+//! Sample rate, samples per frame and sample formats have to agree with the
+//! audio devices and with the other end of a transmission, and most
+//! combinations fail in ways that are hard to diagnose. A [`Preset`] picks
+//! values that are known to work for one kind of application.
+
+use crate::{Parameters, ProtocolId, protocols, sample_formats};
+
+/// A parameter set for a common kind of application
+///
+/// Applied with [`GGWaveBuilder::preset`](crate::GGWaveBuilder::preset).
+/// Every preset runs at 48 kHz, which all common sound cards support, and
+/// leaves the other builder settings as they are. Both ends of a transmission
+/// must use the same preset, or at least the same processing sample rate and
+/// samples per frame.
+///
+/// # Examples
+///
+/// ```
+/// use ggwave_rs::{GGWaveBuilder, Preset};
+///
+/// let ggwave = GGWaveBuilder::new()
+///     .preset(Preset::Chat)
+///     .build()
+///     .expect("Failed to initialize GGWave");
+/// let waveform = ggwave
+///     .encode("Hello, World!", Preset::Chat.protocol(), 50)
+///     .expect("Failed to encode text");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Preset {
+    /// Short text messages between speakers and microphones in a room
+    ///
+    /// Optimizes for reliability: 1024 samples per frame, `F32` input as
+    /// captured by microphones and `I16` output as played by most players.
+    /// These are the rates and formats of
+    /// [`GGWaveBuilder::rx_preset`](crate::GGWaveBuilder::rx_preset).
+    Chat,
+    /// Large payloads split with `encode_chunked` or `encode_sequence`
+    ///
+    /// Optimizes for throughput: uses the fastest audible protocol and `F32`
+    /// on both sides so no precision is lost. Works best over a short, quiet
+    /// path or a cable.
+    FileTransfer,
+    /// Inaudible announcements, such as a device repeating its identifier
+    ///
+    /// Optimizes for staying out of earshot: uses the most robust ultrasound
+    /// protocol, whose tones sit between 15 and 20 kHz. Cheap speakers and
+    /// microphones often roll off in that range, so check the devices with
+    /// [`GGWave::assess_device`](crate::GGWave::assess_device).
+    UltrasoundBeacon,
+    /// Interactive use where a message should be reported as soon as it ends
+    ///
+    /// Optimizes for latency: 512 samples per frame halves the time between
+    /// decoder updates, and `F32` on both sides avoids conversions in audio
+    /// callbacks. Frequency resolution is halved, so it is less robust to
+    /// noise than [`Preset::Chat`].
+    LowLatency,
+}
+
+impl Preset {
+    /// Every preset
+    pub const ALL: [Self; 4] = [
+        Self::Chat,
+        Self::FileTransfer,
+        Self::UltrasoundBeacon,
+        Self::LowLatency,
+    ];
+
+    /// The protocol the preset is tuned for
+    pub const fn protocol(self) -> ProtocolId {
+        match self {
+            Self::Chat => protocols::AUDIBLE_FAST,
+            Self::FileTransfer | Self::LowLatency => protocols::AUDIBLE_FASTEST,
+            Self::UltrasoundBeacon => protocols::ULTRASOUND_NORMAL,
+        }
+    }
+
+    /// Set the sample rates, samples per frame and sample formats of `params`
+    pub(crate) fn apply(self, params: &mut Parameters) {
+        let (samples_per_frame, input_format, output_format) = match self {
+            Self::Chat | Self::UltrasoundBeacon => (1024, sample_formats::F32, sample_formats::I16),
+            Self::FileTransfer => (1024, sample_formats::F32, sample_formats::F32),
+            Self::LowLatency => (512, sample_formats::F32, sample_formats::F32),
+        };
+
+        params.sampleRate = 48000.0;
+        params.sampleRateInp = 48000.0;
+        params.sampleRateOut = 48000.0;
+        params.samplesPerFrame = samples_per_frame;
+        params.sampleFormatInp = input_format;
+        params.sampleFormatOut = output_format;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GGWaveBuilder;
+
+    #[test]
+    fn test_presets_round_trip() {
+        let _serial = crate::tests::serial();
+        let text = "Preset round trip";
+        let mut buffer = vec![0u8; 1024];
+
+        for preset in Preset::ALL {
+            let ggwave = GGWaveBuilder::new()
+                .preset(preset)
+                .build()
+                .unwrap_or_else(|e| panic!("Failed to build {:?}: {}", preset, e));
+            let params = ggwave.parameters();
+            assert_eq!(params.sampleRateInp, 48000.0);
+
+            // Encode as captured audio, which may differ from the output format
+            let waveform = ggwave
+                .encode_as_format(text, preset.protocol(), 50, params.sampleFormatInp)
+                .unwrap_or_else(|e| panic!("Failed to encode with {:?}: {}", preset, e));
+            assert_eq!(
+                ggwave.decode(&waveform, &mut buffer).ok(),
+                Some(text),
+                "{:?}",
+                preset
+            );
+        }
+    }
+
+    #[test]
+    fn test_preset_override() {
+        let _serial = crate::tests::serial();
+        let ggwave = GGWaveBuilder::new()
+            .preset(Preset::Chat)
+            .samples_per_frame(512)
+            .output_sample_format(sample_formats::F32)
+            .build()
+            .expect("Failed to initialize GGWave");
+
+        let params = ggwave.parameters();
+        assert_eq!(params.samplesPerFrame, 512);
+        assert_eq!(params.sampleFormatOut, sample_formats::F32);
+        assert_eq!(params.sampleFormatInp, sample_formats::F32);
+        assert_eq!(params.sampleRate, 48000.0);
+    }
+}
@@ -0,0 +1,171 @@
+//! Sets of enabled protocols
+//!
+//! The C library keeps a single process-wide table of enabled rx and tx
+//! protocols, and every instance copies that table when it is created. A
+//! [`ProtocolMask`] describes the protocols an instance should be created with;
+//! the global table is switched to it for the duration of `ggwave_init` and
+//! restored afterwards, so instances with different masks do not interfere.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use crate::{ProtocolId, ggwave_rxToggleProtocol, ggwave_txToggleProtocol, protocols};
+
+/// A set of protocols, one bit per protocol id
+///
+/// # Examples
+///
+/// ```
+/// use ggwave_rs::{ProtocolMask, protocols};
+///
+/// let mask = ProtocolMask::NONE
+///     .with(protocols::AUDIBLE_FAST)
+///     .with(protocols::AUDIBLE_FASTEST);
+///
+/// assert!(mask.contains(protocols::AUDIBLE_FAST));
+/// assert!(!mask.contains(protocols::ULTRASOUND_FAST));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProtocolMask(u32);
+
+impl ProtocolMask {
+    /// No protocols
+    pub const NONE: Self = Self(0);
+
+    /// Every protocol, including the custom ones
+    pub const ALL: Self = Self((1 << protocols::COUNT) - 1);
+
+    /// The built-in protocols, which is what the C library enables by default
+    pub const BUILT_IN: Self = Self((1 << protocols::CUSTOM_0) - 1);
+
+    /// Create a mask from its raw bits, ignoring bits beyond the last protocol
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits & Self::ALL.0)
+    }
+
+    /// The raw bits of the mask, bit `n` standing for protocol id `n`
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Check whether a protocol is in the mask
+    pub const fn contains(self, protocol_id: ProtocolId) -> bool {
+        protocol_id < protocols::COUNT && self.0 & (1 << protocol_id) != 0
+    }
+
+    /// Return the mask with a protocol added
+    pub const fn with(self, protocol_id: ProtocolId) -> Self {
+        Self::from_bits(self.0 | Self::bit(protocol_id))
+    }
+
+    /// Return the mask with a protocol removed
+    pub const fn without(self, protocol_id: ProtocolId) -> Self {
+        Self(self.0 & !Self::bit(protocol_id))
+    }
+
+    /// Add or remove a protocol
+    pub fn set(&mut self, protocol_id: ProtocolId, enabled: bool) {
+        *self = if enabled {
+            self.with(protocol_id)
+        } else {
+            self.without(protocol_id)
+        };
+    }
+
+    /// Iterate over the protocol ids in the mask
+    pub fn iter(self) -> impl Iterator<Item = ProtocolId> {
+        (0..protocols::COUNT).filter(move |&id| self.contains(id))
+    }
+
+    const fn bit(protocol_id: ProtocolId) -> u32 {
+        if protocol_id < protocols::COUNT {
+            1 << protocol_id
+        } else {
+            0
+        }
+    }
+}
+
+impl Default for ProtocolMask {
+    fn default() -> Self {
+        Self::BUILT_IN
+    }
+}
+
+impl FromIterator<ProtocolId> for ProtocolMask {
+    fn from_iter<I: IntoIterator<Item = ProtocolId>>(iter: I) -> Self {
+        iter.into_iter().fold(Self::NONE, Self::with)
+    }
+}
+
+// Mirrors of the global protocol tables, which the C API cannot report
+static RX_PROTOCOLS: AtomicU32 = AtomicU32::new(ProtocolMask::BUILT_IN.0);
+static TX_PROTOCOLS: AtomicU32 = AtomicU32::new(ProtocolMask::BUILT_IN.0);
+
+/// The protocols currently enabled for reception in the global table
+pub(crate) fn global_rx() -> ProtocolMask {
+    ProtocolMask(RX_PROTOCOLS.load(Ordering::SeqCst))
+}
+
+/// The protocols currently enabled for transmission in the global table
+pub(crate) fn global_tx() -> ProtocolMask {
+    ProtocolMask(TX_PROTOCOLS.load(Ordering::SeqCst))
+}
+
+/// Switch the global rx table to `mask`, returning the previous mask
+///
+/// # Safety
+///
+/// The caller must hold the global write lock.
+pub(crate) unsafe fn apply_rx(mask: ProtocolMask) -> ProtocolMask {
+    let previous = global_rx();
+    for id in (0..protocols::COUNT).filter(|&id| mask.contains(id) != previous.contains(id)) {
+        unsafe { ggwave_rxToggleProtocol(id, mask.contains(id) as i32) };
+    }
+    RX_PROTOCOLS.store(mask.0, Ordering::SeqCst);
+    previous
+}
+
+/// Switch the global tx table to `mask`, returning the previous mask
+///
+/// # Safety
+///
+/// The caller must hold the global write lock.
+pub(crate) unsafe fn apply_tx(mask: ProtocolMask) -> ProtocolMask {
+    let previous = global_tx();
+    for id in (0..protocols::COUNT).filter(|&id| mask.contains(id) != previous.contains(id)) {
+        unsafe { ggwave_txToggleProtocol(id, mask.contains(id) as i32) };
+    }
+    TX_PROTOCOLS.store(mask.0, Ordering::SeqCst);
+    previous
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_operations() {
+        let mask: ProtocolMask = [protocols::AUDIBLE_FAST, protocols::DT_FAST]
+            .into_iter()
+            .collect();
+
+        assert!(mask.contains(protocols::AUDIBLE_FAST));
+        assert!(!mask.contains(protocols::AUDIBLE_NORMAL));
+        assert_eq!(
+            mask.iter().collect::<Vec<_>>(),
+            [protocols::AUDIBLE_FAST, protocols::DT_FAST]
+        );
+        assert_eq!(
+            mask.without(protocols::DT_FAST),
+            ProtocolMask::NONE.with(protocols::AUDIBLE_FAST)
+        );
+
+        assert!(!ProtocolMask::ALL.contains(protocols::COUNT));
+        assert_eq!(
+            ProtocolMask::NONE.with(protocols::COUNT),
+            ProtocolMask::NONE
+        );
+        assert!(ProtocolMask::BUILT_IN.contains(protocols::MT_FASTEST));
+        assert!(!ProtocolMask::BUILT_IN.contains(protocols::CUSTOM_0));
+    }
+}
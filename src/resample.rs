@@ -0,0 +1,178 @@
+//! Sample rate conversion for captured audio
+//!
+//! ggwave expects its input at exactly `sampleRateInp`; tones captured at a
+//! different rate land in the wrong frequency bins and never decode. The
+//! [`Resampler`] converts a stream of normalized `f32` samples from one rate to
+//! another using linear interpolation.
+
+use alloc::vec::Vec;
+
+use crate::{Error, Result};
+
+/// Streaming linear-interpolation resampler
+///
+/// The resampler keeps the last input sample and the fractional read position
+/// between calls, so a stream can be fed chunk by chunk with the same result as
+/// resampling it in one go. The output vector passed to
+/// [`process`](Self::process) can be reused to avoid allocating on every chunk.
+///
+/// # Latency and quality
+///
+/// Linear interpolation adds one input sample of latency and is cheap enough
+/// for any real-time callback. It slightly attenuates frequencies near the
+/// Nyquist limit and, when downsampling, does not filter out content above the
+/// new Nyquist frequency. This is harmless for the audible protocols, which sit
+/// well below either limit, but ultrasound protocols lose some margin, so
+/// prefer capturing at the instance's rate when the device allows it.
+///
+/// # Examples
+///
+/// ```
+/// use ggwave_rs::Resampler;
+///
+/// let mut resampler = Resampler::new(44100.0, 48000.0).expect("Invalid rates");
+/// let mut output = Vec::new();
+///
+/// for chunk in [[0.0f32; 441], [0.0f32; 441]] {
+///     output.clear();
+///     resampler.process(&chunk, &mut output);
+///     // `output` holds about 480 samples at 48 kHz
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Resampler {
+    input_rate: f32,
+    output_rate: f32,
+    /// Input samples advanced per output sample
+    step: f64,
+    /// Read position relative to `last`, which sits at index 0
+    position: f64,
+    /// Last input sample of the previous chunk
+    last: Option<f32>,
+}
+
+impl Resampler {
+    /// Create a resampler between two sample rates
+    ///
+    /// # Arguments
+    ///
+    /// * `input_rate` - Sample rate of the incoming audio in Hz
+    /// * `output_rate` - Sample rate to convert to in Hz
+    ///
+    /// # Returns
+    ///
+    /// `Error::InvalidParameter` if either rate is not a positive number
+    pub fn new(input_rate: f32, output_rate: f32) -> Result<Self> {
+        let valid = |rate: f32| rate.is_finite() && rate > 0.0;
+        if !valid(input_rate) || !valid(output_rate) {
+            return Err(Error::InvalidParameter("Sample rates must be positive"));
+        }
+
+        Ok(Self {
+            input_rate,
+            output_rate,
+            step: input_rate as f64 / output_rate as f64,
+            position: 0.0,
+            last: None,
+        })
+    }
+
+    /// Sample rate of the incoming audio in Hz
+    pub fn input_rate(&self) -> f32 {
+        self.input_rate
+    }
+
+    /// Sample rate of the produced audio in Hz
+    pub fn output_rate(&self) -> f32 {
+        self.output_rate
+    }
+
+    /// Resample a chunk of the stream, appending the result to `output`
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The next samples of the stream at the input rate
+    /// * `output` - Vector the resampled samples are appended to
+    ///
+    /// # Returns
+    ///
+    /// The number of samples appended
+    pub fn process(&mut self, input: &[f32], output: &mut Vec<f32>) -> usize {
+        // The sample carried over from the previous chunk sits at index 0
+        let last = self.last;
+        let offset = usize::from(last.is_some());
+        let len = input.len() + offset;
+        let sample = |i: usize| match last {
+            Some(last) if i == 0 => last,
+            _ => input[i - offset],
+        };
+
+        if len == 0 {
+            return 0;
+        }
+
+        let start = output.len();
+        let end = (len - 1) as f64;
+        output.reserve(((end - self.position) / self.step).max(0.0) as usize + 1);
+
+        // Positions past the last sample need the next chunk to interpolate
+        while self.position <= end {
+            let index = self.position as usize;
+            let frac = (self.position - index as f64) as f32;
+            let value = if frac > 0.0 {
+                sample(index) * (1.0 - frac) + sample(index + 1) * frac
+            } else {
+                sample(index)
+            };
+
+            output.push(value);
+            self.position += self.step;
+        }
+
+        self.position -= end;
+        self.last = Some(sample(len - 1));
+
+        output.len() - start
+    }
+
+    /// Forget the stream state, e.g. before resampling an unrelated recording
+    pub fn reset(&mut self) {
+        self.position = 0.0;
+        self.last = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunked_matches_whole() {
+        let input: Vec<f32> = (0..1000).map(|i| (i as f32 * 0.05).sin()).collect();
+
+        let mut whole = Vec::new();
+        Resampler::new(44100.0, 48000.0)
+            .unwrap()
+            .process(&input, &mut whole);
+
+        let mut resampler = Resampler::new(44100.0, 48000.0).unwrap();
+        let mut chunked = Vec::new();
+        for chunk in input.chunks(97) {
+            resampler.process(chunk, &mut chunked);
+        }
+
+        assert_eq!(whole.len(), chunked.len());
+        assert!(
+            whole
+                .iter()
+                .zip(&chunked)
+                .all(|(a, b)| (a - b).abs() < 1e-5)
+        );
+
+        // 1000 samples at 44.1 kHz span 999 sample intervals
+        let expected = (999.0 * 48000.0 / 44100.0) as usize + 1;
+        assert!(whole.len().abs_diff(expected) <= 1);
+
+        assert!(Resampler::new(0.0, 48000.0).is_err());
+    }
+}
@@ -0,0 +1,84 @@
+//! Layout of multi-part transmissions
+//!
+//! [`GGWave::encode_sequence`](crate::GGWave::encode_sequence) encodes several
+//! payloads back to back into one waveform and describes the result with a
+//! [`SequenceManifest`], which a cooperating receiver can use to check that it
+//! got every part.
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::checksum::Crc32;
+
+/// Position of one part of a sequence in the combined waveform
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SequencePart {
+    /// Offset in bytes of the part's waveform in the combined waveform
+    pub offset: usize,
+    /// Length in bytes of the part's waveform
+    pub length: usize,
+    /// Length in bytes of the part's payload
+    pub payload_length: usize,
+}
+
+impl SequencePart {
+    /// Byte range of the part's waveform in the combined waveform
+    pub fn range(&self) -> Range<usize> {
+        self.offset..self.offset + self.length
+    }
+}
+
+/// Description of a waveform produced by `encode_sequence`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceManifest {
+    /// The parts in transmission order
+    pub parts: Vec<SequencePart>,
+    /// CRC-32 (IEEE) of all payloads concatenated in order
+    pub checksum: u32,
+}
+
+impl SequenceManifest {
+    /// Number of parts in the sequence
+    pub fn len(&self) -> usize {
+        self.parts.len()
+    }
+
+    /// Check whether the sequence has no parts
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty()
+    }
+
+    /// Total length in bytes of the combined waveform
+    pub fn waveform_length(&self) -> usize {
+        self.parts
+            .last()
+            .map_or(0, |part| part.offset + part.length)
+    }
+
+    /// Check that received payloads are the complete, intact sequence
+    ///
+    /// # Arguments
+    ///
+    /// * `payloads` - The decoded payloads in transmission order
+    ///
+    /// # Returns
+    ///
+    /// `true` if there is one payload per part, each of the expected length,
+    /// and their combined checksum matches
+    pub fn verify<P: AsRef<[u8]>>(&self, payloads: &[P]) -> bool {
+        if payloads.len() != self.parts.len() {
+            return false;
+        }
+
+        let mut crc = Crc32::new();
+        for (payload, part) in payloads.iter().zip(&self.parts) {
+            let payload = payload.as_ref();
+            if payload.len() != part.payload_length {
+                return false;
+            }
+            crc.update(payload);
+        }
+
+        crc.finish() == self.checksum
+    }
+}
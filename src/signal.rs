@@ -0,0 +1,350 @@
+//! Lightweight signal analysis on normalized `f32` samples
+//!
+//! These helpers work on frame energy only and are used to locate the part of
+//! a recording that actually carries a transmission.
+
+use std::ops::Range;
+
+use crate::constants;
+
+/// Ratio between a frame's energy and the noise floor for it to count as signal
+const ONSET_RATIO: f32 = 4.0;
+
+/// Minimum RMS for a frame to count as signal, regardless of the noise floor
+const MIN_SIGNAL_RMS: f32 = 1e-4;
+
+/// Fraction of a transmission's typical frame energy that its first frame has
+/// to reach
+const ONSET_LEVEL_RATIO: f32 = 0.1;
+
+/// Frames on either side of an estimated onset searched for the actual one
+const ONSET_SEARCH_FRAMES: usize = 2;
+
+/// Root mean square of a block of samples
+pub(crate) fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+
+    let sum: f32 = samples.iter().map(|s| s * s).sum();
+    (sum / samples.len() as f32).sqrt()
+}
+
+/// RMS of each consecutive frame of `frame_len` samples
+///
+/// A trailing partial frame is included.
+pub(crate) fn frame_rms(samples: &[f32], frame_len: usize) -> Vec<f32> {
+    samples.chunks(frame_len.max(1)).map(rms).collect()
+}
+
+/// Estimate the noise floor as the 10th percentile of the frame energies
+pub(crate) fn noise_floor(energies: &[f32]) -> f32 {
+    if energies.is_empty() {
+        return 0.0;
+    }
+
+    let mut sorted = energies.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    sorted[sorted.len() / 10]
+}
+
+/// Find the range of samples that rises clearly above the noise floor
+///
+/// The sound markers that open and close every transmission are much louder
+/// than the background of a typical capture, so the first and last frames
+/// well above the noise floor bound the message. The returned range is
+/// aligned to frame boundaries.
+///
+/// Returns `None` when no frame stands out, e.g. for pure silence or when the
+/// whole buffer is signal.
+pub(crate) fn trim_silence(samples: &[f32], frame_len: usize) -> Option<Range<usize>> {
+    let frame_len = frame_len.max(1);
+    let energies = frame_rms(samples, frame_len);
+    let threshold = (noise_floor(&energies) * ONSET_RATIO).max(MIN_SIGNAL_RMS);
+
+    let first = energies.iter().position(|&e| e > threshold)?;
+    let last = energies.iter().rposition(|&e| e > threshold)?;
+
+    Some(first * frame_len..((last + 1) * frame_len).min(samples.len()))
+}
+
+/// Find where a transmission starts, given an estimate to within a couple of
+/// frames
+///
+/// The transmission is taken to run from `estimate` to the end of `samples`,
+/// and its median frame energy sets the level its first frame has to reach, so
+/// background noise well below the transmission does not count. The returned
+/// offset is aligned to frame boundaries.
+pub(crate) fn onset(samples: &[f32], frame_len: usize, estimate: usize) -> usize {
+    let frame_len = frame_len.max(1);
+    let energies = frame_rms(samples, frame_len);
+    let estimate = (estimate / frame_len).min(energies.len());
+
+    let mut levels = energies[estimate..].to_vec();
+    if levels.is_empty() {
+        return estimate * frame_len;
+    }
+    levels.sort_by(f32::total_cmp);
+    let threshold = levels[levels.len() / 2] * ONSET_LEVEL_RATIO;
+
+    let last = (estimate + ONSET_SEARCH_FRAMES).min(energies.len() - 1);
+    let first = (estimate.saturating_sub(ONSET_SEARCH_FRAMES)..=last)
+        .find(|&i| energies[i] > threshold)
+        .unwrap_or(estimate);
+    first * frame_len
+}
+
+/// Amplitude of a single frequency component, using the Goertzel algorithm
+///
+/// The result is normalized so that a pure sine of amplitude `A` at
+/// `frequency` yields `A`.
+pub(crate) fn goertzel(samples: &[f32], frequency: f32, sample_rate: f32) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+
+    let coeff = 2.0 * (2.0 * std::f32::consts::PI * frequency / sample_rate).cos();
+    let (mut s1, mut s2) = (0.0f32, 0.0f32);
+
+    for &x in samples {
+        let s0 = x + coeff * s1 - s2;
+        s2 = s1;
+        s1 = s0;
+    }
+
+    let power = (s1 * s1 + s2 * s2 - coeff * s1 * s2).max(0.0);
+    2.0 * power.sqrt() / samples.len() as f32
+}
+
+/// Ratio between a bin's power and the band's noise floor for it to count as
+/// a tone
+const TONE_RATIO: f32 = 4.0;
+
+/// Tone-to-noise ratios in dB mapped to a confidence of 0.0 and 1.0; white
+/// noise alone measures about 7 dB
+const CONFIDENCE_DB: Range<f32> = 10.0..30.0;
+
+/// Percentile of the frame scores reported as the confidence of a transmission
+const CONFIDENCE_PERCENTILE: usize = 75;
+
+/// Estimate how cleanly a transmission's tones stand out from the noise
+///
+/// Each frame of a transmission holds a few tones on the bins of the
+/// protocol's band, while most bins only hold noise. For each frame the mean
+/// power of the bins well above the median bin, which estimates the noise
+/// floor, is compared to that median, and the ratio in dB is mapped to 0.0-1.0
+/// through `CONFIDENCE_DB`. Frames that straddle a change of tones smear them
+/// over the band even in a clean transmission, so the score is the
+/// `CONFIDENCE_PERCENTILE`th percentile of the frames rather than their mean.
+///
+/// `samples` should start at the beginning of the transmission, so that the
+/// frames line up with the tones.
+pub(crate) fn tone_confidence(
+    samples: &[f32],
+    band: Range<f32>,
+    bin_width: f32,
+    sample_rate: f32,
+) -> f32 {
+    if bin_width <= 0.0 {
+        return 0.0;
+    }
+
+    let frame_len = (sample_rate / bin_width).round() as usize;
+    let bins = ((band.end - band.start) / bin_width).round() as usize;
+    if frame_len == 0 || bins == 0 {
+        return 0.0;
+    }
+
+    let mut scores = Vec::new();
+    for frame in samples.chunks_exact(frame_len) {
+        if rms(frame) < MIN_SIGNAL_RMS {
+            continue;
+        }
+
+        let mut powers: Vec<f32> = (0..bins)
+            .map(|bin| {
+                let amplitude = goertzel(frame, band.start + bin as f32 * bin_width, sample_rate);
+                amplitude * amplitude
+            })
+            .collect();
+        powers.sort_by(f32::total_cmp);
+
+        // Clean synthetic tones leave the other bins empty
+        let floor = powers[bins / 2].max(f32::MIN_POSITIVE);
+        let tones: Vec<f32> = powers
+            .into_iter()
+            .filter(|&power| power > floor * TONE_RATIO)
+            .collect();
+
+        if tones.is_empty() {
+            scores.push(0.0);
+            continue;
+        }
+
+        let ratio = tones.iter().sum::<f32>() / tones.len() as f32 / floor;
+        let db = 10.0 * ratio.log10();
+        scores.push(
+            ((db - CONFIDENCE_DB.start) / (CONFIDENCE_DB.end - CONFIDENCE_DB.start))
+                .clamp(0.0, 1.0),
+        );
+    }
+
+    if scores.is_empty() {
+        return 0.0;
+    }
+    scores.sort_by(f32::total_cmp);
+    scores[scores.len() * CONFIDENCE_PERCENTILE / 100]
+}
+
+/// Number of bits in each sound marker, one pair of adjacent bins per bit
+const MARKER_BITS: usize = 16;
+
+/// Number of marker bits ggwave needs to see, more than 3/4 of them
+const MARKER_BITS_REQUIRED: usize = 13;
+
+/// Consecutive frames that have to pass as a sound marker for a recording to
+/// count as holding one, half the length of a marker
+const MARKER_RUN_FRAMES: usize = constants::DEFAULT_MARKER_FRAMES / 2;
+
+/// Factor between the highest marker ratio seen in noise and the recommended
+/// sound marker threshold
+const MARKER_MARGIN: f32 = 1.5;
+
+/// Lowest recommended sound marker threshold, the crate's default
+const MIN_MARKER_THRESHOLD: f32 = constants::DEFAULT_SOUND_MARKER_THRESHOLD;
+
+/// Highest recommended sound marker threshold
+const MAX_MARKER_THRESHOLD: f32 = 10.0;
+
+/// Layouts a sound marker can take, as (lower bin loud on the first bit,
+/// loud bin alternating from bit to bit)
+///
+/// Start and end markers are loud on opposite bins of each pair, and
+/// depending on the ggwave version the loud bin is either the same for every
+/// bit or alternates.
+const MARKER_LAYOUTS: [(bool, bool); 4] =
+    [(true, false), (false, false), (true, true), (false, true)];
+
+/// Lowest threshold at which `frame` passes as a sound marker whose first bin
+/// is at `start` Hz, in whichever layout fits it best
+///
+/// For every bit of a sound marker, ggwave compares the power of two adjacent
+/// bins and counts the bit when the loud one exceeds the other by the
+/// threshold; a marker is detected once `MARKER_BITS_REQUIRED` bits count.
+fn marker_score(frame: &[f32], start: f32, bin_width: f32, sample_rate: f32) -> f32 {
+    let ratios: [f32; MARKER_BITS] = core::array::from_fn(|bit| {
+        let frequency = start + (2 * bit) as f32 * bin_width;
+        let lower = goertzel(frame, frequency, sample_rate).powi(2);
+        let upper = goertzel(frame, frequency + bin_width, sample_rate).powi(2);
+        lower.max(f32::MIN_POSITIVE) / upper.max(f32::MIN_POSITIVE)
+    });
+
+    MARKER_LAYOUTS
+        .iter()
+        .map(|&(lower_first, alternating)| {
+            let mut oriented: [f32; MARKER_BITS] = core::array::from_fn(|bit| {
+                if lower_first != (alternating && !bit.is_multiple_of(2)) {
+                    ratios[bit]
+                } else {
+                    ratios[bit].recip()
+                }
+            });
+            oriented.sort_by(f32::total_cmp);
+            oriented[MARKER_BITS - MARKER_BITS_REQUIRED]
+        })
+        .fold(0.0, f32::max)
+}
+
+/// Best marker score of each frame of `samples`, or `None` for a frame
+/// without any sound
+///
+/// Frames are `sample_rate / bin_width` samples long, the length of a
+/// decoder frame at `sample_rate`, and a trailing partial frame is skipped.
+/// `marker_starts` are the frequencies of the first marker bin of each
+/// protocol.
+fn marker_scores<'a>(
+    samples: &'a [f32],
+    marker_starts: &'a [f32],
+    bin_width: f32,
+    sample_rate: f32,
+) -> impl Iterator<Item = Option<f32>> + 'a {
+    let frame_len = if bin_width > 0.0 {
+        (sample_rate / bin_width).round() as usize
+    } else {
+        0
+    };
+
+    // A bad bin width gives no frames rather than a zero chunk size
+    let samples = if frame_len == 0 {
+        &samples[..0]
+    } else {
+        samples
+    };
+
+    samples.chunks_exact(frame_len.max(1)).map(move |frame| {
+        (rms(frame) >= MIN_SIGNAL_RMS).then(|| {
+            marker_starts
+                .iter()
+                .map(|&start| marker_score(frame, start, bin_width, sample_rate))
+                .fold(0.0, f32::max)
+        })
+    })
+}
+
+/// Recommend a sound marker threshold for a recording of background noise
+///
+/// For each frame of `samples`, this finds the lowest threshold at which the
+/// noise alone would pass as a sound marker, and the highest of these, times
+/// `MARKER_MARGIN`, is recommended. The result is clamped to
+/// `MIN_MARKER_THRESHOLD` and `MAX_MARKER_THRESHOLD`.
+///
+/// Returns `None` if no frame of `samples` holds any sound.
+pub(crate) fn recommend_marker_threshold(
+    samples: &[f32],
+    marker_starts: &[f32],
+    bin_width: f32,
+    sample_rate: f32,
+) -> Option<f32> {
+    marker_scores(samples, marker_starts, bin_width, sample_rate)
+        .flatten()
+        .reduce(f32::max)
+        .map(|ratio| (ratio * MARKER_MARGIN).clamp(MIN_MARKER_THRESHOLD, MAX_MARKER_THRESHOLD))
+}
+
+/// Check whether `samples` hold a sound marker at `threshold`
+///
+/// Only the marker bins are measured, so this is far cheaper than decoding.
+/// A marker lasts `DEFAULT_MARKER_FRAMES` frames, so frames that are not
+/// aligned with the transmission still see whole frames of it, while noise
+/// that passes for a marker in one frame does not keep doing so for
+/// `MARKER_RUN_FRAMES` frames in a row.
+pub(crate) fn contains_marker(
+    samples: &[f32],
+    marker_starts: &[f32],
+    bin_width: f32,
+    sample_rate: f32,
+    threshold: f32,
+) -> bool {
+    let mut run = 0;
+    marker_scores(samples, marker_starts, bin_width, sample_rate).any(|score| {
+        run = if score.is_some_and(|score| score > threshold) {
+            run + 1
+        } else {
+            0
+        };
+        run >= MARKER_RUN_FRAMES
+    })
+}
+
+/// Fraction of samples at full scale above which a recording counts as clipped
+const CLIPPED_FRACTION: f32 = 0.01;
+
+/// Check whether a recording was saturated on its way in
+///
+/// A few samples may touch full scale in a loud but clean recording, so it
+/// only counts as clipped once more than `CLIPPED_FRACTION` of the samples are
+/// at or beyond ±1.0.
+pub(crate) fn is_clipped(samples: &[f32]) -> bool {
+    let clipped = samples.iter().filter(|s| s.abs() >= 1.0).count();
+    clipped as f32 > samples.len() as f32 * CLIPPED_FRACTION
+}
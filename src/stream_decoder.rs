@@ -0,0 +1,457 @@
+//! Frame-aligned decoding of a live sample stream
+//!
+//! ggwave keeps the state of a message being received inside the instance and
+//! expects to be fed whole frames. A [`StreamDecoder`] takes samples in chunks
+//! of any size, cuts them into frames and keeps every buffer it needs, so the
+//! steady state of a real-time capture loop does not allocate.
+
+use std::collections::VecDeque;
+
+use crate::{DecodeResult, Error, GGWave, Result, constants, convert, signal};
+
+/// Pull-style decoder for a continuous stream of normalized `f32` samples
+///
+/// The decoder owns its [`GGWave`] instance, a partial-frame buffer, a
+/// reusable payload buffer and a history of the last
+/// [`GGWave::max_rx_duration_frames`] frames. Samples are handed to the C
+/// library one frame at a time, as soon as a whole frame has accumulated.
+///
+/// Nothing is allocated while listening. When a message is decoded, the
+/// history is used to work out which protocol carried it, as
+/// [`GGWave::decode_detailed`] does.
+///
+/// # Examples
+///
+/// ```
+/// use ggwave_rs::{GGWave, StreamDecoder};
+///
+/// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+/// let mut decoder = StreamDecoder::new(ggwave).expect("Failed to create decoder");
+///
+/// // In a real application these chunks come from the microphone callback
+/// for chunk in [[0.0f32; 480], [0.0f32; 480]] {
+///     if let Some(message) = decoder.push(&chunk).expect("Failed to decode") {
+///         println!("Received: {}", message.text);
+///     }
+/// }
+/// ```
+pub struct StreamDecoder {
+    ggwave: GGWave,
+    frames: FrameDecoder,
+}
+
+impl StreamDecoder {
+    /// Create a decoder that feeds a stream into `ggwave`
+    ///
+    /// # Arguments
+    ///
+    /// * `ggwave` - The instance to decode with; it must be able to receive
+    ///
+    /// # Returns
+    ///
+    /// `Error::InvalidParameter` if the instance cannot receive
+    pub fn new(ggwave: GGWave) -> Result<Self> {
+        Ok(Self {
+            frames: FrameDecoder::new(&ggwave)?,
+            ggwave,
+        })
+    }
+
+    /// The instance used for decoding
+    pub fn ggwave(&self) -> &GGWave {
+        &self.ggwave
+    }
+
+    /// Start a new session, discarding any partially received message
+    ///
+    /// Clears the buffered samples and calls [`GGWave::reset_rx_state`], so
+    /// audio pushed before the reset cannot contribute to a message decoded
+    /// after it. Offsets of later messages count from the reset.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating whether the decoder state could be reset
+    pub fn reset(&mut self) -> Result<()> {
+        self.frames.clear();
+        self.ggwave.reset_rx_state()
+    }
+
+    /// Only report messages whose checksum verifies
+    ///
+    /// During always-on listening ggwave now and then decodes noise into a
+    /// short garbage payload. With the checksum required, payloads that do not
+    /// carry a valid CRC-32 are dropped silently, so only messages the sender
+    /// encoded with [`GGWave::encode_checked`] are reported, with the checksum
+    /// stripped from their text. Leave it off to receive plain [`GGWave::encode`]
+    /// transmissions, which would all be dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to drop payloads without a valid checksum
+    pub fn require_checksum(mut self, enabled: bool) -> Self {
+        self.frames.checked = enabled;
+        self
+    }
+
+    /// Take back the instance, dropping any partially received message
+    pub fn into_inner(self) -> GGWave {
+        self.ggwave
+    }
+
+    /// Spectrum of the last whole frame handed to the decoder
+    ///
+    /// # Returns
+    ///
+    /// The spectrum, or `None` before the first frame has been processed
+    #[cfg(feature = "analysis")]
+    pub fn last_rx_spectrum(&self) -> Option<crate::analysis::Spectrum> {
+        let history = &self.frames.history;
+        let start = history.len().checked_sub(self.frames.frame_len)?;
+        let frame: Vec<f32> = history.range(start..).copied().collect();
+        self.ggwave.frame_spectrum(&frame)
+    }
+
+    /// Feed the next samples of the stream
+    ///
+    /// Chunks can have any length. At most one message is returned per call,
+    /// which is never a limitation for chunks shorter than a transmission, such
+    /// as those delivered by audio callbacks.
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - Normalized samples at the instance's input sample rate
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the message completed by these samples, if any
+    pub fn push(&mut self, samples: &[f32]) -> Result<Option<DecodeResult>> {
+        let mut decoded = None;
+        let mut rest = samples;
+
+        while !rest.is_empty() {
+            let frames = &mut self.frames;
+            rest = frames.fill(rest);
+
+            if !frames.is_frame_full() {
+                break;
+            }
+
+            let length = frames.process_frame(&self.ggwave)?;
+            if decoded.is_none() && length > 0 {
+                decoded = Some(frames.identify(&self.ggwave, length)?);
+            }
+        }
+
+        Ok(decoded)
+    }
+}
+
+/// Iterator over the messages in a stream of samples
+///
+/// Returned by [`GGWave::decode_iter`]. Samples are pulled from the underlying
+/// iterator one frame at a time, only as far as needed to find the next
+/// message. A partial frame left when the samples run out is not decoded.
+pub struct DecodeIter<'a, I> {
+    ggwave: &'a GGWave,
+    samples: I,
+    frames: Option<FrameDecoder>,
+    /// Why the frame decoder could not be set up, reported by the first call
+    error: Option<Error>,
+}
+
+impl<'a, I: Iterator<Item = f32>> DecodeIter<'a, I> {
+    pub(crate) fn new(ggwave: &'a GGWave, samples: I) -> Self {
+        let (frames, error) = match FrameDecoder::new(ggwave) {
+            Ok(frames) => (Some(frames), None),
+            Err(e) => (None, Some(e)),
+        };
+
+        Self {
+            ggwave,
+            samples,
+            frames,
+            error,
+        }
+    }
+}
+
+impl<I> DecodeIter<'_, I> {
+    /// Only yield messages whose checksum verifies
+    ///
+    /// Payloads without a valid CRC-32 are skipped, as with
+    /// [`StreamDecoder::require_checksum`]; the sender must use
+    /// [`GGWave::encode_checked`].
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to skip payloads without a valid checksum
+    pub fn require_checksum(mut self, enabled: bool) -> Self {
+        if let Some(frames) = &mut self.frames {
+            frames.checked = enabled;
+        }
+        self
+    }
+}
+
+impl<I: Iterator<Item = f32>> Iterator for DecodeIter<'_, I> {
+    type Item = Result<DecodeResult>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Some(frames) = &mut self.frames else {
+            return self.error.take().map(Err);
+        };
+
+        loop {
+            while !frames.is_frame_full() {
+                frames.pending.push(self.samples.next()?);
+            }
+
+            match frames.process_frame(self.ggwave) {
+                Ok(0) => continue,
+                Ok(length) => return Some(frames.identify(self.ggwave, length)),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Buffers that cut a stream into frames and keep what is needed to describe
+/// a decoded message
+pub(crate) struct FrameDecoder {
+    frame_len: usize,
+    /// Samples of the frame being accumulated
+    pending: Vec<f32>,
+    /// The current frame in the instance's input format
+    frame_bytes: Vec<u8>,
+    payload: Vec<u8>,
+    /// The most recent samples, long enough to hold a whole transmission
+    history: VecDeque<f32>,
+    /// Number of samples handed to the decoder since the start of the stream
+    position: usize,
+    /// Drop payloads that fail the checksum of [`GGWave::encode_checked`]
+    checked: bool,
+}
+
+impl FrameDecoder {
+    pub(crate) fn new(ggwave: &GGWave) -> Result<Self> {
+        let rx_frames = ggwave.max_rx_duration_frames();
+        if rx_frames == 0 {
+            return Err(Error::InvalidParameter("Instance cannot receive"));
+        }
+
+        let params = ggwave.parameters();
+        let frame_len = params.samplesPerFrame.max(1) as usize;
+        let sample_size = convert::sample_size(params.sampleFormatInp)?;
+
+        Ok(Self {
+            frame_len,
+            pending: Vec::with_capacity(frame_len),
+            frame_bytes: Vec::with_capacity(frame_len * sample_size),
+            payload: vec![0u8; constants::MAX_DATA_SIZE],
+            history: VecDeque::with_capacity(rx_frames * frame_len),
+            position: 0,
+            checked: false,
+        })
+    }
+
+    /// Forget all buffered samples, as at the start of a stream
+    fn clear(&mut self) {
+        self.pending.clear();
+        self.history.clear();
+        self.position = 0;
+    }
+
+    /// Add samples to the frame being accumulated, returning those that did
+    /// not fit
+    pub(crate) fn fill<'a>(&mut self, samples: &'a [f32]) -> &'a [f32] {
+        let take = (self.frame_len - self.pending.len()).min(samples.len());
+        self.pending.extend_from_slice(&samples[..take]);
+        &samples[take..]
+    }
+
+    pub(crate) fn is_frame_full(&self) -> bool {
+        self.pending.len() >= self.frame_len
+    }
+
+    /// Hand the accumulated frame to the decoder, returning the payload length
+    pub(crate) fn process_frame(&mut self, ggwave: &GGWave) -> Result<usize> {
+        let format = ggwave.parameters().sampleFormatInp;
+        convert::write_f32_bytes(&self.pending, format, &mut self.frame_bytes)?;
+        debug_assert_eq!(self.frame_bytes.len() % convert::sample_size(format)?, 0);
+
+        let capacity = self.history.capacity();
+        let overflow = (self.history.len() + self.pending.len()).saturating_sub(capacity);
+        self.history.drain(..overflow);
+        self.position += self.pending.len();
+        self.history.extend(self.pending.drain(..));
+
+        let received = match ggwave.process_audio_chunk_binary(&self.frame_bytes, &mut self.payload)
+        {
+            Ok(Some(payload)) => payload.len(),
+            Ok(None) | Err(Error::DecodeFailed(_)) => return Ok(0),
+            Err(e) => return Err(e),
+        };
+
+        // Noise decoded by chance almost never carries a valid checksum
+        let start = if self.checked {
+            if crate::checked_data(&self.payload[..received]).is_err() {
+                return Ok(0);
+            }
+            crate::CHECKSUM_LEN
+        } else {
+            0
+        };
+
+        if std::str::from_utf8(&self.payload[start..received]).is_err() {
+            return Ok(0);
+        }
+        self.payload.copy_within(start..received, 0);
+        Ok(received - start)
+    }
+
+    /// The text of a payload that was just decoded
+    #[cfg(feature = "audio")]
+    pub(crate) fn text(&self, length: usize) -> Result<&str> {
+        std::str::from_utf8(&self.payload[..length]).map_err(Error::Utf8Error)
+    }
+
+    /// Build the result for a payload that was just decoded
+    fn identify(&mut self, ggwave: &GGWave, length: usize) -> Result<DecodeResult> {
+        let text = std::str::from_utf8(&self.payload[..length]).map_err(Error::Utf8Error)?;
+
+        let history_start = self.position - self.history.len();
+        let history = self.history.make_contiguous();
+        let reception = ggwave.reception(history);
+        let samples = &history[reception.clone()];
+        // The checksum was part of the transmission
+        let sent = length + if self.checked { crate::CHECKSUM_LEN } else { 0 };
+        let protocol = ggwave.identify_protocol(samples, sent)?;
+
+        Ok(DecodeResult {
+            text: text.to_string(),
+            protocol,
+            received_volume: ggwave.received_volume(samples),
+            confidence: ggwave.confidence(samples, protocol),
+            clipped: signal::is_clipped(samples),
+            length,
+            offset: history_start + reception.start,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{protocols, sample_formats};
+
+    #[test]
+    fn test_stream_decoder() {
+        let _serial = crate::tests::serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let text = "Streamed";
+
+        let waveform = ggwave
+            .encode(text, protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text");
+        let mut samples = convert::bytes_to_f32(&waveform, sample_formats::F32).unwrap();
+        samples.resize(samples.len() + 48000, 0.0);
+
+        let mut decoder = StreamDecoder::new(ggwave).expect("Failed to create decoder");
+        let received: Vec<DecodeResult> = samples
+            .chunks(333)
+            .filter_map(|chunk| decoder.push(chunk).expect("Failed to push samples"))
+            .collect();
+
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].text, text);
+        assert_eq!(received[0].protocol, protocols::AUDIBLE_FAST);
+    }
+
+    #[test]
+    fn test_stream_decoder_reset() {
+        let _serial = crate::tests::serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+
+        let waveform = ggwave
+            .encode("Next session", protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text");
+        let samples = convert::bytes_to_f32(&waveform, sample_formats::F32).unwrap();
+        let frame_len = ggwave.parameters().samplesPerFrame as usize;
+        let silence = vec![0.0f32; ggwave.max_rx_duration_frames() * frame_len];
+
+        let mut decoder = StreamDecoder::new(ggwave).expect("Failed to create decoder");
+        decoder.push(&samples[..samples.len() / 2]).unwrap();
+        decoder.reset().expect("Failed to reset decoder");
+        assert!(decoder.push(&silence).unwrap().is_none());
+
+        let received = decoder
+            .push(&samples)
+            .unwrap()
+            .or_else(|| decoder.push(&silence).unwrap())
+            .expect("Message after reset was not decoded");
+        assert_eq!(received.text, "Next session");
+        // Counted from the reset, not from the start of the stream
+        assert!(received.offset.abs_diff(silence.len()) <= 512);
+    }
+
+    #[test]
+    fn test_decode_iter() {
+        let _serial = crate::tests::serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+        let text = "Iterated";
+
+        let waveform = ggwave
+            .encode(text, protocols::AUDIBLE_FAST, 50)
+            .expect("Failed to encode text");
+        let mut samples = vec![0.0f32; 4096];
+        samples.extend(convert::bytes_to_f32(&waveform, sample_formats::F32).unwrap());
+        samples.resize(samples.len() + 48000, 0.0);
+
+        let received: Vec<DecodeResult> = ggwave
+            .decode_iter(samples)
+            .collect::<Result<_>>()
+            .expect("Failed to decode samples");
+
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].text, text);
+        assert_eq!(received[0].protocol, protocols::AUDIBLE_FAST);
+    }
+
+    #[test]
+    fn test_stream_decoder_require_checksum() {
+        let _serial = crate::tests::serial();
+        let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+
+        // A payload without a checksum stands in for noise decoded by chance
+        let mut samples = vec![0.0f32; 4096];
+        for waveform in [
+            ggwave.encode("Noise", protocols::AUDIBLE_FAST, 50),
+            ggwave.encode_checked(b"Certified", protocols::AUDIBLE_FAST, 50),
+        ] {
+            let waveform = waveform.expect("Failed to encode");
+            samples.extend(convert::bytes_to_f32(&waveform, sample_formats::F32).unwrap());
+            samples.resize(samples.len() + 4096, 0.0);
+        }
+        samples.resize(samples.len() + 48000, 0.0);
+
+        let mut decoder = StreamDecoder::new(ggwave)
+            .expect("Failed to create decoder")
+            .require_checksum(true);
+        let received: Vec<DecodeResult> = samples
+            .chunks(333)
+            .filter_map(|chunk| decoder.push(chunk).expect("Failed to push samples"))
+            .collect();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].text, "Certified");
+        assert_eq!(received[0].length, "Certified".len());
+
+        let ggwave = decoder.into_inner();
+        let texts: Vec<String> = ggwave
+            .decode_iter(samples)
+            .require_checksum(true)
+            .map(|message| message.map(|m| m.text))
+            .collect::<Result<_>>()
+            .expect("Failed to decode samples");
+        assert_eq!(texts, ["Certified"]);
+    }
+}
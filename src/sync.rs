@@ -0,0 +1,121 @@
+//! Reader-writer lock guarding the global state of the C library
+//!
+//! With the `std` feature this wraps `std::sync::RwLock`. Without it, a
+//! minimal spinning lock stands in; the critical sections are single FFI
+//! calls, so readers and writers only ever wait briefly.
+
+#[cfg(feature = "std")]
+mod imp {
+    use std::sync::RwLock;
+
+    pub(crate) type ReadGuard = std::sync::RwLockReadGuard<'static, ()>;
+    pub(crate) type WriteGuard = std::sync::RwLockWriteGuard<'static, ()>;
+
+    pub(crate) struct GlobalLock(RwLock<()>);
+
+    impl GlobalLock {
+        pub(crate) const fn new() -> Self {
+            Self(RwLock::new(()))
+        }
+
+        pub(crate) fn read(&'static self) -> ReadGuard {
+            // The lock protects no data, so a panic while holding it leaves
+            // nothing inconsistent behind
+            self.0
+                .read()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+        }
+
+        pub(crate) fn write(&'static self) -> WriteGuard {
+            self.0
+                .write()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+        }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+mod imp {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    /// State of a lock held by a writer; otherwise the state counts readers
+    const WRITER: usize = usize::MAX;
+
+    pub(crate) struct GlobalLock(AtomicUsize);
+
+    pub(crate) struct ReadGuard(&'static AtomicUsize);
+
+    pub(crate) struct WriteGuard(&'static AtomicUsize);
+
+    impl GlobalLock {
+        pub(crate) const fn new() -> Self {
+            Self(AtomicUsize::new(0))
+        }
+
+        pub(crate) fn read(&'static self) -> ReadGuard {
+            let mut state = self.0.load(Ordering::Relaxed);
+            loop {
+                // Held by a writer, or so many readers that one more would
+                // look like a writer
+                if state >= WRITER - 1 {
+                    core::hint::spin_loop();
+                    state = self.0.load(Ordering::Relaxed);
+                    continue;
+                }
+
+                match self.0.compare_exchange_weak(
+                    state,
+                    state + 1,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => return ReadGuard(&self.0),
+                    Err(current) => state = current,
+                }
+            }
+        }
+
+        pub(crate) fn write(&'static self) -> WriteGuard {
+            while self
+                .0
+                .compare_exchange_weak(0, WRITER, Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+            {
+                core::hint::spin_loop();
+            }
+            WriteGuard(&self.0)
+        }
+    }
+
+    impl Drop for ReadGuard {
+        fn drop(&mut self) {
+            self.0.fetch_sub(1, Ordering::Release);
+        }
+    }
+
+    impl Drop for WriteGuard {
+        fn drop(&mut self) {
+            self.0.store(0, Ordering::Release);
+        }
+    }
+}
+
+pub(crate) use imp::{GlobalLock, ReadGuard, WriteGuard};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_global_lock() {
+        static LOCK: GlobalLock = GlobalLock::new();
+
+        let first = LOCK.read();
+        let second = LOCK.read();
+        drop((first, second));
+
+        let writer = LOCK.write();
+        drop(writer);
+        let _reader = LOCK.read();
+    }
+}
@@ -0,0 +1,292 @@
+//! Text-safe encodings of the transmitted payload
+//!
+//! ggwave carries arbitrary bytes, but a receiver that passes decoded
+//! messages through a text-only pipeline can mangle anything that is not
+//! printable. A [`TransportEncoding`] other than `Raw` turns the payload into
+//! ASCII before it is encoded and back after it is decoded, at the cost of
+//! payload capacity.
+
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+
+use crate::{Error, Result};
+
+/// Characters of the standard base64 alphabet
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Characters used for hex digits
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// How the payload is represented on the air
+///
+/// Set with [`GGWaveBuilder::transport_encoding`](crate::GGWaveBuilder::transport_encoding).
+/// Sender and receiver must use the same encoding. A receiver decoding with
+/// the wrong one gets the encoded text, or `Error::DecodeFailed(-1)` if it is
+/// not valid for its encoding.
+///
+/// # Examples
+///
+/// ```
+/// use ggwave_rs::{GGWave, TransportEncoding};
+///
+/// let ggwave = GGWave::builder()
+///     .transport_encoding(TransportEncoding::Base64)
+///     .build()
+///     .expect("Failed to initialize GGWave");
+/// assert_eq!(ggwave.max_payload_len(), 105);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TransportEncoding {
+    /// Send the payload bytes as they are
+    #[default]
+    Raw,
+    /// Send the payload as padded standard base64, 4 characters per 3 bytes
+    Base64,
+    /// Send the payload as lowercase hex, 2 characters per byte
+    Hex,
+}
+
+impl TransportEncoding {
+    /// Number of bytes transmitted for a payload of `len` bytes
+    pub const fn encoded_len(self, len: usize) -> usize {
+        match self {
+            Self::Raw => len,
+            Self::Base64 => len.div_ceil(3) * 4,
+            Self::Hex => len * 2,
+        }
+    }
+
+    /// Largest payload whose encoded form fits in `capacity` bytes
+    pub const fn max_payload_len(self, capacity: usize) -> usize {
+        match self {
+            Self::Raw => capacity,
+            Self::Base64 => capacity / 4 * 3,
+            Self::Hex => capacity / 2,
+        }
+    }
+
+    /// Encode a payload for transmission
+    pub(crate) fn encode(self, payload: &[u8]) -> Cow<'_, [u8]> {
+        if self == Self::Raw {
+            return Cow::Borrowed(payload);
+        }
+
+        let mut encoded = Vec::with_capacity(self.encoded_len(payload.len()));
+        match self {
+            Self::Raw => {}
+            Self::Base64 => {
+                for chunk in payload.chunks(3) {
+                    let group = (chunk[0] as u32) << 16
+                        | (chunk.get(1).copied().unwrap_or(0) as u32) << 8
+                        | chunk.get(2).copied().unwrap_or(0) as u32;
+                    for i in 0..4 {
+                        if i <= chunk.len() {
+                            encoded.push(BASE64_ALPHABET[(group >> (18 - 6 * i)) as usize & 63]);
+                        } else {
+                            encoded.push(b'=');
+                        }
+                    }
+                }
+            }
+            Self::Hex => {
+                for &byte in payload {
+                    encoded.push(HEX_DIGITS[(byte >> 4) as usize]);
+                    encoded.push(HEX_DIGITS[(byte & 0x0F) as usize]);
+                }
+            }
+        }
+        Cow::Owned(encoded)
+    }
+
+    /// Decode a received payload in place
+    ///
+    /// Trailing zero bytes, which pad fixed-length payloads, are ignored.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the length of the decoded payload at the start of
+    /// `data`, or `Error::DecodeFailed(-1)` if `data` is not valid for this
+    /// encoding
+    pub(crate) fn decode_in_place(self, data: &mut [u8]) -> Result<usize> {
+        let len = data.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+        match self {
+            Self::Raw => Ok(data.len()),
+            Self::Base64 => {
+                if !len.is_multiple_of(4) {
+                    return Err(Error::DecodeFailed(-1));
+                }
+
+                let groups = len / 4;
+                let mut written = 0;
+                for index in 0..groups {
+                    // Decoded bytes never overtake the group being read
+                    let chars: [u8; 4] = data[index * 4..index * 4 + 4].try_into().unwrap();
+                    let padding = chars.iter().rev().take_while(|&&c| c == b'=').count();
+                    if padding > 2 || (padding > 0 && index + 1 < groups) {
+                        return Err(Error::DecodeFailed(-1));
+                    }
+
+                    let mut group = 0u32;
+                    for &c in &chars[..4 - padding] {
+                        group = group << 6 | base64_value(c).ok_or(Error::DecodeFailed(-1))? as u32;
+                    }
+                    group <<= 6 * padding;
+
+                    for i in 0..3 - padding {
+                        data[written] = (group >> (16 - 8 * i)) as u8;
+                        written += 1;
+                    }
+                }
+                Ok(written)
+            }
+            Self::Hex => {
+                if !len.is_multiple_of(2) {
+                    return Err(Error::DecodeFailed(-1));
+                }
+
+                for index in 0..len / 2 {
+                    let high = hex_value(data[index * 2]).ok_or(Error::DecodeFailed(-1))?;
+                    let low = hex_value(data[index * 2 + 1]).ok_or(Error::DecodeFailed(-1))?;
+                    data[index] = high << 4 | low;
+                }
+                Ok(len / 2)
+            }
+        }
+    }
+}
+
+/// Value of a base64 character, or `None` if it is not in the alphabet
+fn base64_value(c: u8) -> Option<u8> {
+    match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+/// Value of a hex digit of either case, or `None` if it is not one
+fn hex_value(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GGWave, operating_modes, protocols};
+
+    const ENCODINGS: [TransportEncoding; 3] = [
+        TransportEncoding::Raw,
+        TransportEncoding::Base64,
+        TransportEncoding::Hex,
+    ];
+
+    #[test]
+    fn test_transport_encoding_round_trip() {
+        let _serial = crate::tests::serial();
+        let payload = [0x00, 0xFF, 0xFE, 0x80, 0x01, 0x7F, 0x0A];
+        let mut buffer = vec![0u8; 1024];
+
+        for encoding in ENCODINGS {
+            let ggwave = GGWave::builder()
+                .transport_encoding(encoding)
+                .build()
+                .expect("Failed to initialize GGWave");
+            assert_eq!(ggwave.transport_encoding(), encoding);
+
+            let waveform = ggwave
+                .encode_payload(&payload, protocols::AUDIBLE_FAST, 50)
+                .unwrap_or_else(|e| panic!("Failed to encode with {:?}: {}", encoding, e));
+            assert_eq!(
+                ggwave.decode_binary(&waveform, &mut buffer).ok(),
+                Some(&payload[..]),
+                "{:?}",
+                encoding
+            );
+        }
+    }
+
+    #[test]
+    fn test_transport_encoding_fixed_length() {
+        let _serial = crate::tests::serial();
+        let payload = [0xDE, 0xAD, 0x00, 0xEF];
+        let mut buffer = vec![0u8; 64];
+
+        for encoding in [TransportEncoding::Base64, TransportEncoding::Hex] {
+            let ggwave = GGWave::builder()
+                .operating_mode(operating_modes::RX_AND_TX)
+                .fixed_payload_length(16)
+                .transport_encoding(encoding)
+                .build()
+                .expect("Failed to initialize GGWave");
+            assert_eq!(ggwave.max_payload_len(), encoding.max_payload_len(16));
+
+            let waveform = ggwave
+                .encode_payload(&payload, protocols::AUDIBLE_FAST, 50)
+                .expect("Failed to encode payload");
+            assert_eq!(
+                ggwave.decode_binary(&waveform, &mut buffer).ok(),
+                Some(&payload[..]),
+                "{:?}",
+                encoding
+            );
+        }
+    }
+
+    #[test]
+    fn test_transport_encoding_capacity() {
+        let _serial = crate::tests::serial();
+        let ggwave = GGWave::builder()
+            .transport_encoding(TransportEncoding::Base64)
+            .build()
+            .expect("Failed to initialize GGWave");
+        let max = ggwave.max_payload_len();
+        assert_eq!(max, 105);
+
+        let text = "x".repeat(max);
+        assert!(ggwave.encode(&text, protocols::AUDIBLE_FAST, 50).is_ok());
+        let text = "x".repeat(max + 1);
+        assert!(matches!(
+            ggwave.encode(&text, protocols::AUDIBLE_FAST, 50),
+            Err(Error::TextTooLong {
+                length: 106,
+                max: 105
+            })
+        ));
+    }
+
+    #[test]
+    fn test_encode_decode_in_place() {
+        for encoding in ENCODINGS {
+            for len in 0..8 {
+                let payload: Vec<u8> = (0..len).map(|i| (i * 37 + 200) as u8).collect();
+                let mut encoded = encoding.encode(&payload).into_owned();
+                assert_eq!(encoded.len(), encoding.encoded_len(len));
+
+                let decoded = encoding.decode_in_place(&mut encoded).unwrap();
+                assert_eq!(&encoded[..decoded], &payload[..], "{:?}", encoding);
+            }
+        }
+
+        assert_eq!(&*TransportEncoding::Base64.encode(b"ggwave"), b"Z2d3YXZl");
+        assert_eq!(&*TransportEncoding::Hex.encode(b"\x01\xAB"), b"01ab");
+        assert!(
+            TransportEncoding::Base64
+                .decode_in_place(&mut b"Z2=3".to_vec())
+                .is_err()
+        );
+        assert!(
+            TransportEncoding::Hex
+                .decode_in_place(&mut b"0g".to_vec())
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,108 @@
+//! Validated transmission volume
+//!
+//! The `i32` volume taken by most encoding methods is only checked when the
+//! waveform is encoded. A [`Volume`] is checked when it is constructed, so an
+//! encoding method taking one cannot fail because of its volume.
+
+use crate::{Error, ffi::constants};
+
+/// A transmission volume between 0 and 100
+///
+/// # Examples
+///
+/// ```
+/// use ggwave_rs::{GGWave, Volume, protocols};
+///
+/// let quiet = Volume::new(20).expect("Volume must be 0-100");
+/// assert!(Volume::new(150).is_none());
+/// assert_eq!(Volume::clamped(150), Volume::MAX);
+///
+/// let ggwave = GGWave::new().expect("Failed to initialize GGWave");
+/// let waveform = ggwave.encode_v("Hello, World!", protocols::AUDIBLE_FAST, quiet)
+///     .expect("Failed to encode text");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Volume(u8);
+
+impl Volume {
+    /// Silence
+    pub const MIN: Self = Self(constants::MIN_VOLUME as u8);
+
+    /// Full volume
+    pub const MAX: Self = Self(constants::MAX_VOLUME as u8);
+
+    /// The volume used when none is given
+    pub const DEFAULT: Self = Self(constants::DEFAULT_VOLUME as u8);
+
+    /// Create a volume, or `None` if `volume` is above 100
+    pub const fn new(volume: u8) -> Option<Self> {
+        if volume <= Self::MAX.0 {
+            Some(Self(volume))
+        } else {
+            None
+        }
+    }
+
+    /// Create a volume, clamping `volume` to 0-100
+    pub const fn clamped(volume: i32) -> Self {
+        if volume < constants::MIN_VOLUME {
+            Self::MIN
+        } else if volume > constants::MAX_VOLUME {
+            Self::MAX
+        } else {
+            Self(volume as u8)
+        }
+    }
+
+    /// The volume as a number between 0 and 100
+    pub const fn get(self) -> u8 {
+        self.0
+    }
+}
+
+impl Default for Volume {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl From<Volume> for i32 {
+    fn from(volume: Volume) -> Self {
+        volume.0 as i32
+    }
+}
+
+impl TryFrom<i32> for Volume {
+    type Error = Error;
+
+    fn try_from(volume: i32) -> Result<Self, Self::Error> {
+        u8::try_from(volume)
+            .ok()
+            .and_then(Self::new)
+            .ok_or(Error::InvalidParameter("Volume must be 0-100"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_volume_range() {
+        assert_eq!(Volume::new(0), Some(Volume::MIN));
+        assert_eq!(Volume::new(100), Some(Volume::MAX));
+        assert_eq!(Volume::new(101), None);
+        assert_eq!(Volume::default().get(), 50);
+
+        assert_eq!(Volume::clamped(-5), Volume::MIN);
+        assert_eq!(Volume::clamped(42).get(), 42);
+        assert_eq!(Volume::clamped(500), Volume::MAX);
+
+        assert_eq!(Volume::try_from(75).map(i32::from).ok(), Some(75));
+        assert!(matches!(
+            Volume::try_from(-1),
+            Err(Error::InvalidParameter(_))
+        ));
+        assert!(Volume::try_from(256).is_err());
+    }
+}